@@ -0,0 +1,9 @@
+-- Add down migration script here
+DELETE FROM level_roles WHERE id IN (
+    787443819024220210,
+    787445571539304510,
+    787445900992577556,
+    787446715057831976,
+    787447090728796191,
+    787447252783202326
+);
@@ -0,0 +1,9 @@
+-- Add up migration script here
+INSERT INTO level_roles (id, guild_id, level) VALUES
+    (787443819024220210, 745662812335898806, 5),
+    (787445571539304510, 745662812335898806, 10),
+    (787445900992577556, 745662812335898806, 20),
+    (787446715057831976, 745662812335898806, 40),
+    (787447090728796191, 745662812335898806, 60),
+    (787447252783202326, 745662812335898806, 80)
+ON CONFLICT (id) DO NOTHING;
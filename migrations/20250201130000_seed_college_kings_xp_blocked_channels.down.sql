@@ -0,0 +1,4 @@
+-- Add down migration script here
+UPDATE guilds
+SET xp_blocked_channels = array_remove(xp_blocked_channels, 776139754408247326)
+WHERE id = 745662812335898806;
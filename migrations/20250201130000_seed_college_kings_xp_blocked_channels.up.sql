@@ -0,0 +1,6 @@
+-- Add up migration script here
+INSERT INTO guilds (id, xp_blocked_channels)
+VALUES (745662812335898806, '{776139754408247326}')
+ON CONFLICT (id) DO UPDATE
+SET xp_blocked_channels = array_append(guilds.xp_blocked_channels, 776139754408247326)
+WHERE NOT 776139754408247326 = ANY(guilds.xp_blocked_channels);
@@ -0,0 +1,8 @@
+-- Add down migration script here
+DELETE FROM levels WHERE guild_id <> 745662812335898806;
+
+ALTER TABLE levels DROP CONSTRAINT levels_pkey;
+ALTER TABLE levels DROP COLUMN guild_id;
+
+ALTER TABLE levels RENAME COLUMN user_id TO id;
+ALTER TABLE levels ADD PRIMARY KEY (id);
@@ -0,0 +1,10 @@
+-- Add up migration script here
+ALTER TABLE levels RENAME COLUMN id TO user_id;
+
+ALTER TABLE levels
+ADD COLUMN guild_id BIGINT NOT NULL DEFAULT 745662812335898806;
+
+ALTER TABLE levels ALTER COLUMN guild_id DROP DEFAULT;
+
+ALTER TABLE levels DROP CONSTRAINT levels_pkey;
+ALTER TABLE levels ADD PRIMARY KEY (guild_id, user_id);
@@ -0,0 +1,8 @@
+-- Add down migration script here
+ALTER TABLE guilds
+DROP COLUMN xp_curve_a,
+DROP COLUMN xp_curve_b,
+DROP COLUMN xp_curve_c,
+DROP COLUMN xp_min,
+DROP COLUMN xp_max,
+DROP COLUMN xp_cooldown_secs;
@@ -0,0 +1,8 @@
+-- Add up migration script here
+ALTER TABLE guilds
+ADD COLUMN xp_curve_a INT NOT NULL DEFAULT 5,
+ADD COLUMN xp_curve_b INT NOT NULL DEFAULT 50,
+ADD COLUMN xp_curve_c INT NOT NULL DEFAULT 100,
+ADD COLUMN xp_min INT NOT NULL DEFAULT 15,
+ADD COLUMN xp_max INT NOT NULL DEFAULT 24,
+ADD COLUMN xp_cooldown_secs INT NOT NULL DEFAULT 60;
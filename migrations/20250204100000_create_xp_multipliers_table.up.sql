@@ -0,0 +1,10 @@
+-- Add up migration script here
+CREATE TABLE xp_multipliers (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    kind TEXT NOT NULL,
+    target_id BIGINT,
+    multiplier REAL NOT NULL,
+    starts_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP
+);
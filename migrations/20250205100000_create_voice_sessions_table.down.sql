@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP TABLE voice_sessions;
+
+ALTER TABLE guilds
+DROP COLUMN xp_voice_per_minute;
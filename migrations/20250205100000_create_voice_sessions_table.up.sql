@@ -0,0 +1,14 @@
+-- Add up migration script here
+ALTER TABLE guilds
+ADD COLUMN xp_voice_per_minute INT NOT NULL DEFAULT 10;
+
+CREATE TABLE voice_sessions (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    channel_id BIGINT NOT NULL,
+    eligible BOOLEAN NOT NULL DEFAULT FALSE,
+    joined_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    last_accrued_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    checked_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (guild_id, user_id)
+);
@@ -0,0 +1,6 @@
+-- Add up migration script here
+CREATE TABLE rank_card_settings (
+    user_id BIGINT PRIMARY KEY,
+    background INT,
+    accent INT
+);
@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE levels
+ADD COLUMN username TEXT;
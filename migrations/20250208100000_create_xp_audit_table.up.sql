@@ -0,0 +1,13 @@
+-- Add up migration script here
+CREATE TABLE xp_audit (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    moderator_id BIGINT NOT NULL,
+    action TEXT NOT NULL,
+    amount INT,
+    old_total_xp INT NOT NULL,
+    new_total_xp INT NOT NULL,
+    reason TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE guilds
+DROP COLUMN level_up_mode,
+DROP COLUMN level_up_channel_id,
+DROP COLUMN level_up_template,
+DROP COLUMN level_up_milestones;
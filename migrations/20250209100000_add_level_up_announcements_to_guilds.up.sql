@@ -0,0 +1,6 @@
+-- Add up migration script here
+ALTER TABLE guilds
+ADD COLUMN level_up_mode TEXT NOT NULL DEFAULT 'off',
+ADD COLUMN level_up_channel_id BIGINT,
+ADD COLUMN level_up_template TEXT,
+ADD COLUMN level_up_milestones INT[] NOT NULL DEFAULT '{}';
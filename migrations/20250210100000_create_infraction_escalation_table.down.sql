@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP TABLE infraction_escalation;
+
+ALTER TABLE guilds
+DROP COLUMN infraction_decay_days;
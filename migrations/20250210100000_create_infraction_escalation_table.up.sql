@@ -0,0 +1,11 @@
+-- Add up migration script here
+CREATE TABLE infraction_escalation (
+    guild_id BIGINT NOT NULL,
+    points INT NOT NULL,
+    action TEXT NOT NULL,
+    duration_secs INT,
+    PRIMARY KEY (guild_id, points)
+);
+
+ALTER TABLE guilds
+ADD COLUMN infraction_decay_days INT NOT NULL DEFAULT 182;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP TABLE infraction_revisions;
+
+ALTER TABLE infractions
+DROP COLUMN voided;
@@ -0,0 +1,17 @@
+-- Add up migration script here
+ALTER TABLE infractions
+ADD COLUMN voided BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE infraction_revisions (
+    id SERIAL PRIMARY KEY,
+    infraction_id INT NOT NULL,
+    guild_id BIGINT NOT NULL,
+    moderator_id BIGINT NOT NULL,
+    moderator_username VARCHAR(255) NOT NULL,
+    action TEXT NOT NULL,
+    old_value TEXT,
+    new_value TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX infraction_revisions_infraction_id_idx ON infraction_revisions (infraction_id);
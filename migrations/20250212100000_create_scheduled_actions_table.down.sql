@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP TABLE scheduled_actions;
+
+ALTER TABLE guilds
+DROP COLUMN mute_role_id;
@@ -0,0 +1,16 @@
+-- Add up migration script here
+CREATE TABLE scheduled_actions (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    action TEXT NOT NULL,
+    role_id BIGINT,
+    execute_at TIMESTAMP NOT NULL,
+    moderator_id BIGINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX scheduled_actions_execute_at_idx ON scheduled_actions (execute_at);
+
+ALTER TABLE guilds
+ADD COLUMN mute_role_id BIGINT;
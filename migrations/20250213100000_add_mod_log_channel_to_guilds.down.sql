@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE guilds
+DROP COLUMN mod_log_channel_id;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE guilds
+ADD COLUMN mod_log_channel_id BIGINT;
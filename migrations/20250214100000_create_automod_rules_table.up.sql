@@ -0,0 +1,11 @@
+-- Add up migration script here
+CREATE TABLE automod_rules (
+    guild_id BIGINT NOT NULL,
+    rule TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    threshold INT NOT NULL,
+    window_secs INT NOT NULL,
+    action TEXT NOT NULL,
+    duration_secs INT,
+    PRIMARY KEY (guild_id, rule)
+);
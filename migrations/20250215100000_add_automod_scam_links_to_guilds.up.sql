@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE guilds
+ADD COLUMN automod_scam_links BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,7 @@
+-- Add down migration script here
+ALTER TABLE guilds
+DROP COLUMN filter_exempt_roles,
+DROP COLUMN filter_exempt_channels,
+DROP COLUMN filter_warn;
+
+DROP TABLE filter_patterns;
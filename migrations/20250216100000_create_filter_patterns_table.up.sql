@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TABLE filter_patterns (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    pattern TEXT NOT NULL,
+    mode TEXT NOT NULL,
+    moderator_id BIGINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (guild_id, pattern, mode)
+);
+
+ALTER TABLE guilds
+ADD COLUMN filter_exempt_roles BIGINT[] NOT NULL DEFAULT '{}',
+ADD COLUMN filter_exempt_channels BIGINT[] NOT NULL DEFAULT '{}',
+ADD COLUMN filter_warn BOOLEAN NOT NULL DEFAULT FALSE;
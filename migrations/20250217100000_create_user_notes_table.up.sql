@@ -0,0 +1,12 @@
+-- Add up migration script here
+CREATE TABLE user_notes (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    moderator_id BIGINT NOT NULL,
+    moderator_username TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX user_notes_guild_id_user_id_idx ON user_notes (guild_id, user_id);
@@ -0,0 +1,9 @@
+-- Add down migration script here
+ALTER TABLE guilds
+DROP COLUMN raid_enabled,
+DROP COLUMN raid_threshold,
+DROP COLUMN raid_window_secs,
+DROP COLUMN raid_action,
+DROP COLUMN raid_min_account_days,
+DROP COLUMN raid_quarantine_role,
+DROP COLUMN raid_lockdown_level;
@@ -0,0 +1,9 @@
+-- Add up migration script here
+ALTER TABLE guilds
+ADD COLUMN raid_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN raid_threshold INT NOT NULL DEFAULT 10,
+ADD COLUMN raid_window_secs INT NOT NULL DEFAULT 30,
+ADD COLUMN raid_action TEXT NOT NULL DEFAULT 'kick',
+ADD COLUMN raid_min_account_days INT NOT NULL DEFAULT 7,
+ADD COLUMN raid_quarantine_role BIGINT,
+ADD COLUMN raid_lockdown_level SMALLINT;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE guilds
+DROP COLUMN report_channel_id;
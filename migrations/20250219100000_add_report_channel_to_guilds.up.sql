@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE guilds
+ADD COLUMN report_channel_id BIGINT;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE scheduled_actions
+DROP COLUMN channel_id;
+
+DROP TABLE channel_locks;
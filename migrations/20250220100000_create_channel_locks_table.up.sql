@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TABLE channel_locks (
+    guild_id BIGINT NOT NULL,
+    channel_id BIGINT NOT NULL,
+    -- The @everyone overwrite before the lock. NULL when the channel had none.
+    allow BIGINT,
+    deny BIGINT,
+    guild_wide BOOLEAN NOT NULL DEFAULT FALSE,
+    moderator_id BIGINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (guild_id, channel_id)
+);
+
+ALTER TABLE scheduled_actions
+ADD COLUMN channel_id BIGINT;
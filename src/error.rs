@@ -11,6 +11,29 @@ pub enum Error {
     NegativeHours,
     CommandTimeout,
     PatreonTierTooLow,
+    MissingPermissions,
+    InvalidXpRange,
+    InvalidMultiplier,
+    InvalidColour,
+    MemberNotFound,
+    InvalidMilestones,
+    InvalidDuration,
+    CaseNotFound,
+    MuteRoleNotSet,
+    ScheduledActionNotFound,
+    InvalidDate,
+    InvalidFilterPattern,
+    FilterPatternNotFound,
+    FilterTooLarge,
+    NoteNotFound,
+    QuarantineRoleNotSet,
+    AlreadyLockedDown,
+    NotLockedDown,
+    InvalidMessageId,
+    ChannelAlreadyLocked,
+    ChannelNotLocked,
+    UnlockFailed,
+    Serenity(serenity::Error),
 
     GoldStar(gold_star::Error),
     ReactionRole(reaction_roles::Error),
@@ -27,6 +50,30 @@ impl ErrorResponse for Error {
             Error::NegativeHours => "Hours must be a positive number.",
             Error::CommandTimeout => "You have already used this command today.",
             Error::PatreonTierTooLow => "To access College Kings 2, you need to be an active $10 (Junior) patron with a lifetime subscription of $20.\nUse `/patreon_user login` to manually update the cache and link your Discord account.",
+            Error::MissingPermissions => "You do not have permission to use this command.",
+            Error::InvalidXpRange => "The minimum XP cannot be greater than the maximum XP.",
+            Error::InvalidMultiplier => "Role multipliers need a role, channel multipliers need a channel and event multipliers need a duration in hours.",
+            Error::InvalidColour => "Colours must be a hex code, such as #5865F2.",
+            Error::MemberNotFound => "That user is not a member of this server.",
+            Error::InvalidMilestones => "Milestones must be a comma separated list of levels, such as 5, 10, 25.",
+            Error::MuteRoleNotSet => "This server has no mute role. Set one with `/infraction policy mute-role`.",
+            Error::InvalidDate => "Dates must be written as YYYY-MM-DD, such as 2025-02-14.",
+            Error::InvalidFilterPattern => "That pattern is empty or isn't a valid regex.",
+            Error::FilterPatternNotFound => "No filtered pattern with that number exists in this server.",
+            Error::FilterTooLarge => "That pattern would make this server's filter too large. Remove or simplify some patterns first.",
+            Error::NoteNotFound => "No note with that number exists in this server.",
+            Error::QuarantineRoleNotSet => "Set a quarantine role with `/raid set` before using the quarantine action.",
+            Error::AlreadyLockedDown => "The server is already locked down.",
+            Error::NotLockedDown => "The server isn't locked down.",
+            Error::InvalidMessageId => "Message IDs are long numbers, such as 1340000000000000000. You can also paste a message link.",
+            Error::ChannelAlreadyLocked => "That channel is already locked.",
+            Error::ChannelNotLocked => "That channel isn't locked.",
+            Error::UnlockFailed => "Some channels couldn't be unlocked and are still locked. Check the bot's Manage Channels permission and try again.",
+            Error::ScheduledActionNotFound => "No pending action with that number exists in this server.",
+            Error::CaseNotFound => "No case with that number exists in this server.",
+            Error::InvalidDuration => "Durations look like 30m, 8h or 7d. Mutes need a duration of at most 28 days.",
+
+            Error::Serenity(_) => "Discord rejected that action. Check that the bot has the permissions it needs and that its role is above the member's.",
 
             Error::GoldStar(e) => e.to_response(),
             Error::ReactionRole(e) => e.to_response(),
@@ -44,6 +91,12 @@ impl std::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+impl From<serenity::Error> for Error {
+    fn from(e: serenity::Error) -> Self {
+        Error::Serenity(e)
+    }
+}
+
 impl From<gold_star::Error> for Error {
     fn from(e: gold_star::Error) -> Self {
         Error::GoldStar(e)
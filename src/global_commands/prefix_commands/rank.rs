@@ -1,12 +1,20 @@
-use serenity::all::{Context, Message};
+use serenity::all::{Context, CreateMessage, Message};
+use sqlx::PgPool;
 
-use crate::Result;
+use crate::modules::levels::rank_card::rank_card;
+use crate::{Error, Result};
+
+pub async fn run(ctx: &Context, msg: Message, pool: &PgPool) -> Result<()> {
+    let guild_id = msg.guild_id.ok_or(Error::MissingGuildId)?;
+
+    let user = msg.mentions.first().unwrap_or(&msg.author);
+
+    let card = rank_card(ctx, pool, guild_id, user).await?;
 
-pub async fn run(ctx: &Context, msg: Message) -> Result<()> {
     msg.channel_id
-        .say(
-            &ctx,
-            "Mee6 is being deprecated, please use the `/rank` command instead",
+        .send_message(
+            ctx,
+            CreateMessage::new().add_file(card).reference_message(&msg),
         )
         .await
         .unwrap();
@@ -1,14 +1,44 @@
 use async_trait::async_trait;
 use serenity::all::{
     CommandInteraction, CommandOptionType, Context, CreateCommand, CreateCommandOption,
-    CreateEmbed, CreateMessage, DiscordJsonError, EditInteractionResponse, ErrorResponse,
-    HttpError, Permissions, Ready, ResolvedOption, ResolvedValue,
+    EditInteractionResponse, GuildId, Permissions, Ready, ResolvedOption, ResolvedValue, User,
 };
 use sqlx::{PgPool, Postgres};
 use zayden_core::{parse_options, SlashCommand};
 
+use crate::modules::moderation::mod_log::response_link;
+use crate::modules::moderation::{apply_sanction, Sanction, SanctionOutcome, SanctionRequest};
 use crate::{Error, Result};
 
+pub const SCAM_REASON: &str = "Compromised account: Sending scam links.";
+
+/// Soft bans a compromised account to clear its recent messages. Compromised accounts aren't at
+/// fault, so the soft ban is recorded without points.
+pub async fn soft_ban_scammer(
+    ctx: &Context,
+    pool: &PgPool,
+    guild_id: GuildId,
+    user: &User,
+    moderator: &User,
+    reason: &str,
+    jump_url: Option<String>,
+) -> Result<SanctionOutcome> {
+    apply_sanction(
+        ctx,
+        pool,
+        SanctionRequest {
+            guild_id,
+            user,
+            moderator,
+            sanction: Sanction::SoftBan,
+            points: 0,
+            reason,
+            jump_url,
+        },
+    )
+    .await
+}
+
 pub struct Scam;
 
 #[async_trait]
@@ -17,7 +47,7 @@ impl SlashCommand<Error, Postgres> for Scam {
         ctx: &Context,
         interaction: &CommandInteraction,
         options: Vec<ResolvedOption<'_>>,
-        _pool: &PgPool,
+        pool: &PgPool,
     ) -> Result<()> {
         interaction.defer(&ctx).await.unwrap();
 
@@ -31,45 +61,20 @@ impl SlashCommand<Error, Postgres> for Scam {
 
         let reason = match options.remove("reason") {
             Some(ResolvedValue::String(reason)) => reason,
-            _ => "Compromised account: Sending scam links.",
+            _ => SCAM_REASON,
         };
 
-        let guild = guild_id.to_partial_guild(&ctx).await.unwrap();
-
-        match user
-            .create_dm_channel(&ctx)
-            .await
-            .unwrap()
-            .send_message(
-                &ctx,
-                CreateMessage::new().add_embed(CreateEmbed::new().description(format!(
-                    "You have been soft banned from {} for the following reason: {}",
-                    guild.name, reason
-                ))),
-            )
-            .await
-        {
-            // 50007: Cannot send messages to this user
-            Err(serenity::Error::Http(HttpError::UnsuccessfulRequest(ErrorResponse {
-                error: DiscordJsonError { code: 50007, .. },
-                ..
-            }))) => {}
-            result => {
-                result.unwrap();
-            }
-        }
-
-        guild_id
-            .ban_with_reason(&ctx, user, 1, reason)
-            .await
-            .unwrap();
-        guild_id.unban(&ctx, user).await.unwrap();
-
-        let embed = CreateEmbed::new().title("Soft Banned").description(format!(
-            "{} has been successfully soft banned for the following reason: {}",
-            user.display_name(),
-            reason
-        ));
+        let embed = soft_ban_scammer(
+            ctx,
+            pool,
+            guild_id,
+            user,
+            &interaction.user,
+            reason,
+            response_link(ctx, interaction).await,
+        )
+        .await?
+        .embed;
 
         interaction
             .edit_response(ctx, EditInteractionResponse::new().embed(embed))
@@ -0,0 +1,17 @@
+use serenity::all::{Context, GuildAuditLogEntryCreateEvent};
+use sqlx::PgPool;
+
+use crate::modules::moderation::mod_log;
+use crate::Result;
+
+use super::Handler;
+
+impl Handler {
+    pub(super) async fn guild_audit_log_entry_create(
+        ctx: &Context,
+        event: GuildAuditLogEntryCreateEvent,
+        pool: &PgPool,
+    ) -> Result<()> {
+        mod_log::audit_log_entry(ctx, pool, event).await
+    }
+}
@@ -0,0 +1,17 @@
+use serenity::all::{Context, Member};
+use sqlx::PgPool;
+
+use crate::modules::automod::RaidProtection;
+use crate::Result;
+
+use super::Handler;
+
+impl Handler {
+    pub(super) async fn guild_member_addition(
+        ctx: &Context,
+        member: Member,
+        pool: &PgPool,
+    ) -> Result<()> {
+        RaidProtection::member_join(ctx, pool, &member).await
+    }
+}
@@ -7,11 +7,15 @@ use crate::guild_commands::college_kings::{
 };
 use crate::guild_commands::college_kings_team::Review;
 use crate::handler::Handler;
+use crate::modules::automod::{AutoModCommand, FilterCommand, RaidCommand};
 use crate::modules::gold_star::slash_commands::{GiveStarCommand, StarsCommand};
 use crate::modules::levels::slash_commands::{Rank, Xp};
 use crate::modules::levels::Levels;
 use crate::modules::misc::{Link, Sleep};
-use crate::modules::moderation::{Infraction, Logs, RulesCommand};
+use crate::modules::moderation::{
+    Case, Dossier, Infraction, Lock, Logs, Note, Purge, ReportMessage, RulesCommand, Scheduled,
+    Slowmode, Unlock,
+};
 use crate::modules::patreon::Patreon;
 use crate::modules::reaction_roles::ReactionRoleCommand;
 use crate::modules::suggestions::FetchSuggestions;
@@ -74,9 +78,21 @@ impl Handler {
             //endregion: misc
 
             //region: moderation
+            "automod" => AutoModCommand::run(ctx, command, options, &pool),
+            "case" => Case::run(ctx, command, options, &pool),
+            "dossier" => Dossier::run(ctx, command, options, &pool),
+            "filter" => FilterCommand::run(ctx, command, options, &pool),
             "infraction" => Infraction::run(ctx, command, options, &pool),
+            "lock" => Lock::run(ctx, command, options, &pool),
             "logs" => Logs::run(ctx, command, options, &pool),
+            "note" => Note::run(ctx, command, options, &pool),
+            "purge" => Purge::run(ctx, command, options, &pool),
+            "raid" => RaidCommand::run(ctx, command, options, &pool),
+            "Report to moderators" => ReportMessage::run(ctx, command, options, &pool),
             "rules" => RulesCommand::run(ctx, command, options, &pool),
+            "scheduled" => Scheduled::run(ctx, command, options, &pool),
+            "slowmode" => Slowmode::run(ctx, command, options, &pool),
+            "unlock" => Unlock::run(ctx, command, options, &pool),
             //endregion: moderation
 
             //region: patreon
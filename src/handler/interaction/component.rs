@@ -6,7 +6,9 @@ use suggestions::Suggestions;
 use zayden_core::{Component, ErrorResponse};
 
 use crate::handler::Handler;
+use crate::modules::automod::{raid, scam};
 use crate::modules::levels::Levels;
+use crate::modules::moderation::report;
 use crate::modules::ticket::Ticket;
 use crate::{components, Result, SUPER_USERS};
 
@@ -26,7 +28,7 @@ impl Handler {
             "cron_unavailable" => components::availability_check(ctx, interaction, false).await,
             "faq" => components::faq(ctx, interaction, false).await,
             "faq_ephemeral" => components::faq(ctx, interaction, true).await,
-            "levels_previous" | "levels_user" | "levels_next" => {
+            custom_id if custom_id.starts_with("levels_") => {
                 Levels::run(ctx, interaction, pool).await
             }
             "production_request" => components::production_request(ctx, interaction).await,
@@ -53,6 +55,17 @@ impl Handler {
             "sleep_cancel" => Ok(()),
             //endregion: Misc
 
+            //region: Moderation
+            "logs_previous" | "logs_next" | "dossier_previous" | "dossier_next" => Ok(()),
+            "raid_end" => raid::end_button(ctx, interaction, pool).await,
+            custom_id if custom_id.starts_with("scam_undo_") => {
+                scam::undo(ctx, interaction, pool).await
+            }
+            custom_id if custom_id.starts_with("report_") => {
+                report::button(ctx, interaction, pool).await
+            }
+            //endregion: Moderation
+
             //region: Ticket
             "ticket_create" | "support_ticket" => Ticket::ticket_create(ctx, interaction).await,
             "support_close" => Ticket::support_close(ctx, interaction).await,
@@ -5,6 +5,8 @@ use ticket::TicketModal;
 
 use crate::handler::Handler;
 use crate::modals::{production_request, render_request};
+use crate::modules::levels::leaderboard;
+use crate::modules::moderation::report;
 use crate::modules::ticket::TicketTable;
 use crate::sqlx_lib::GuildTable;
 use crate::{Error, Result};
@@ -30,6 +32,12 @@ impl Handler {
             "suggestions_reject" => {
                 Suggestions::modal(ctx, modal, false).await;
             }
+            custom_id if custom_id.starts_with("levels_jump_") => {
+                leaderboard::jump_modal_submit(ctx, modal, pool).await?;
+            }
+            custom_id if custom_id.starts_with("report_modal_") => {
+                report::submit(ctx, modal, pool).await?;
+            }
             "create_ticket" => {
                 TicketModal::run::<Postgres, GuildTable, TicketTable>(ctx, modal, pool)
                     .await
@@ -4,6 +4,7 @@ use zayden_core::MessageCommand;
 
 use crate::global_commands::prefix_commands::{ping, rank};
 use crate::handler::Handler;
+use crate::modules::automod::AutoMod;
 use crate::modules::levels::Levels;
 use crate::modules::ticket::message_commands::support;
 use crate::Result;
@@ -14,11 +15,15 @@ impl Handler {
             return Ok(());
         }
 
+        if AutoMod::run(ctx, &msg, pool).await? {
+            return Ok(());
+        }
+
         let command = msg.content.split_whitespace().next().unwrap_or_default();
 
         match command.to_lowercase().as_str() {
             "!ping" => ping::run(ctx, msg).await?,
-            "!rank" => rank::run(ctx, msg).await?,
+            "!rank" => rank::run(ctx, msg, pool).await?,
             _ => {
                 tokio::try_join!(Levels::run(ctx, &msg, pool), support(ctx, &msg, pool))?;
             }
@@ -8,11 +8,14 @@ pub use ready::OnReady;
 use crate::sqlx_lib::PostgresPool;
 use crate::SUPER_USERS;
 
+mod guild_audit_log_entry_create;
+mod guild_member_addition;
 mod interaction;
 mod message;
 mod reaction_add;
 mod reaction_remove;
 mod ready;
+mod voice_state_update;
 
 pub struct Handler;
 
@@ -42,7 +45,16 @@ impl RawEventHandler for Handler {
             Event::ReactionRemove(reaction) => {
                 Self::reaction_remove(&ctx, reaction.reaction, &pool).await
             }
+            Event::GuildAuditLogEntryCreate(event) => {
+                Self::guild_audit_log_entry_create(&ctx, event, &pool).await
+            }
+            Event::GuildMemberAdd(event) => {
+                Self::guild_member_addition(&ctx, event.member, &pool).await
+            }
             Event::Ready(ready) => Self::ready(&ctx, ready.ready).await,
+            Event::VoiceStateUpdate(voice_state) => {
+                Self::voice_state_update(&ctx, voice_state.voice_state, &pool).await
+            }
             _ => Ok(()),
         };
 
@@ -7,7 +7,9 @@ use message_updates::update_messages;
 use crate::cron::start_cron_jobs;
 use crate::handler::Handler;
 use crate::modules;
+use crate::modules::levels::voice::VoiceXp;
 use crate::modules::misc::Sleep;
+use crate::modules::moderation::ScheduledActions;
 use crate::{global_commands, guilds, Result};
 
 mod message_updates;
@@ -38,7 +40,15 @@ impl Handler {
         update_messages(ctx).await?;
 
         let ctx_clone = ctx.clone();
-        tokio::spawn(async move { Sleep::on_ready(ctx_clone, ready).await });
+        let ready_clone = ready.clone();
+        tokio::spawn(async move { Sleep::on_ready(ctx_clone, ready_clone).await });
+
+        let ctx_clone = ctx.clone();
+        let ready_clone = ready.clone();
+        tokio::spawn(async move { VoiceXp::on_ready(ctx_clone, ready_clone).await });
+
+        let ctx_clone = ctx.clone();
+        tokio::spawn(async move { ScheduledActions::on_ready(ctx_clone, ready).await });
 
         let ctx_clone = ctx.clone();
         tokio::spawn(async move { start_cron_jobs(ctx_clone).await });
@@ -0,0 +1,21 @@
+use serenity::all::{Context, VoiceState};
+use sqlx::PgPool;
+
+use crate::modules::levels::voice;
+use crate::Result;
+
+use super::Handler;
+
+impl Handler {
+    pub(super) async fn voice_state_update(
+        ctx: &Context,
+        voice_state: VoiceState,
+        pool: &PgPool,
+    ) -> Result<()> {
+        let Some(guild_id) = voice_state.guild_id else {
+            return Ok(());
+        };
+
+        voice::sync_guild(ctx, pool, guild_id, Some(&voice_state)).await
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::env;
 
 use serenity::all::{ClientBuilder, GatewayIntents, UserId};
@@ -5,6 +6,8 @@ use serenity::prelude::TypeMap;
 
 pub use error::{Error, Result};
 use guild_commands::college_kings::greetings::GreetingLockedUsers;
+use modules::automod::AutoModState;
+use modules::levels::blocked_channels::XpBlockedChannels;
 use sqlx_lib::PostgresPool;
 
 use crate::image_cache::ImageCache;
@@ -36,6 +39,8 @@ async fn main() -> Result<()> {
     let mut type_map = TypeMap::new();
     type_map.insert::<ImageCache>(ImageCache::new());
     type_map.insert::<GreetingLockedUsers>(Vec::new());
+    type_map.insert::<XpBlockedChannels>(HashMap::new());
+    type_map.insert::<AutoModState>(AutoModState::default());
     type_map.insert::<PostgresPool>(pool);
 
     let token = &env::var("DISCORD_TOKEN").expect("Expected DISCORD_TOKEN in the environment");
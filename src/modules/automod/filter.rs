@@ -0,0 +1,881 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use regex::{RegexBuilder, RegexSet, RegexSetBuilder};
+use serenity::all::{
+    ChannelId, CommandInteraction, CommandOptionType, Context, CreateCommand, CreateCommandOption,
+    CreateEmbed, EditInteractionResponse, GuildId, Mentionable, Message, Permissions, Ready,
+    ResolvedOption, ResolvedValue, RoleId, User,
+};
+use sqlx::{PgPool, Postgres};
+use zayden_core::{parse_options, SlashCommand};
+
+use crate::modules::levels::blocked_channels::channel_ancestry;
+use crate::sqlx_lib::GuildTable;
+use crate::{Error, Result};
+
+use super::rules::AutoModAction;
+use super::{enforce, AutoModState};
+
+/// Keeps a single pattern, or a guild's whole list, from compiling into something huge.
+const SIZE_LIMIT: usize = 1 << 20;
+
+const LIST_LIMIT: usize = 50;
+
+/// Embed descriptions hold 4096 characters. The rest is room for the "…and N more" line.
+const DESCRIPTION_LIMIT: usize = 4000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterMode {
+    /// Matches the pattern as a whole word, so `ass` doesn't match `class`.
+    Word,
+    Substring,
+    Regex,
+}
+
+impl FilterMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Word => "word",
+            Self::Substring => "substring",
+            Self::Regex => "regex",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "word" => Some(Self::Word),
+            "substring" => Some(Self::Substring),
+            "regex" => Some(Self::Regex),
+            _ => None,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Word => "Word",
+            Self::Substring => "Substring",
+            Self::Regex => "Regex",
+        }
+    }
+
+    fn to_regex(self, pattern: &str) -> String {
+        match self {
+            // `\b` needs a word character on one side, which patterns like `f*ck!` don't have.
+            Self::Word => format!(r"(?:^|\W){}(?:\W|$)", regex::escape(pattern)),
+            Self::Substring => regex::escape(pattern),
+            Self::Regex => pattern.to_string(),
+        }
+    }
+}
+
+/// Checks that the pattern compiles before it's saved.
+pub fn validate(mode: FilterMode, pattern: &str) -> Result<()> {
+    if pattern.trim().is_empty() {
+        return Err(Error::InvalidFilterPattern);
+    }
+
+    RegexBuilder::new(&mode.to_regex(pattern))
+        .size_limit(SIZE_LIMIT)
+        .build()
+        .map_err(|_| Error::InvalidFilterPattern)?;
+
+    Ok(())
+}
+
+/// A guild's patterns compiled into one matcher. Matching is case insensitive in every mode.
+#[derive(Debug, Clone)]
+pub struct WordFilter {
+    ids: Vec<i32>,
+    set: RegexSet,
+}
+
+impl Default for WordFilter {
+    fn default() -> Self {
+        Self {
+            ids: Vec::new(),
+            set: RegexSet::empty(),
+        }
+    }
+}
+
+fn build_set(regexes: &[String]) -> Option<RegexSet> {
+    RegexSetBuilder::new(regexes)
+        .case_insensitive(true)
+        .size_limit(SIZE_LIMIT * 8)
+        .build()
+        .ok()
+}
+
+impl WordFilter {
+    /// Patterns that no longer compile, or that would push the set over its size limit, are
+    /// skipped rather than disabling the whole filter. Older patterns win.
+    pub fn compile<'a>(patterns: impl IntoIterator<Item = (i32, FilterMode, &'a str)>) -> Self {
+        let (ids, regexes): (Vec<_>, Vec<_>) = patterns
+            .into_iter()
+            .filter(|(_, mode, pattern)| validate(*mode, pattern).is_ok())
+            .map(|(id, mode, pattern)| (id, mode.to_regex(pattern)))
+            .unzip();
+
+        if let Some(set) = build_set(&regexes) {
+            return Self { ids, set };
+        }
+
+        let mut kept_ids = Vec::new();
+        let mut kept = Vec::new();
+
+        for (id, regex) in ids.into_iter().zip(regexes) {
+            kept.push(regex);
+
+            if build_set(&kept).is_some() {
+                kept_ids.push(id);
+            } else {
+                kept.pop();
+            }
+        }
+
+        Self {
+            ids: kept_ids,
+            set: build_set(&kept).unwrap_or_else(RegexSet::empty),
+        }
+    }
+
+    /// Whether adding the pattern keeps the set within its size limit.
+    pub fn fits<'a>(
+        patterns: impl IntoIterator<Item = (FilterMode, &'a str)>,
+        mode: FilterMode,
+        pattern: &str,
+    ) -> bool {
+        let mut regexes = patterns
+            .into_iter()
+            .filter(|(mode, pattern)| validate(*mode, pattern).is_ok())
+            .map(|(mode, pattern)| mode.to_regex(pattern))
+            .collect::<Vec<_>>();
+        regexes.push(mode.to_regex(pattern));
+
+        build_set(&regexes).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// The ids of every pattern found in the content.
+    pub fn matches(&self, content: &str) -> Vec<i32> {
+        self.set
+            .matches(content)
+            .into_iter()
+            .map(|index| self.ids[index])
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FilterSettings {
+    pub exempt_roles: Vec<RoleId>,
+    pub exempt_channels: Vec<ChannelId>,
+    /// Record a warn infraction as well as deleting the message.
+    pub warn: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GuildFilter {
+    pub filter: WordFilter,
+    pub settings: FilterSettings,
+}
+
+impl GuildFilter {
+    pub async fn get(ctx: &Context, pool: &PgPool, guild_id: GuildId) -> Result<Self> {
+        {
+            let data = ctx.data.read().await;
+            if let Some(filter) = data
+                .get::<AutoModState>()
+                .and_then(|state| state.filters.get(&guild_id))
+            {
+                return Ok(filter.clone());
+            }
+        }
+
+        let rows = FilterPatternsTable::get_guild_rows(pool, guild_id).await?;
+        let filter = Self {
+            filter: compile_rows(&rows),
+            settings: GuildTable::get_filter_settings(pool, guild_id).await?,
+        };
+
+        let mut data = ctx.data.write().await;
+        if let Some(state) = data.get_mut::<AutoModState>() {
+            state.filters.insert(guild_id, filter.clone());
+        }
+
+        Ok(filter)
+    }
+
+    async fn invalidate(ctx: &Context, guild_id: GuildId) {
+        let mut data = ctx.data.write().await;
+        if let Some(state) = data.get_mut::<AutoModState>() {
+            state.filters.remove(&guild_id);
+        }
+    }
+}
+
+fn compile_rows(rows: &[FilterPatternRow]) -> WordFilter {
+    WordFilter::compile(rows.iter().filter_map(|row| {
+        Some((
+            row.id,
+            FilterMode::from_name(&row.mode)?,
+            row.pattern.as_str(),
+        ))
+    }))
+}
+
+#[allow(dead_code)]
+pub struct FilterPatternRow {
+    pub id: i32,
+    pub guild_id: i64,
+    pub pattern: String,
+    pub mode: String,
+    pub moderator_id: i64,
+    pub created_at: NaiveDateTime,
+}
+
+pub struct FilterPatternsTable;
+
+impl FilterPatternsTable {
+    pub async fn get_guild_rows(pool: &PgPool, guild_id: GuildId) -> Result<Vec<FilterPatternRow>> {
+        let rows = sqlx::query_as!(
+            FilterPatternRow,
+            "SELECT * FROM filter_patterns WHERE guild_id = $1 ORDER BY id",
+            guild_id.get() as i64
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        Ok(rows)
+    }
+
+    /// Adds the pattern, returning its id. Adding a pattern twice returns the existing id.
+    pub async fn insert(
+        pool: &PgPool,
+        guild_id: GuildId,
+        pattern: &str,
+        mode: FilterMode,
+        moderator: &User,
+    ) -> Result<i32> {
+        let row = sqlx::query!(
+            "INSERT INTO filter_patterns (guild_id, pattern, mode, moderator_id) VALUES ($1, $2, $3, $4)
+             ON CONFLICT (guild_id, pattern, mode) DO UPDATE SET pattern = EXCLUDED.pattern
+             RETURNING id",
+            guild_id.get() as i64,
+            pattern,
+            mode.name(),
+            moderator.id.get() as i64
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        Ok(row.id)
+    }
+
+    pub async fn delete(
+        pool: &PgPool,
+        guild_id: GuildId,
+        id: i32,
+    ) -> Result<Option<FilterPatternRow>> {
+        let row = sqlx::query_as!(
+            FilterPatternRow,
+            "DELETE FROM filter_patterns WHERE guild_id = $1 AND id = $2 RETURNING *",
+            guild_id.get() as i64,
+            id
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        Ok(row)
+    }
+}
+
+impl GuildTable {
+    pub async fn get_filter_settings(pool: &PgPool, id: GuildId) -> Result<FilterSettings> {
+        let row = sqlx::query!(
+            "SELECT filter_exempt_roles, filter_exempt_channels, filter_warn FROM guilds WHERE id = $1",
+            id.get() as i64
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        let settings = row
+            .map(|row| FilterSettings {
+                exempt_roles: row
+                    .filter_exempt_roles
+                    .into_iter()
+                    .map(|id| RoleId::new(id as u64))
+                    .collect(),
+                exempt_channels: row
+                    .filter_exempt_channels
+                    .into_iter()
+                    .map(|id| ChannelId::new(id as u64))
+                    .collect(),
+                warn: row.filter_warn,
+            })
+            .unwrap_or_default();
+
+        Ok(settings)
+    }
+
+    pub async fn save_filter_exempt_role(
+        pool: &PgPool,
+        id: GuildId,
+        role_id: RoleId,
+        exempt: bool,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO guilds (id, filter_exempt_roles) VALUES ($1, CASE WHEN $3 THEN ARRAY[$2::BIGINT] ELSE '{}' END)
+             ON CONFLICT (id) DO UPDATE
+             SET filter_exempt_roles = CASE WHEN $3
+                THEN array_append(array_remove(guilds.filter_exempt_roles, $2), $2)
+                ELSE array_remove(guilds.filter_exempt_roles, $2) END",
+            id.get() as i64,
+            role_id.get() as i64,
+            exempt
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+
+    pub async fn save_filter_exempt_channel(
+        pool: &PgPool,
+        id: GuildId,
+        channel_id: ChannelId,
+        exempt: bool,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO guilds (id, filter_exempt_channels) VALUES ($1, CASE WHEN $3 THEN ARRAY[$2::BIGINT] ELSE '{}' END)
+             ON CONFLICT (id) DO UPDATE
+             SET filter_exempt_channels = CASE WHEN $3
+                THEN array_append(array_remove(guilds.filter_exempt_channels, $2), $2)
+                ELSE array_remove(guilds.filter_exempt_channels, $2) END",
+            id.get() as i64,
+            channel_id.get() as i64,
+            exempt
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+
+    pub async fn save_filter_warn(pool: &PgPool, id: GuildId, warn: bool) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO guilds (id, filter_warn) VALUES ($1, $2)
+             ON CONFLICT (id) DO UPDATE SET filter_warn = $2",
+            id.get() as i64,
+            warn
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+}
+
+/// Deletes messages containing a filtered pattern, warning the author if the guild asks for it.
+/// Returns true if the message was removed.
+pub async fn run(ctx: &Context, msg: &Message, pool: &PgPool, guild_id: GuildId) -> Result<bool> {
+    let GuildFilter { filter, settings } = GuildFilter::get(ctx, pool, guild_id).await?;
+
+    if filter.is_empty() {
+        return Ok(false);
+    }
+
+    if msg.member.as_ref().is_some_and(|member| {
+        member
+            .roles
+            .iter()
+            .any(|role_id| settings.exempt_roles.contains(role_id))
+    }) {
+        return Ok(false);
+    }
+
+    if !settings.exempt_channels.is_empty()
+        && channel_ancestry(ctx, guild_id, msg.channel_id)
+            .await
+            .iter()
+            .any(|channel_id| settings.exempt_channels.contains(channel_id))
+    {
+        return Ok(false);
+    }
+
+    let Some(id) = filter.matches(&msg.content).first().copied() else {
+        return Ok(false);
+    };
+
+    let action = if settings.warn {
+        AutoModAction::Warn
+    } else {
+        AutoModAction::Delete
+    };
+
+    enforce(
+        ctx,
+        pool,
+        msg,
+        guild_id,
+        action,
+        &format!("Automod: Filtered word (pattern #{})", id),
+    )
+    .await?;
+
+    Ok(true)
+}
+
+fn describe(row: &FilterPatternRow) -> String {
+    let mode = FilterMode::from_name(&row.mode).map_or(row.mode.as_str(), |mode| mode.label());
+
+    format!(
+        "`#{}` **{}** `{}`",
+        row.id,
+        mode,
+        row.pattern.replace('`', "'")
+    )
+}
+
+pub struct FilterCommand;
+
+#[async_trait]
+impl SlashCommand<Error, Postgres> for FilterCommand {
+    async fn run(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        mut options: Vec<ResolvedOption<'_>>,
+        pool: &PgPool,
+    ) -> Result<()> {
+        interaction.defer_ephemeral(ctx).await.unwrap();
+
+        let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+        let command = options.remove(0);
+        let ResolvedValue::SubCommand(options) = command.value else {
+            unreachable!("Subcommand is required");
+        };
+        let mut options = parse_options(options);
+
+        let response = match command.name {
+            "add" => {
+                let Some(ResolvedValue::String(pattern)) = options.remove("pattern") else {
+                    unreachable!("Pattern option is required");
+                };
+
+                let mode = match options.remove("mode") {
+                    Some(ResolvedValue::String(mode)) => {
+                        FilterMode::from_name(mode).expect("Unknown mode")
+                    }
+                    _ => FilterMode::Word,
+                };
+
+                validate(mode, pattern)?;
+
+                let rows = FilterPatternsTable::get_guild_rows(pool, guild_id).await?;
+                let existing = rows.iter().filter_map(|row| {
+                    Some((FilterMode::from_name(&row.mode)?, row.pattern.as_str()))
+                });
+                if !WordFilter::fits(existing, mode, pattern) {
+                    return Err(Error::FilterTooLarge);
+                }
+
+                let id =
+                    FilterPatternsTable::insert(pool, guild_id, pattern, mode, &interaction.user)
+                        .await?;
+
+                EditInteractionResponse::new().content(format!(
+                    "Added pattern #{} as a {} match.",
+                    id,
+                    mode.name()
+                ))
+            }
+            "remove" => {
+                let Some(ResolvedValue::Integer(id)) = options.remove("id") else {
+                    unreachable!("Id option is required");
+                };
+
+                let row = FilterPatternsTable::delete(pool, guild_id, id as i32)
+                    .await?
+                    .ok_or(Error::FilterPatternNotFound)?;
+
+                EditInteractionResponse::new().content(format!("Removed {}.", describe(&row)))
+            }
+            "list" => {
+                let rows = FilterPatternsTable::get_guild_rows(pool, guild_id).await?;
+                let settings = GuildTable::get_filter_settings(pool, guild_id).await?;
+
+                let mut patterns = String::new();
+                let mut shown = 0;
+
+                for line in rows.iter().take(LIST_LIMIT).map(describe) {
+                    if patterns.chars().count() + line.chars().count() + 1 > DESCRIPTION_LIMIT {
+                        break;
+                    }
+
+                    if !patterns.is_empty() {
+                        patterns.push('\n');
+                    }
+                    patterns.push_str(&line);
+                    shown += 1;
+                }
+
+                if rows.is_empty() {
+                    patterns = String::from("No patterns are filtered.");
+                } else if rows.len() > shown {
+                    patterns.push_str(&format!("\n…and {} more", rows.len() - shown));
+                }
+
+                let mentions = |ids: Vec<String>| {
+                    if ids.is_empty() {
+                        String::from("None")
+                    } else {
+                        ids.join(", ")
+                    }
+                };
+
+                let embed = CreateEmbed::new()
+                    .title("Filtered Patterns")
+                    .description(patterns)
+                    .field(
+                        "Exempt roles",
+                        mentions(
+                            settings
+                                .exempt_roles
+                                .iter()
+                                .map(|id| id.mention().to_string())
+                                .collect(),
+                        ),
+                        true,
+                    )
+                    .field(
+                        "Exempt channels",
+                        mentions(
+                            settings
+                                .exempt_channels
+                                .iter()
+                                .map(|id| id.mention().to_string())
+                                .collect(),
+                        ),
+                        true,
+                    )
+                    .field(
+                        "On match",
+                        if settings.warn {
+                            "Delete and warn"
+                        } else {
+                            "Delete"
+                        },
+                        true,
+                    );
+
+                EditInteractionResponse::new().embed(embed)
+            }
+            "test" => {
+                let Some(ResolvedValue::String(text)) = options.remove("text") else {
+                    unreachable!("Text option is required");
+                };
+
+                let rows = FilterPatternsTable::get_guild_rows(pool, guild_id).await?;
+                let matches = compile_rows(&rows).matches(text);
+
+                let content = if matches.is_empty() {
+                    String::from("No patterns match.")
+                } else {
+                    let matched = rows
+                        .iter()
+                        .filter(|row| matches.contains(&row.id))
+                        .map(describe)
+                        .collect::<Vec<_>>()
+                        .join("\n");
+
+                    format!("Matched:\n{}", matched)
+                };
+
+                EditInteractionResponse::new().content(content)
+            }
+            "exempt-role" => {
+                let Some(ResolvedValue::Role(role)) = options.remove("role") else {
+                    unreachable!("Role option is required");
+                };
+                let Some(ResolvedValue::Boolean(exempt)) = options.remove("exempt") else {
+                    unreachable!("Exempt option is required");
+                };
+
+                GuildTable::save_filter_exempt_role(pool, guild_id, role.id, exempt).await?;
+
+                let content = if exempt {
+                    format!("{} is now exempt from the filter.", role.id.mention())
+                } else {
+                    format!("{} is no longer exempt from the filter.", role.id.mention())
+                };
+
+                EditInteractionResponse::new().content(content)
+            }
+            "exempt-channel" => {
+                let Some(ResolvedValue::Channel(channel)) = options.remove("channel") else {
+                    unreachable!("Channel option is required");
+                };
+                let Some(ResolvedValue::Boolean(exempt)) = options.remove("exempt") else {
+                    unreachable!("Exempt option is required");
+                };
+
+                GuildTable::save_filter_exempt_channel(pool, guild_id, channel.id, exempt).await?;
+
+                let content = if exempt {
+                    format!("{} is now exempt from the filter.", channel.id.mention())
+                } else {
+                    format!(
+                        "{} is no longer exempt from the filter.",
+                        channel.id.mention()
+                    )
+                };
+
+                EditInteractionResponse::new().content(content)
+            }
+            "warn" => {
+                let Some(ResolvedValue::Boolean(warn)) = options.remove("enabled") else {
+                    unreachable!("Enabled option is required");
+                };
+
+                GuildTable::save_filter_warn(pool, guild_id, warn).await?;
+
+                let content = if warn {
+                    "Filtered messages will be deleted and their authors warned."
+                } else {
+                    "Filtered messages will be deleted without a warning."
+                };
+
+                EditInteractionResponse::new().content(content)
+            }
+            _ => unreachable!("Unknown subcommand"),
+        };
+
+        if command.name != "list" && command.name != "test" {
+            GuildFilter::invalidate(ctx, guild_id).await;
+        }
+
+        interaction.edit_response(ctx, response).await.unwrap();
+
+        Ok(())
+    }
+
+    fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
+        let command = CreateCommand::new("filter")
+            .description("Manage blocked words and patterns")
+            .default_member_permissions(Permissions::MANAGE_GUILD)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "add",
+                    "Block a word or pattern",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "pattern",
+                        "The word, phrase or regex to block",
+                    )
+                    .max_length(200)
+                    .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "mode",
+                        "How the pattern is matched",
+                    )
+                    .add_string_choice("Whole word (default)", "word")
+                    .add_string_choice("Anywhere in the message", "substring")
+                    .add_string_choice("Regex", "regex"),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "remove",
+                    "Unblock a pattern",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "id",
+                        "The pattern number from /filter list",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "list",
+                "List blocked patterns and exemptions",
+            ))
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "test",
+                    "Check which patterns a message would match",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "text",
+                        "The message to test",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "exempt-role",
+                    "Let a role post filtered patterns",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Role, "role", "The role")
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "exempt",
+                        "Whether the role is exempt",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "exempt-channel",
+                    "Allow filtered patterns in a channel or category",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Channel, "channel", "The channel")
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "exempt",
+                        "Whether the channel is exempt",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "warn",
+                    "Warn members whose messages are filtered",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "enabled",
+                        "Whether to record a warn infraction",
+                    )
+                    .required(true),
+                ),
+            );
+
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate, FilterMode, WordFilter};
+
+    #[test]
+    fn word_mode_needs_whole_words() {
+        let filter = WordFilter::compile([(1, FilterMode::Word, "ass")]);
+
+        assert_eq!(filter.matches("you ass"), vec![1]);
+        assert_eq!(filter.matches("ASS!"), vec![1]);
+        assert!(filter.matches("first class").is_empty());
+        assert!(filter.matches("assassin").is_empty());
+    }
+
+    #[test]
+    fn word_mode_handles_punctuation_in_the_pattern() {
+        let filter = WordFilter::compile([(1, FilterMode::Word, "f*ck")]);
+
+        assert_eq!(filter.matches("what the f*ck"), vec![1]);
+        assert!(filter.matches("fuck").is_empty());
+    }
+
+    #[test]
+    fn substring_mode_matches_inside_words() {
+        let filter = WordFilter::compile([(1, FilterMode::Substring, "nitro")]);
+
+        assert_eq!(filter.matches("FreeNitroHere"), vec![1]);
+        assert!(filter.matches("nit ro").is_empty());
+    }
+
+    #[test]
+    fn regex_mode_is_case_insensitive() {
+        let filter = WordFilter::compile([(1, FilterMode::Regex, r"s+p+a+m+")]);
+
+        assert_eq!(filter.matches("SSSPAAAM"), vec![1]);
+        assert!(filter.matches("spa m").is_empty());
+    }
+
+    #[test]
+    fn reports_every_matching_pattern() {
+        let filter = WordFilter::compile([
+            (3, FilterMode::Word, "foo"),
+            (5, FilterMode::Substring, "bar"),
+            (8, FilterMode::Word, "baz"),
+        ]);
+
+        assert_eq!(filter.matches("foo and crowbar"), vec![3, 5]);
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected_and_skipped() {
+        assert!(validate(FilterMode::Regex, "(unclosed").is_err());
+        assert!(validate(FilterMode::Word, "  ").is_err());
+        assert!(validate(FilterMode::Substring, "(unclosed").is_ok());
+
+        let filter = WordFilter::compile([
+            (1, FilterMode::Regex, "(unclosed"),
+            (2, FilterMode::Word, "ok"),
+        ]);
+        assert_eq!(filter.matches("ok"), vec![2]);
+    }
+
+    #[test]
+    fn patterns_over_the_size_limit_are_skipped() {
+        let patterns = (1..=16).map(|id| (id, FilterMode::Regex, r"\w{20}"));
+        let filter = WordFilter::compile(patterns);
+
+        let kept = filter.matches(&"a".repeat(20));
+        assert!(!kept.is_empty());
+        assert!(kept.len() < 16);
+        assert!(!WordFilter::fits(
+            kept.iter().map(|_| (FilterMode::Regex, r"\w{20}")),
+            FilterMode::Regex,
+            r"\w{20}"
+        ));
+        assert!(WordFilter::fits([], FilterMode::Word, "spam"));
+    }
+
+    #[test]
+    fn empty_filter_matches_nothing() {
+        let filter = WordFilter::compile([]);
+
+        assert!(filter.is_empty());
+        assert!(filter.matches("anything").is_empty());
+    }
+}
@@ -0,0 +1,557 @@
+pub mod filter;
+pub mod raid;
+pub mod rules;
+pub mod scam;
+
+pub use filter::FilterCommand;
+pub use raid::{RaidCommand, RaidProtection};
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::TimeDelta;
+use serenity::all::{
+    CommandInteraction, CommandOptionType, Context, CreateCommand, CreateCommandOption,
+    CreateEmbed, EditInteractionResponse, GuildId, Message, Permissions, Ready, ResolvedOption,
+    ResolvedValue, User, UserId,
+};
+use serenity::prelude::TypeMapKey;
+use sqlx::{PgPool, Postgres};
+use zayden_core::{parse_options, SlashCommand};
+
+use crate::modules::moderation::mod_log::{post_mod_log, ModLogEntry};
+use crate::modules::moderation::{
+    apply_sanction, format_duration, parse_duration, Sanction, SanctionRequest, MAX_MUTE,
+};
+use crate::sqlx_lib::GuildTable;
+use crate::{Error, Result};
+
+use rules::{AutoModAction, AutoModConfig, AutoModRule, MessageSample, RuleConfig, UserHistory};
+
+/// Histories are swept for idle members once this many are tracked.
+const HISTORY_SWEEP_SIZE: usize = 1000;
+
+pub fn register(ctx: &Context, ready: &Ready) -> Result<Vec<CreateCommand>> {
+    let commands = vec![
+        AutoModCommand::register(ctx, ready)?,
+        FilterCommand::register(ctx, ready)?,
+        RaidCommand::register(ctx, ready)?,
+    ];
+
+    Ok(commands)
+}
+
+#[derive(Default)]
+pub struct AutoModState {
+    configs: HashMap<GuildId, AutoModConfig>,
+    histories: HashMap<(GuildId, UserId), UserHistory>,
+    filters: HashMap<GuildId, filter::GuildFilter>,
+    raid_configs: HashMap<GuildId, raid::RaidConfig>,
+    joins: HashMap<GuildId, raid::JoinTracker>,
+}
+
+impl TypeMapKey for AutoModState {
+    type Value = AutoModState;
+}
+
+struct AutoModRuleRow {
+    rule: String,
+    enabled: bool,
+    threshold: i32,
+    window_secs: i32,
+    action: String,
+    duration_secs: Option<i32>,
+}
+
+impl GuildTable {
+    pub async fn get_automod_config(pool: &PgPool, id: GuildId) -> Result<AutoModConfig> {
+        let rows = sqlx::query_as!(
+            AutoModRuleRow,
+            "SELECT rule, enabled, threshold, window_secs, action, duration_secs FROM automod_rules WHERE guild_id = $1",
+            id.get() as i64
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        let mut config = AutoModConfig::default();
+
+        if let Some(row) = sqlx::query!(
+            "SELECT automod_scam_links FROM guilds WHERE id = $1",
+            id.get() as i64
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap()
+        {
+            config.scam_links = row.automod_scam_links;
+        }
+
+        for row in rows {
+            let Some(rule) = AutoModRule::from_name(&row.rule) else {
+                continue;
+            };
+            let duration = row
+                .duration_secs
+                .map(|secs| TimeDelta::seconds(secs as i64));
+            let Some(action) = AutoModAction::from_parts(&row.action, duration) else {
+                continue;
+            };
+
+            config.set_rule(
+                rule,
+                RuleConfig {
+                    enabled: row.enabled,
+                    threshold: row.threshold,
+                    window: TimeDelta::seconds(row.window_secs as i64),
+                    action,
+                },
+            );
+        }
+
+        Ok(config)
+    }
+
+    pub async fn save_automod_rule(
+        pool: &PgPool,
+        id: GuildId,
+        rule: AutoModRule,
+        config: RuleConfig,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO automod_rules (guild_id, rule, enabled, threshold, window_secs, action, duration_secs) VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (guild_id, rule) DO UPDATE SET enabled = $3, threshold = $4, window_secs = $5, action = $6, duration_secs = $7",
+            id.get() as i64,
+            rule.name(),
+            config.enabled,
+            config.threshold,
+            config.window.num_seconds() as i32,
+            config.action.name(),
+            config.action.duration().map(|duration| duration.num_seconds() as i32)
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+
+    pub async fn save_automod_scam_links(pool: &PgPool, id: GuildId, enabled: bool) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO guilds (id, automod_scam_links) VALUES ($1, $2)
+             ON CONFLICT (id) DO UPDATE SET automod_scam_links = $2",
+            id.get() as i64,
+            enabled
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+}
+
+pub struct AutoMod;
+
+impl AutoMod {
+    async fn config(ctx: &Context, pool: &PgPool, guild_id: GuildId) -> Result<AutoModConfig> {
+        {
+            let data = ctx.data.read().await;
+            if let Some(config) = data
+                .get::<AutoModState>()
+                .and_then(|state| state.configs.get(&guild_id))
+            {
+                return Ok(config.clone());
+            }
+        }
+
+        let config = GuildTable::get_automod_config(pool, guild_id).await?;
+
+        let mut data = ctx.data.write().await;
+        if let Some(state) = data.get_mut::<AutoModState>() {
+            state.configs.insert(guild_id, config.clone());
+        }
+
+        Ok(config)
+    }
+
+    async fn invalidate(ctx: &Context, guild_id: GuildId) {
+        let mut data = ctx.data.write().await;
+        if let Some(state) = data.get_mut::<AutoModState>() {
+            state.configs.remove(&guild_id);
+        }
+    }
+
+    /// Members who can manage messages in the channel are trusted to moderate themselves.
+    fn is_exempt(ctx: &Context, msg: &Message) -> bool {
+        let (Some(guild_id), Some(member)) = (msg.guild_id, &msg.member) else {
+            return true;
+        };
+
+        let Some(guild) = ctx.cache.guild(guild_id) else {
+            return false;
+        };
+
+        guild.channels.get(&msg.channel_id).is_some_and(|channel| {
+            guild
+                .partial_member_permissions_in(channel, msg.author.id, member)
+                .manage_messages()
+        })
+    }
+
+    /// Checks the message against the guild's rules and acts on the first one it breaks. Returns
+    /// true if the message was removed.
+    pub async fn run(ctx: &Context, msg: &Message, pool: &PgPool) -> Result<bool> {
+        let Some(guild_id) = msg.guild_id else {
+            return Ok(false);
+        };
+
+        if Self::is_exempt(ctx, msg) {
+            return Ok(false);
+        }
+
+        let config = Self::config(ctx, pool, guild_id).await?;
+
+        if config.scam_links {
+            if let Some(link) = scam::find_scam_link(&msg.content) {
+                scam::remove_scammer(ctx, msg, pool, guild_id, link).await?;
+                return Ok(true);
+            }
+        }
+
+        if filter::run(ctx, msg, pool, guild_id).await? {
+            return Ok(true);
+        }
+
+        if !config.is_enabled() {
+            return Ok(false);
+        }
+
+        let sample = MessageSample {
+            content: msg.content.clone(),
+            mentions: if msg.mention_everyone { 1 } else { 0 }
+                + msg.mentions.len()
+                + msg.mention_roles.len(),
+            sent_at: msg.timestamp.naive_utc(),
+        };
+
+        let violation = {
+            let mut data = ctx.data.write().await;
+            let Some(state) = data.get_mut::<AutoModState>() else {
+                return Ok(false);
+            };
+
+            let keep = config.longest_window();
+            if state.histories.len() >= HISTORY_SWEEP_SIZE {
+                let cutoff = sample.sent_at - keep;
+                state
+                    .histories
+                    .retain(|_, history| !history.is_stale(cutoff));
+            }
+
+            let history = state
+                .histories
+                .entry((guild_id, msg.author.id))
+                .or_default();
+            history.record(sample, keep);
+
+            let violation = config.check(history);
+            if violation.is_some() {
+                history.clear();
+            }
+            violation
+        };
+
+        let Some(violation) = violation else {
+            return Ok(false);
+        };
+
+        let reason = format!("Automod: {}", violation.rule.label());
+        enforce(ctx, pool, msg, guild_id, violation.action, &reason).await?;
+
+        Ok(true)
+    }
+}
+
+/// Deletes the message and takes the action against its author. Warns and timeouts go through the
+/// infraction recorder, which also logs them. Members the bot can't moderate, such as those with a
+/// higher role, only get their message deleted.
+async fn enforce(
+    ctx: &Context,
+    pool: &PgPool,
+    msg: &Message,
+    guild_id: GuildId,
+    action: AutoModAction,
+    reason: &str,
+) -> Result<()> {
+    // Another bot or moderator may have removed it already.
+    let _ = msg.delete(ctx).await;
+
+    let bot = User::from(ctx.cache.current_user().clone());
+
+    let sanction = match action {
+        AutoModAction::Delete => {
+            post_mod_log(
+                ctx,
+                pool,
+                guild_id,
+                &ModLogEntry {
+                    action: "Automod Delete",
+                    case_id: None,
+                    target: msg.author.id,
+                    moderator: Some(bot.id),
+                    reason: Some(reason),
+                    duration: None,
+                    jump_url: None,
+                    colour: 0x99AAB5,
+                },
+            )
+            .await?;
+
+            return Ok(());
+        }
+        AutoModAction::Warn => Sanction::Warn,
+        AutoModAction::Timeout(duration) => Sanction::Mute(duration),
+    };
+
+    if let Err(e) = apply_sanction(
+        ctx,
+        pool,
+        SanctionRequest {
+            guild_id,
+            user: &msg.author,
+            moderator: &bot,
+            sanction,
+            points: 1,
+            reason,
+            jump_url: None,
+        },
+    )
+    .await
+    {
+        eprintln!("Error enforcing automod on {}: {:?}", msg.author.id, e);
+    }
+
+    Ok(())
+}
+
+pub struct AutoModCommand;
+
+#[async_trait]
+impl SlashCommand<Error, Postgres> for AutoModCommand {
+    async fn run(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        mut options: Vec<ResolvedOption<'_>>,
+        pool: &PgPool,
+    ) -> Result<()> {
+        interaction.defer_ephemeral(ctx).await.unwrap();
+
+        let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+        let command = options.remove(0);
+        let ResolvedValue::SubCommand(options) = command.value else {
+            unreachable!("Subcommand is required");
+        };
+        let mut options = parse_options(options);
+
+        let mut config = GuildTable::get_automod_config(pool, guild_id).await?;
+
+        if command.name == "scam-links" {
+            let Some(ResolvedValue::Boolean(enabled)) = options.remove("enabled") else {
+                unreachable!("Enabled option is required");
+            };
+
+            GuildTable::save_automod_scam_links(pool, guild_id, enabled).await?;
+            config.scam_links = enabled;
+            AutoMod::invalidate(ctx, guild_id).await;
+        } else if command.name != "view" {
+            let Some(ResolvedValue::String(rule)) = options.remove("rule") else {
+                unreachable!("Rule option is required");
+            };
+            let rule = AutoModRule::from_name(rule).expect("Unknown rule");
+            let mut rule_config = config.rule(rule);
+
+            match command.name {
+                "set" => {
+                    let Some(ResolvedValue::Integer(threshold)) = options.remove("threshold")
+                    else {
+                        unreachable!("Threshold option is required");
+                    };
+                    let Some(ResolvedValue::String(action)) = options.remove("action") else {
+                        unreachable!("Action option is required");
+                    };
+
+                    let duration = match options.remove("duration") {
+                        Some(ResolvedValue::String(duration)) => {
+                            Some(parse_duration(duration).ok_or(Error::InvalidDuration)?)
+                        }
+                        _ => None,
+                    };
+
+                    let action = AutoModAction::from_parts(action, duration)
+                        .ok_or(Error::InvalidDuration)?;
+
+                    if action
+                        .duration()
+                        .is_some_and(|duration| duration > MAX_MUTE)
+                    {
+                        return Err(Error::InvalidDuration);
+                    }
+
+                    if let Some(ResolvedValue::Integer(seconds)) = options.remove("window") {
+                        rule_config.window = TimeDelta::seconds(seconds);
+                    }
+
+                    rule_config.enabled = true;
+                    rule_config.threshold = threshold as i32;
+                    rule_config.action = action;
+                }
+                "disable" => rule_config.enabled = false,
+                _ => unreachable!("Unknown subcommand"),
+            }
+
+            GuildTable::save_automod_rule(pool, guild_id, rule, rule_config).await?;
+            config.set_rule(rule, rule_config);
+            AutoMod::invalidate(ctx, guild_id).await;
+        }
+
+        let fields = AutoModRule::ALL.into_iter().map(|rule| {
+            let rule_config = config.rule(rule);
+
+            let mut value = if rule_config.enabled {
+                format!(
+                    "**Threshold:** {}\n**Action:** {}",
+                    rule_config.threshold, rule_config.action
+                )
+            } else {
+                String::from("Disabled")
+            };
+
+            if rule_config.enabled && matches!(rule, AutoModRule::Flood | AutoModRule::Duplicate) {
+                value.push_str(&format!(
+                    "\n**Window:** {}",
+                    format_window(rule_config.window)
+                ));
+            }
+
+            (rule.label(), value, true)
+        });
+
+        let scam_links = if config.scam_links {
+            "Soft ban the author"
+        } else {
+            "Disabled"
+        };
+
+        let embed = CreateEmbed::new()
+            .title("Automod")
+            .description("Members who can manage messages are exempt.")
+            .fields(fields)
+            .field("Scam links", scam_links, true);
+
+        interaction
+            .edit_response(ctx, EditInteractionResponse::new().embed(embed))
+            .await
+            .unwrap();
+
+        Ok(())
+    }
+
+    fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
+        let rule = || {
+            let mut option =
+                CreateCommandOption::new(CommandOptionType::String, "rule", "The rule to change")
+                    .required(true);
+            for rule in AutoModRule::ALL {
+                option = option.add_string_choice(rule.label(), rule.name());
+            }
+            option
+        };
+
+        let command = CreateCommand::new("automod")
+            .description("Configure automatic moderation")
+            .default_member_permissions(Permissions::MANAGE_GUILD)
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "view",
+                "Show the automod rules",
+            ))
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "set",
+                    "Enable a rule with a threshold and action",
+                )
+                .add_sub_option(rule())
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "threshold",
+                        "Messages, copies, mentions or invites that trigger the rule",
+                    )
+                    .min_int_value(1)
+                    .max_int_value(50)
+                    .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "action",
+                        "What to do with the message and its author",
+                    )
+                    .add_string_choice("Delete", "delete")
+                    .add_string_choice("Delete and warn", "warn")
+                    .add_string_choice("Delete and timeout", "timeout")
+                    .required(true),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "duration",
+                    "How long timeouts last, such as 10m or 1h",
+                ))
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "window",
+                        "Seconds that floods and duplicates are counted over",
+                    )
+                    .min_int_value(1)
+                    .max_int_value(3600),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "disable",
+                    "Disable a rule",
+                )
+                .add_sub_option(rule()),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "scam-links",
+                    "Soft ban members who post scam links",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "enabled",
+                        "Whether scam links are removed",
+                    )
+                    .required(true),
+                ),
+            );
+
+        Ok(command)
+    }
+}
+
+fn format_window(window: TimeDelta) -> String {
+    if window.num_seconds() % 60 == 0 {
+        format_duration(window)
+    } else {
+        format!("{} seconds", window.num_seconds())
+    }
+}
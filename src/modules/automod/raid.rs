@@ -0,0 +1,706 @@
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use serenity::all::{
+    ButtonStyle, CommandInteraction, CommandOptionType, ComponentInteraction, Context,
+    CreateActionRow, CreateButton, CreateCommand, CreateCommandOption, CreateEmbed, CreateMessage,
+    EditGuild, EditInteractionResponse, GuildId, Member, Mentionable, Permissions, Ready,
+    ResolvedOption, ResolvedValue, RoleId, Timestamp, User, UserId, VerificationLevel,
+};
+use sqlx::{PgPool, Postgres};
+use zayden_core::{parse_options, SlashCommand};
+
+use crate::modules::moderation::mod_log::send_mod_log;
+use crate::sqlx_lib::GuildTable;
+use crate::{Error, Result};
+
+use super::{format_window, AutoModState};
+
+const LOCKDOWN_REASON: &str = "Raid lockdown";
+
+/// What happens to young accounts that join during a lockdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateAction {
+    Kick,
+    Quarantine,
+}
+
+impl GateAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Kick => "kick",
+            Self::Quarantine => "quarantine",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "kick" => Some(Self::Kick),
+            "quarantine" => Some(Self::Quarantine),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RaidConfig {
+    pub enabled: bool,
+    /// Joins within the window that start a lockdown.
+    pub threshold: i32,
+    pub window: TimeDelta,
+    pub action: GateAction,
+    /// Accounts younger than this are kicked or quarantined during a lockdown.
+    pub min_account_age: TimeDelta,
+    pub quarantine_role: Option<RoleId>,
+    /// The verification level to restore, set while the guild is locked down.
+    pub lockdown: Option<VerificationLevel>,
+}
+
+impl Default for RaidConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 10,
+            window: TimeDelta::seconds(30),
+            action: GateAction::Kick,
+            min_account_age: TimeDelta::days(7),
+            quarantine_role: None,
+            lockdown: None,
+        }
+    }
+}
+
+impl RaidConfig {
+    pub fn is_locked_down(&self) -> bool {
+        self.lockdown.is_some()
+    }
+
+    fn gate_description(&self) -> String {
+        let action = match (self.action, self.quarantine_role) {
+            (GateAction::Quarantine, Some(role_id)) => {
+                format!("given {}", role_id.mention())
+            }
+            _ => String::from("kicked"),
+        };
+
+        format!(
+            "Accounts younger than {} days are {}",
+            self.min_account_age.num_days(),
+            action
+        )
+    }
+}
+
+/// Recent joins to one guild.
+#[derive(Debug, Default)]
+pub struct JoinTracker {
+    joins: VecDeque<(UserId, NaiveDateTime)>,
+}
+
+impl JoinTracker {
+    /// Records the join and returns how many joins there have been within the window.
+    pub fn record(&mut self, user_id: UserId, now: NaiveDateTime, window: TimeDelta) -> usize {
+        let since = now - window;
+        while self.joins.front().is_some_and(|(_, at)| *at < since) {
+            self.joins.pop_front();
+        }
+
+        self.joins.push_back((user_id, now));
+        self.joins.len()
+    }
+
+    /// Takes the joins that are still being tracked.
+    pub fn drain(&mut self) -> Vec<UserId> {
+        self.joins.drain(..).map(|(user_id, _)| user_id).collect()
+    }
+}
+
+pub fn is_young(created_at: NaiveDateTime, now: NaiveDateTime, min_age: TimeDelta) -> bool {
+    now - created_at < min_age
+}
+
+impl GuildTable {
+    pub async fn get_raid_config(pool: &PgPool, id: GuildId) -> Result<RaidConfig> {
+        let row = sqlx::query!(
+            "SELECT raid_enabled, raid_threshold, raid_window_secs, raid_action, raid_min_account_days, raid_quarantine_role, raid_lockdown_level FROM guilds WHERE id = $1",
+            id.get() as i64
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        let Some(row) = row else {
+            return Ok(RaidConfig::default());
+        };
+
+        let config = RaidConfig {
+            enabled: row.raid_enabled,
+            threshold: row.raid_threshold,
+            window: TimeDelta::seconds(row.raid_window_secs as i64),
+            action: GateAction::from_name(&row.raid_action).unwrap_or(GateAction::Kick),
+            min_account_age: TimeDelta::days(row.raid_min_account_days as i64),
+            quarantine_role: row.raid_quarantine_role.map(|id| RoleId::new(id as u64)),
+            lockdown: row
+                .raid_lockdown_level
+                .map(|level| VerificationLevel::from(level as u8)),
+        };
+
+        Ok(config)
+    }
+
+    pub async fn save_raid_config(pool: &PgPool, id: GuildId, config: &RaidConfig) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO guilds (id, raid_enabled, raid_threshold, raid_window_secs, raid_action, raid_min_account_days, raid_quarantine_role) VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (id) DO UPDATE SET raid_enabled = $2, raid_threshold = $3, raid_window_secs = $4, raid_action = $5, raid_min_account_days = $6, raid_quarantine_role = $7",
+            id.get() as i64,
+            config.enabled,
+            config.threshold,
+            config.window.num_seconds() as i32,
+            config.action.name(),
+            config.min_account_age.num_days() as i32,
+            config.quarantine_role.map(|id| id.get() as i64)
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+
+    pub async fn save_raid_lockdown(
+        pool: &PgPool,
+        id: GuildId,
+        lockdown: Option<VerificationLevel>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO guilds (id, raid_lockdown_level) VALUES ($1, $2)
+             ON CONFLICT (id) DO UPDATE SET raid_lockdown_level = $2",
+            id.get() as i64,
+            lockdown.map(|level| u8::from(level) as i16)
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+}
+
+pub struct RaidProtection;
+
+impl RaidProtection {
+    async fn config(ctx: &Context, pool: &PgPool, guild_id: GuildId) -> Result<RaidConfig> {
+        {
+            let data = ctx.data.read().await;
+            if let Some(config) = data
+                .get::<AutoModState>()
+                .and_then(|state| state.raid_configs.get(&guild_id))
+            {
+                return Ok(config.clone());
+            }
+        }
+
+        let config = GuildTable::get_raid_config(pool, guild_id).await?;
+
+        let mut data = ctx.data.write().await;
+        if let Some(state) = data.get_mut::<AutoModState>() {
+            state.raid_configs.insert(guild_id, config.clone());
+        }
+
+        Ok(config)
+    }
+
+    async fn invalidate(ctx: &Context, guild_id: GuildId) {
+        let mut data = ctx.data.write().await;
+        if let Some(state) = data.get_mut::<AutoModState>() {
+            state.raid_configs.remove(&guild_id);
+            state.joins.remove(&guild_id);
+        }
+    }
+
+    /// Counts the join towards the guild's join rate, starting a lockdown once it crosses the
+    /// threshold. Young accounts are gated while a lockdown is running, including the ones that
+    /// set it off.
+    pub async fn member_join(ctx: &Context, pool: &PgPool, member: &Member) -> Result<()> {
+        if member.user.bot {
+            return Ok(());
+        }
+
+        let guild_id = member.guild_id;
+        let config = Self::config(ctx, pool, guild_id).await?;
+
+        if config.is_locked_down() {
+            gate(ctx, guild_id, member.user.id, &config).await;
+            return Ok(());
+        }
+
+        if !config.enabled {
+            return Ok(());
+        }
+
+        let raiders = {
+            let mut data = ctx.data.write().await;
+            let Some(state) = data.get_mut::<AutoModState>() else {
+                return Ok(());
+            };
+
+            let tracker = state.joins.entry(guild_id).or_default();
+            let joins = tracker.record(member.user.id, Utc::now().naive_utc(), config.window);
+
+            if joins < config.threshold as usize {
+                return Ok(());
+            }
+
+            tracker.drain()
+        };
+
+        let trigger = format!(
+            "{} joins within {}",
+            raiders.len(),
+            format_window(config.window)
+        );
+
+        let config = match start_lockdown(ctx, pool, guild_id, &trigger).await {
+            Ok(config) => config,
+            // Another join got there first.
+            Err(Error::AlreadyLockedDown) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        for user_id in raiders {
+            gate(ctx, guild_id, user_id, &config).await;
+        }
+
+        Ok(())
+    }
+}
+
+/// Kicks or quarantines the member if their account is too new. Members who have already left
+/// are skipped.
+async fn gate(ctx: &Context, guild_id: GuildId, user_id: UserId, config: &RaidConfig) {
+    let Some(created_at) = DateTime::from_timestamp(user_id.created_at().unix_timestamp(), 0)
+    else {
+        return;
+    };
+
+    if !is_young(
+        created_at.naive_utc(),
+        Utc::now().naive_utc(),
+        config.min_account_age,
+    ) {
+        return;
+    }
+
+    match (config.action, config.quarantine_role) {
+        (GateAction::Quarantine, Some(role_id)) => {
+            let _ = ctx
+                .http
+                .add_member_role(guild_id, user_id, role_id, Some(LOCKDOWN_REASON))
+                .await;
+        }
+        _ => {
+            let _ = guild_id
+                .kick_with_reason(ctx, user_id, LOCKDOWN_REASON)
+                .await;
+        }
+    }
+}
+
+/// Raises the verification level, remembers the old one and alerts the mod log. Returns the
+/// config with the lockdown set. Joins are gated even if the bot can't change the verification
+/// level, since that's when protection matters most.
+async fn start_lockdown(
+    ctx: &Context,
+    pool: &PgPool,
+    guild_id: GuildId,
+    trigger: &str,
+) -> Result<RaidConfig> {
+    let mut config = GuildTable::get_raid_config(pool, guild_id).await?;
+
+    if config.is_locked_down() {
+        return Err(Error::AlreadyLockedDown);
+    }
+
+    let cached = ctx
+        .cache
+        .guild(guild_id)
+        .map(|guild| guild.verification_level);
+    // If the level can't be read, High is remembered so ending the lockdown never lowers it.
+    let previous = match cached {
+        Some(level) => level,
+        None => guild_id
+            .to_partial_guild(ctx)
+            .await
+            .map_or(VerificationLevel::High, |guild| guild.verification_level),
+    };
+
+    let mut raised = true;
+    if previous < VerificationLevel::High {
+        if let Err(e) = guild_id
+            .edit(
+                ctx,
+                EditGuild::new()
+                    .verification_level(VerificationLevel::High)
+                    .audit_log_reason(LOCKDOWN_REASON),
+            )
+            .await
+        {
+            eprintln!("Error raising verification level in {}: {:?}", guild_id, e);
+            raised = false;
+        }
+    }
+
+    GuildTable::save_raid_lockdown(pool, guild_id, Some(previous)).await?;
+    RaidProtection::invalidate(ctx, guild_id).await;
+    config.lockdown = Some(previous);
+
+    let verification = if raised {
+        "The verification level is at least High until the lockdown ends."
+    } else {
+        "The verification level couldn't be raised. Give the bot Manage Server to do this next time."
+    };
+
+    let embed = CreateEmbed::new()
+        .title("Raid Lockdown")
+        .colour(0xED4245)
+        .description(format!("{}.\n{}", config.gate_description(), verification))
+        .field("Trigger", trigger, false)
+        .timestamp(Timestamp::now());
+
+    let end = CreateButton::new("raid_end")
+        .label("End lockdown")
+        .style(ButtonStyle::Danger);
+
+    send_mod_log(
+        ctx,
+        pool,
+        guild_id,
+        CreateMessage::new()
+            .embed(embed)
+            .components(vec![CreateActionRow::Buttons(vec![end])]),
+    )
+    .await?;
+
+    Ok(config)
+}
+
+/// Restores the verification level from before the lockdown. Quarantined members keep the role
+/// until a moderator has reviewed them.
+async fn end_lockdown(
+    ctx: &Context,
+    pool: &PgPool,
+    guild_id: GuildId,
+    moderator: &User,
+) -> Result<()> {
+    let config = GuildTable::get_raid_config(pool, guild_id).await?;
+
+    let Some(previous) = config.lockdown else {
+        return Err(Error::NotLockedDown);
+    };
+
+    // The lockdown still ends if the level can't be restored, so joins stop being gated.
+    let mut description = format!("Ended by {}", moderator.mention());
+    if previous < VerificationLevel::High {
+        if let Err(e) = guild_id
+            .edit(
+                ctx,
+                EditGuild::new()
+                    .verification_level(previous)
+                    .audit_log_reason("Raid lockdown ended"),
+            )
+            .await
+        {
+            eprintln!(
+                "Error restoring verification level in {}: {:?}",
+                guild_id, e
+            );
+            description.push_str("\nThe verification level couldn't be restored.");
+        }
+    }
+
+    GuildTable::save_raid_lockdown(pool, guild_id, None).await?;
+    RaidProtection::invalidate(ctx, guild_id).await;
+
+    let embed = CreateEmbed::new()
+        .title("Raid Lockdown Ended")
+        .colour(0x57F287)
+        .description(description)
+        .timestamp(Timestamp::now());
+
+    send_mod_log(ctx, pool, guild_id, CreateMessage::new().embed(embed)).await
+}
+
+/// The "End lockdown" button on the alert.
+pub async fn end_button(
+    ctx: &Context,
+    interaction: &ComponentInteraction,
+    pool: &PgPool,
+) -> Result<()> {
+    interaction.defer(ctx).await.unwrap();
+
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    if !interaction
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_guild())
+    {
+        return Err(Error::MissingPermissions);
+    }
+
+    match end_lockdown(ctx, pool, guild_id, &interaction.user).await {
+        Ok(()) | Err(Error::NotLockedDown) => {}
+        Err(e) => return Err(e),
+    }
+
+    let embed = interaction
+        .message
+        .embeds
+        .first()
+        .cloned()
+        .map(CreateEmbed::from)
+        .unwrap_or_default()
+        .colour(0x57F287)
+        .field("Ended", format!("By {}", interaction.user.mention()), false);
+
+    interaction
+        .edit_response(
+            ctx,
+            EditInteractionResponse::new()
+                .embed(embed)
+                .components(Vec::new()),
+        )
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+pub struct RaidCommand;
+
+#[async_trait]
+impl SlashCommand<Error, Postgres> for RaidCommand {
+    async fn run(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        mut options: Vec<ResolvedOption<'_>>,
+        pool: &PgPool,
+    ) -> Result<()> {
+        interaction.defer_ephemeral(ctx).await.unwrap();
+
+        let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+        let command = options.remove(0);
+        let ResolvedValue::SubCommand(options) = command.value else {
+            unreachable!("Subcommand is required");
+        };
+        let mut options = parse_options(options);
+
+        let mut config = GuildTable::get_raid_config(pool, guild_id).await?;
+
+        let content = match command.name {
+            "view" => None,
+            "set" => {
+                if let Some(ResolvedValue::Boolean(enabled)) = options.remove("enabled") {
+                    config.enabled = enabled;
+                }
+                if let Some(ResolvedValue::Integer(threshold)) = options.remove("threshold") {
+                    config.threshold = threshold as i32;
+                }
+                if let Some(ResolvedValue::Integer(seconds)) = options.remove("window") {
+                    config.window = TimeDelta::seconds(seconds);
+                }
+                if let Some(ResolvedValue::String(action)) = options.remove("action") {
+                    config.action = GateAction::from_name(action).expect("Unknown action");
+                }
+                if let Some(ResolvedValue::Integer(days)) = options.remove("min-age") {
+                    config.min_account_age = TimeDelta::days(days);
+                }
+                if let Some(ResolvedValue::Role(role)) = options.remove("quarantine-role") {
+                    config.quarantine_role = Some(role.id);
+                }
+
+                if config.action == GateAction::Quarantine && config.quarantine_role.is_none() {
+                    return Err(Error::QuarantineRoleNotSet);
+                }
+
+                GuildTable::save_raid_config(pool, guild_id, &config).await?;
+                RaidProtection::invalidate(ctx, guild_id).await;
+
+                None
+            }
+            "start" => {
+                config = start_lockdown(
+                    ctx,
+                    pool,
+                    guild_id,
+                    &format!("Started by {}", interaction.user.mention()),
+                )
+                .await?;
+
+                Some("The server is locked down.")
+            }
+            "end" => {
+                end_lockdown(ctx, pool, guild_id, &interaction.user).await?;
+                config.lockdown = None;
+
+                Some("The lockdown has ended.")
+            }
+            _ => unreachable!("Unknown subcommand"),
+        };
+
+        let status = match config.lockdown {
+            Some(_) => "**Locked down**",
+            None if config.enabled => "Watching joins",
+            None => "Disabled",
+        };
+
+        let embed = CreateEmbed::new()
+            .title("Raid Protection")
+            .field("Status", status, true)
+            .field(
+                "Trigger",
+                format!(
+                    "{} joins within {}",
+                    config.threshold,
+                    format_window(config.window)
+                ),
+                true,
+            )
+            .field("During a lockdown", config.gate_description(), false);
+
+        let mut response = EditInteractionResponse::new().embed(embed);
+        if let Some(content) = content {
+            response = response.content(content);
+        }
+
+        interaction.edit_response(ctx, response).await.unwrap();
+
+        Ok(())
+    }
+
+    fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
+        let command = CreateCommand::new("raid")
+            .description("Protect the server from join raids")
+            .default_member_permissions(Permissions::MANAGE_GUILD)
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "view",
+                "Show the raid protection settings",
+            ))
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "set",
+                    "Change the raid protection settings",
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "enabled",
+                    "Whether to watch the join rate",
+                ))
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "threshold",
+                        "Joins within the window that start a lockdown",
+                    )
+                    .min_int_value(2),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "window",
+                        "The window in seconds",
+                    )
+                    .min_int_value(5)
+                    .max_int_value(3600),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "action",
+                        "What happens to young accounts during a lockdown",
+                    )
+                    .add_string_choice("Kick", "kick")
+                    .add_string_choice("Quarantine role", "quarantine"),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "min-age",
+                        "Accounts younger than this many days are gated",
+                    )
+                    .min_int_value(0)
+                    .max_int_value(365),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::Role,
+                    "quarantine-role",
+                    "The role given to gated accounts",
+                )),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "start",
+                "Lock the server down now",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "end",
+                "End the lockdown",
+            ));
+
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+    use serenity::all::UserId;
+
+    use super::{is_young, JoinTracker};
+
+    fn at(secs: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 2, 14)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            + TimeDelta::seconds(secs)
+    }
+
+    #[test]
+    fn counts_joins_within_the_window() {
+        let mut tracker = JoinTracker::default();
+        let window = TimeDelta::seconds(30);
+
+        assert_eq!(tracker.record(UserId::new(1), at(0), window), 1);
+        assert_eq!(tracker.record(UserId::new(2), at(10), window), 2);
+        assert_eq!(tracker.record(UserId::new(3), at(30), window), 3);
+        assert_eq!(tracker.record(UserId::new(4), at(41), window), 2);
+    }
+
+    #[test]
+    fn drain_takes_the_tracked_joins() {
+        let mut tracker = JoinTracker::default();
+        let window = TimeDelta::seconds(30);
+
+        tracker.record(UserId::new(1), at(0), window);
+        tracker.record(UserId::new(2), at(5), window);
+
+        assert_eq!(tracker.drain(), vec![UserId::new(1), UserId::new(2)]);
+        assert_eq!(tracker.record(UserId::new(3), at(6), window), 1);
+    }
+
+    #[test]
+    fn young_accounts() {
+        let min_age = TimeDelta::days(7);
+
+        assert!(is_young(at(0), at(60), min_age));
+        assert!(!is_young(at(0), at(0) + TimeDelta::days(7), min_age));
+        assert!(!is_young(at(0), at(0), TimeDelta::zero()));
+    }
+}
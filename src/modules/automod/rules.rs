@@ -0,0 +1,462 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+
+use chrono::{NaiveDateTime, TimeDelta};
+
+use crate::modules::moderation::format_duration;
+
+/// The most messages kept per member. Flood thresholds above this can never trigger.
+const HISTORY_LIMIT: usize = 50;
+
+const INVITE_PATTERNS: [&str; 3] = [
+    "discord.gg/",
+    "discord.com/invite/",
+    "discordapp.com/invite/",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AutoModRule {
+    /// Too many messages in a short window.
+    Flood,
+    /// The same message sent repeatedly.
+    Duplicate,
+    /// Too many user, role or everyone mentions in one message.
+    MassMention,
+    /// Discord invite links.
+    Invite,
+}
+
+impl AutoModRule {
+    /// Rules about a single message are checked before rules about the member's history.
+    pub const ALL: [Self; 4] = [
+        Self::Invite,
+        Self::MassMention,
+        Self::Duplicate,
+        Self::Flood,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Flood => "flood",
+            Self::Duplicate => "duplicate",
+            Self::MassMention => "mass_mention",
+            Self::Invite => "invite",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Flood => "Message flood",
+            Self::Duplicate => "Duplicate spam",
+            Self::MassMention => "Mass mention",
+            Self::Invite => "Invite link",
+        }
+    }
+
+    fn default_config(&self) -> RuleConfig {
+        let (threshold, window, action) = match self {
+            Self::Flood => (
+                6,
+                TimeDelta::seconds(10),
+                AutoModAction::Timeout(TimeDelta::minutes(10)),
+            ),
+            Self::Duplicate => (3, TimeDelta::seconds(60), AutoModAction::Warn),
+            Self::MassMention => (
+                5,
+                TimeDelta::zero(),
+                AutoModAction::Timeout(TimeDelta::hours(1)),
+            ),
+            Self::Invite => (1, TimeDelta::zero(), AutoModAction::Delete),
+        };
+
+        RuleConfig {
+            enabled: false,
+            threshold,
+            window,
+            action,
+        }
+    }
+}
+
+/// What happens to a message that breaks a rule. Every action deletes the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoModAction {
+    Delete,
+    /// Deletes the message and records a warn infraction.
+    Warn,
+    /// Deletes the message and times the author out, recorded as a mute infraction.
+    Timeout(TimeDelta),
+}
+
+impl AutoModAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Delete => "delete",
+            Self::Warn => "warn",
+            Self::Timeout(_) => "timeout",
+        }
+    }
+
+    pub fn duration(&self) -> Option<TimeDelta> {
+        match self {
+            Self::Timeout(duration) => Some(*duration),
+            _ => None,
+        }
+    }
+
+    pub fn from_parts(name: &str, duration: Option<TimeDelta>) -> Option<Self> {
+        match (name, duration) {
+            ("delete", _) => Some(Self::Delete),
+            ("warn", _) => Some(Self::Warn),
+            ("timeout", Some(duration)) => Some(Self::Timeout(duration)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for AutoModAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Delete => write!(f, "Delete"),
+            Self::Warn => write!(f, "Warn"),
+            Self::Timeout(duration) => write!(f, "Timeout for {}", format_duration(*duration)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuleConfig {
+    pub enabled: bool,
+    /// The count at which the rule triggers: messages for floods, copies for duplicates, mentions
+    /// for mass mentions and links for invites.
+    pub threshold: i32,
+    /// How far back floods and duplicates look. Unused by the other rules.
+    pub window: TimeDelta,
+    pub action: AutoModAction,
+}
+
+/// A guild's automod settings. Rules without saved settings are disabled with default thresholds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoModConfig {
+    rules: HashMap<AutoModRule, RuleConfig>,
+    /// Soft ban members who post scam links. Off until a guild turns it on.
+    pub scam_links: bool,
+}
+
+impl Default for AutoModConfig {
+    fn default() -> Self {
+        Self {
+            rules: AutoModRule::ALL
+                .into_iter()
+                .map(|rule| (rule, rule.default_config()))
+                .collect(),
+            scam_links: false,
+        }
+    }
+}
+
+impl AutoModConfig {
+    pub fn rule(&self, rule: AutoModRule) -> RuleConfig {
+        self.rules
+            .get(&rule)
+            .copied()
+            .unwrap_or_else(|| rule.default_config())
+    }
+
+    pub fn set_rule(&mut self, rule: AutoModRule, config: RuleConfig) {
+        self.rules.insert(rule, config);
+    }
+
+    /// True if any rule is enabled. Scam links are checked separately.
+    pub fn is_enabled(&self) -> bool {
+        self.rules.values().any(|config| config.enabled)
+    }
+
+    /// How much history the enabled rules need.
+    pub fn longest_window(&self) -> TimeDelta {
+        self.rules
+            .values()
+            .filter(|config| config.enabled)
+            .map(|config| config.window)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Checks the member's latest message against each enabled rule, returning the first broken.
+    pub fn check(&self, history: &UserHistory) -> Option<Violation> {
+        let latest = history.latest()?;
+
+        AutoModRule::ALL.into_iter().find_map(|rule| {
+            let config = self.rule(rule);
+            if !config.enabled {
+                return None;
+            }
+
+            let count = match rule {
+                AutoModRule::Flood => history.count_since(latest.sent_at - config.window),
+                AutoModRule::Duplicate => history.duplicates_since(latest.sent_at - config.window),
+                AutoModRule::MassMention => latest.mentions,
+                AutoModRule::Invite => invite_count(&latest.content),
+            };
+
+            (count >= config.threshold.max(1) as usize).then_some(Violation {
+                rule,
+                action: config.action,
+                count,
+            })
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    pub rule: AutoModRule,
+    pub action: AutoModAction,
+    pub count: usize,
+}
+
+/// The parts of a message the rules look at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageSample {
+    pub content: String,
+    /// Users and roles mentioned, counting @everyone and @here as one.
+    pub mentions: usize,
+    pub sent_at: NaiveDateTime,
+}
+
+/// A member's recent messages in one guild, oldest first.
+#[derive(Debug, Clone, Default)]
+pub struct UserHistory {
+    messages: VecDeque<MessageSample>,
+}
+
+impl UserHistory {
+    /// Adds the message and forgets anything older than `keep` before it.
+    pub fn record(&mut self, sample: MessageSample, keep: TimeDelta) {
+        let cutoff = sample.sent_at - keep;
+
+        self.messages.push_back(sample);
+        while self
+            .messages
+            .front()
+            .is_some_and(|message| message.sent_at < cutoff)
+            || self.messages.len() > HISTORY_LIMIT
+        {
+            self.messages.pop_front();
+        }
+    }
+
+    /// Forgets every message, so one burst is only punished once.
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+
+    pub fn latest(&self) -> Option<&MessageSample> {
+        self.messages.back()
+    }
+
+    pub fn is_stale(&self, cutoff: NaiveDateTime) -> bool {
+        self.latest().is_none_or(|latest| latest.sent_at < cutoff)
+    }
+
+    fn count_since(&self, since: NaiveDateTime) -> usize {
+        self.messages
+            .iter()
+            .filter(|message| message.sent_at >= since)
+            .count()
+    }
+
+    /// Counts the messages since `since` with the same content as the latest, ignoring case and
+    /// surrounding whitespace. Messages without text, such as attachments, never count.
+    fn duplicates_since(&self, since: NaiveDateTime) -> usize {
+        let Some(latest) = self.latest() else {
+            return 0;
+        };
+
+        let content = normalise(&latest.content);
+        if content.is_empty() {
+            return 0;
+        }
+
+        self.messages
+            .iter()
+            .filter(|message| message.sent_at >= since && normalise(&message.content) == content)
+            .count()
+    }
+}
+
+fn normalise(content: &str) -> String {
+    content.trim().to_lowercase()
+}
+
+pub fn invite_count(content: &str) -> usize {
+    let content = content.to_lowercase();
+
+    INVITE_PATTERNS
+        .iter()
+        .map(|pattern| content.matches(pattern).count())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+
+    use super::{
+        invite_count, AutoModAction, AutoModConfig, AutoModRule, MessageSample, UserHistory,
+    };
+
+    fn at(seconds: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 2, 14)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            + TimeDelta::seconds(seconds)
+    }
+
+    fn sample(content: &str, mentions: usize, seconds: i64) -> MessageSample {
+        MessageSample {
+            content: content.to_string(),
+            mentions,
+            sent_at: at(seconds),
+        }
+    }
+
+    fn config(rules: &[AutoModRule]) -> AutoModConfig {
+        let mut config = AutoModConfig::default();
+        for &rule in rules {
+            let mut rule_config = config.rule(rule);
+            rule_config.enabled = true;
+            config.set_rule(rule, rule_config);
+        }
+        config
+    }
+
+    fn history(config: &AutoModConfig, samples: Vec<MessageSample>) -> UserHistory {
+        let mut history = UserHistory::default();
+        for sample in samples {
+            history.record(sample, config.longest_window());
+        }
+        history
+    }
+
+    #[test]
+    fn disabled_rules_never_trigger() {
+        let config = AutoModConfig::default();
+        let history = history(&config, vec![sample("discord.gg/abc @a @b @c @d @e", 5, 0)]);
+
+        assert!(!config.is_enabled());
+        assert_eq!(config.check(&history), None);
+    }
+
+    #[test]
+    fn flood_triggers_at_threshold_within_window() {
+        let config = config(&[AutoModRule::Flood]);
+        let samples = (0..5).map(|i| sample(&i.to_string(), 0, i)).collect();
+        let mut history = history(&config, samples);
+
+        assert_eq!(config.check(&history), None);
+
+        history.record(sample("5", 0, 5), config.longest_window());
+        let violation = config.check(&history).unwrap();
+        assert_eq!(violation.rule, AutoModRule::Flood);
+        assert_eq!(violation.count, 6);
+        assert_eq!(
+            violation.action,
+            AutoModAction::Timeout(TimeDelta::minutes(10))
+        );
+    }
+
+    #[test]
+    fn flood_ignores_messages_outside_window() {
+        let config = config(&[AutoModRule::Flood]);
+        let samples = (0..6).map(|i| sample(&i.to_string(), 0, i * 3)).collect();
+        let history = history(&config, samples);
+
+        assert_eq!(config.check(&history), None);
+    }
+
+    #[test]
+    fn duplicates_ignore_case_and_whitespace() {
+        let config = config(&[AutoModRule::Duplicate]);
+        let history = history(
+            &config,
+            vec![
+                sample("Free nitro", 0, 0),
+                sample("something else", 0, 10),
+                sample("free NITRO ", 0, 20),
+                sample("  free nitro", 0, 30),
+            ],
+        );
+
+        let violation = config.check(&history).unwrap();
+        assert_eq!(violation.rule, AutoModRule::Duplicate);
+        assert_eq!(violation.count, 3);
+    }
+
+    #[test]
+    fn empty_messages_are_not_duplicates() {
+        let config = config(&[AutoModRule::Duplicate]);
+        let history = history(
+            &config,
+            vec![sample("", 0, 0), sample("", 0, 1), sample("", 0, 2)],
+        );
+
+        assert_eq!(config.check(&history), None);
+    }
+
+    #[test]
+    fn mass_mentions_use_the_latest_message() {
+        let config = config(&[AutoModRule::MassMention]);
+
+        let history_below = history(&config, vec![sample("hi", 4, 0)]);
+        assert_eq!(config.check(&history_below), None);
+
+        let history_at = history(&config, vec![sample("hi", 5, 0)]);
+        assert_eq!(
+            config.check(&history_at).unwrap().rule,
+            AutoModRule::MassMention
+        );
+    }
+
+    #[test]
+    fn invites_are_detected_in_any_case() {
+        assert_eq!(invite_count("join discord.gg/abc"), 1);
+        assert_eq!(invite_count("https://DISCORD.com/invite/abc"), 1);
+        assert_eq!(invite_count("discordapp.com/invite/a and discord.gg/b"), 2);
+        assert_eq!(invite_count("discord.com/channels/1/2"), 0);
+    }
+
+    #[test]
+    fn message_rules_are_checked_before_history_rules() {
+        let config = config(&[AutoModRule::Flood, AutoModRule::Invite]);
+        let samples = (0..6).map(|i| sample("discord.gg/abc", 0, i)).collect();
+        let history = history(&config, samples);
+
+        assert_eq!(config.check(&history).unwrap().rule, AutoModRule::Invite);
+    }
+
+    #[test]
+    fn cleared_history_does_not_trigger_again() {
+        let config = config(&[AutoModRule::Flood]);
+        let samples = (0..6).map(|i| sample(&i.to_string(), 0, i)).collect();
+        let mut history = history(&config, samples);
+
+        assert!(config.check(&history).is_some());
+
+        history.clear();
+        history.record(sample("6", 0, 6), config.longest_window());
+        assert_eq!(config.check(&history), None);
+    }
+
+    #[test]
+    fn rule_names_round_trip() {
+        for rule in AutoModRule::ALL {
+            assert_eq!(AutoModRule::from_name(rule.name()), Some(rule));
+        }
+    }
+}
@@ -0,0 +1,439 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use lazy_static::lazy_static;
+use serenity::all::{
+    ButtonStyle, ChannelId, ComponentInteraction, Context, CreateActionRow, CreateButton,
+    CreateEmbed, CreateEmbedFooter, CreateInvite, CreateMessage, EditInteractionResponse, GuildId,
+    Mentionable, Message, Timestamp, User, UserId,
+};
+use sqlx::PgPool;
+use url::Url;
+
+use crate::global_commands::slash_commands::scam::{soft_ban_scammer, SCAM_REASON};
+use crate::modules::moderation::mod_log::send_mod_log;
+use crate::modules::moderation::{void_case, InfractionRow};
+use crate::{Error, Result};
+
+/// Domains owned by the services scammers imitate. Subdomains are trusted too.
+const OFFICIAL_DOMAINS: [&str; 13] = [
+    "discord.com",
+    "discord.gg",
+    "discord.gift",
+    "discord.media",
+    "discord.new",
+    "discord.dev",
+    "discord.co",
+    "discordapp.com",
+    "discordapp.net",
+    "discordstatus.com",
+    "steamcommunity.com",
+    "steampowered.com",
+    "steamstatic.com",
+];
+
+/// Legitimate sites whose names are a near miss of a brand. Subdomains are trusted too.
+const NOT_LOOKALIKES: [&str; 1] = ["discords.com"];
+
+/// The names lookalike domains are compared against.
+const BRANDS: [&str; 4] = ["discord", "discordapp", "steamcommunity", "steampowered"];
+
+/// Words that make a domain containing a brand name suspicious, such as `discord-nitro.com`.
+const BAIT_WORDS: [&str; 8] = [
+    "nitro", "gift", "free", "claim", "airdrop", "promo", "verify", "trade",
+];
+
+lazy_static! {
+    static ref BLOCKLIST: HashSet<&'static str> =
+        include_str!("../../../blocklists/scam_domains.txt")
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScamReason {
+    Blocklisted,
+    /// Imitates the named brand.
+    Lookalike(&'static str),
+}
+
+impl Display for ScamReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Blocklisted => write!(f, "on the blocklist"),
+            Self::Lookalike(brand) => write!(f, "looks like {}", brand),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScamLink {
+    pub domain: String,
+    pub reason: ScamReason,
+}
+
+/// The hosts of every http(s) link in the message, including links in markdown and `<...>`.
+pub fn link_domains(content: &str) -> Vec<String> {
+    content
+        .split_whitespace()
+        .filter_map(|word| {
+            let start = word.find("http://").or_else(|| word.find("https://"))?;
+            let link = word[start..].trim_end_matches([')', '>', ']', '|', '*', '_', '~']);
+
+            let domain = Url::parse(link).ok()?.host_str()?.to_lowercase();
+            Some(domain.trim_end_matches('.').to_string())
+        })
+        .collect()
+}
+
+/// The domain followed by each of its parents, such as `a.b.com`, `b.com` and `com`.
+fn parents(domain: &str) -> impl Iterator<Item = &str> {
+    std::iter::successors(Some(domain), |domain| {
+        domain.split_once('.').map(|(_, parent)| parent)
+    })
+}
+
+/// Undoes the character swaps scammers use to pass a glance, such as `dlscord` or `stearn`.
+fn normalise(label: &str) -> String {
+    label
+        .replace('-', "")
+        .replace("rn", "m")
+        .replace("vv", "w")
+        .replace("cl", "d")
+        .chars()
+        .map(|c| match c {
+            '1' | 'l' | '!' | '|' => 'i',
+            '0' => 'o',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            c => c,
+        })
+        .collect()
+}
+
+/// True if `a` becomes `b` with at most one inserted, removed or swapped adjacent character.
+fn within_one_edit(a: &str, b: &str) -> bool {
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+
+    let (shorter, longer) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+
+    match longer.len() - shorter.len() {
+        0 => {
+            let diffs = (0..a.len()).filter(|&i| a[i] != b[i]).collect::<Vec<_>>();
+            match diffs.as_slice() {
+                [] => true,
+                [i, j] => *j == i + 1 && a[*i] == b[*j] && a[*j] == b[*i],
+                _ => false,
+            }
+        }
+        1 => {
+            let prefix = shorter
+                .iter()
+                .zip(longer)
+                .take_while(|(a, b)| a == b)
+                .count();
+            shorter[prefix..] == longer[prefix + 1..]
+        }
+        _ => false,
+    }
+}
+
+fn lookalike(domain: &str) -> Option<&'static str> {
+    let labels = domain.split('.').collect::<Vec<_>>();
+    let label = *labels.iter().rev().nth(1)?;
+
+    let normalised = normalise(label);
+
+    BRANDS.into_iter().find(|brand| {
+        within_one_edit(&normalised, brand)
+            || (normalised.contains(brand) && BAIT_WORDS.iter().any(|word| label.contains(word)))
+    })
+}
+
+pub fn classify(domain: &str) -> Option<ScamReason> {
+    if parents(domain)
+        .any(|parent| OFFICIAL_DOMAINS.contains(&parent) || NOT_LOOKALIKES.contains(&parent))
+    {
+        return None;
+    }
+
+    if parents(domain).any(|parent| BLOCKLIST.contains(parent)) {
+        return Some(ScamReason::Blocklisted);
+    }
+
+    lookalike(domain).map(ScamReason::Lookalike)
+}
+
+pub fn find_scam_link(content: &str) -> Option<ScamLink> {
+    link_domains(content).into_iter().find_map(|domain| {
+        let reason = classify(&domain)?;
+        Some(ScamLink { domain, reason })
+    })
+}
+
+fn undo_custom_id(case_id: i32, user_id: UserId, channel_id: ChannelId) -> String {
+    format!("scam_undo_{}_{}_{}", case_id, user_id, channel_id)
+}
+
+fn parse_undo_custom_id(custom_id: &str) -> Option<(i32, UserId, ChannelId)> {
+    let mut parts = custom_id.strip_prefix("scam_undo_")?.split('_');
+
+    let case_id = parts.next()?.parse().ok()?;
+    let user_id = parts.next()?.parse().ok()?;
+    let channel_id = parts.next()?.parse().ok()?;
+
+    Some((case_id, user_id, channel_id))
+}
+
+/// Deletes the message, soft bans its author the same way `/scam` does and alerts the mod log
+/// with a button to undo it.
+pub async fn remove_scammer(
+    ctx: &Context,
+    msg: &Message,
+    pool: &PgPool,
+    guild_id: GuildId,
+    link: ScamLink,
+) -> Result<()> {
+    let _ = msg.delete(ctx).await;
+
+    let bot = User::from(ctx.cache.current_user().clone());
+
+    // Members above the bot can't be banned, but moderators still need to hear about the link.
+    let outcome = soft_ban_scammer(ctx, pool, guild_id, &msg.author, &bot, SCAM_REASON, None).await;
+    if let Err(e) = &outcome {
+        eprintln!("Error soft banning scammer {}: {:?}", msg.author.id, e);
+    }
+
+    // A code block keeps the link from being clicked from the mod log.
+    let content = msg
+        .content
+        .replace('`', "'")
+        .chars()
+        .take(1000)
+        .collect::<String>();
+
+    let mut embed = CreateEmbed::new()
+        .title("Scam Link Detected")
+        .colour(0xED4245)
+        .field(
+            "Author",
+            format!("{} ({})", msg.author.mention(), msg.author.id),
+            true,
+        )
+        .field("Channel", msg.channel_id.mention().to_string(), true)
+        .field(
+            "Domain",
+            format!("`{}` ({})", link.domain, link.reason),
+            false,
+        )
+        .field("Message", format!("```\n{}\n```", content), false)
+        .timestamp(Timestamp::now());
+
+    let message = match outcome {
+        Ok(outcome) => {
+            embed = embed.footer(CreateEmbedFooter::new(format!(
+                "Case #{} | Undo if this was a mistake",
+                outcome.case_id
+            )));
+
+            let undo = CreateButton::new(undo_custom_id(
+                outcome.case_id,
+                msg.author.id,
+                msg.channel_id,
+            ))
+            .label("Undo")
+            .style(ButtonStyle::Secondary);
+
+            CreateMessage::new()
+                .embed(embed)
+                .components(vec![CreateActionRow::Buttons(vec![undo])])
+        }
+        Err(_) => {
+            embed = embed.field(
+                "Action",
+                "Could not ban. Check the bot's Ban Members permission and that its role is above the author's.",
+                false,
+            );
+
+            CreateMessage::new().embed(embed)
+        }
+    };
+
+    send_mod_log(ctx, pool, guild_id, message).await
+}
+
+/// Voids the soft ban and sends the member an invite back.
+pub async fn undo(ctx: &Context, interaction: &ComponentInteraction, pool: &PgPool) -> Result<()> {
+    interaction.defer(ctx).await.unwrap();
+
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    if !interaction
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.ban_members())
+    {
+        return Err(Error::MissingPermissions);
+    }
+
+    let Some((case_id, user_id, channel_id)) = parse_undo_custom_id(&interaction.data.custom_id)
+    else {
+        return Ok(());
+    };
+
+    // The soft ban already lifted itself. A ban still in place was added by a moderator since, so
+    // it's left alone.
+    if let Ok(mut case) = InfractionRow::get(pool, guild_id, case_id).await {
+        void_case(
+            pool,
+            &mut case,
+            &interaction.user,
+            Some("False positive scam link detection"),
+        )
+        .await?;
+    }
+
+    let guild_name = guild_id.to_partial_guild(ctx).await.unwrap().name;
+
+    if let Ok(invite) = channel_id
+        .create_invite(
+            ctx,
+            CreateInvite::new()
+                .max_age(60 * 60 * 24 * 7)
+                .max_uses(1)
+                .unique(true),
+        )
+        .await
+    {
+        let _ = user_id
+            .direct_message(
+                ctx,
+                CreateMessage::new().content(format!(
+                    "You were removed from {} because a message from your account looked like a scam. A moderator has reviewed it and you're welcome back: {}",
+                    guild_name,
+                    invite.url()
+                )),
+            )
+            .await;
+    }
+
+    let embed = interaction
+        .message
+        .embeds
+        .first()
+        .cloned()
+        .map(CreateEmbed::from)
+        .unwrap_or_default()
+        .colour(0x57F287)
+        .field(
+            "Undone",
+            format!("By {}", interaction.user.mention()),
+            false,
+        );
+
+    interaction
+        .edit_response(
+            ctx,
+            EditInteractionResponse::new()
+                .embed(embed)
+                .components(Vec::new()),
+        )
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify, find_scam_link, link_domains, within_one_edit, ScamReason};
+
+    #[test]
+    fn finds_domains_in_plain_markdown_and_suppressed_links() {
+        assert_eq!(
+            link_domains(
+                "see https://Example.com/a and [here](http://foo.bar/x) or <https://baz.io>"
+            ),
+            vec!["example.com", "foo.bar", "baz.io"]
+        );
+        assert!(link_domains("no links here, just example.com").is_empty());
+    }
+
+    #[test]
+    fn official_domains_and_subdomains_are_trusted() {
+        assert_eq!(classify("discord.com"), None);
+        assert_eq!(classify("cdn.discordapp.com"), None);
+        assert_eq!(classify("store.steampowered.com"), None);
+    }
+
+    #[test]
+    fn blocklisted_domains_and_subdomains_match() {
+        assert_eq!(classify("discord-gift.ru"), Some(ScamReason::Blocklisted));
+        assert_eq!(
+            classify("login.stearncommunity.com"),
+            Some(ScamReason::Blocklisted)
+        );
+    }
+
+    #[test]
+    fn lookalikes_match() {
+        for domain in [
+            "dlscord.com",
+            "discorcl.gg",
+            "dicsord.com",
+            "discordd.net",
+            "d1scord.app",
+            "steamcommunnity.com",
+            "discord-nitro.shop",
+            "discord.ru",
+        ] {
+            assert!(
+                matches!(classify(domain), Some(ScamReason::Lookalike(_))),
+                "{} should look like a brand",
+                domain
+            );
+        }
+    }
+
+    #[test]
+    fn similar_but_unrelated_domains_are_ignored() {
+        for domain in [
+            "discord.fandom.com",
+            "discover.com",
+            "discard.io",
+            "disco.com",
+            "discordbotlist.com",
+            "discords.com",
+            "www.discords.com",
+            "youtube.com",
+            "steamdb.info",
+        ] {
+            assert_eq!(classify(domain), None, "{} should not match", domain);
+        }
+    }
+
+    #[test]
+    fn one_edit_allows_insertions_removals_and_swaps_only() {
+        assert!(within_one_edit("discord", "discord"));
+        assert!(within_one_edit("discrd", "discord"));
+        assert!(within_one_edit("disscord", "discord"));
+        assert!(within_one_edit("dicsord", "discord"));
+        assert!(!within_one_edit("discard", "discord"));
+        assert!(!within_one_edit("dsicrod", "discord"));
+    }
+
+    #[test]
+    fn find_scam_link_reports_the_first_match() {
+        let link =
+            find_scam_link("legit https://discord.com then https://dlscord.com/gift").unwrap();
+
+        assert_eq!(link.domain, "dlscord.com");
+        assert_eq!(link.reason, ScamReason::Lookalike("discord"));
+    }
+}
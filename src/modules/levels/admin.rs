@@ -0,0 +1,178 @@
+use std::fmt::Display;
+
+use serenity::all::{
+    CommandInteraction, Context, EditInteractionResponse, GuildId, Mentionable, Permissions,
+    ResolvedOption, ResolvedValue, UserId,
+};
+use sqlx::{PgExecutor, PgPool};
+use zayden_core::parse_options;
+
+use crate::modules::check_permissions;
+use crate::sqlx_lib::GuildTable;
+use crate::{Error, Result};
+
+use super::message_command::update_member_roles;
+use super::{get_user_level_data, update_user_xp};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XpAdminAction {
+    Give,
+    Take,
+    Set,
+    Reset,
+}
+
+impl XpAdminAction {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "give" => Some(Self::Give),
+            "take" => Some(Self::Take),
+            "set" => Some(Self::Set),
+            "reset" => Some(Self::Reset),
+            _ => None,
+        }
+    }
+
+    /// The member's new total XP after applying the action.
+    pub fn apply(&self, total_xp: i32, amount: i32) -> i32 {
+        match self {
+            Self::Give => total_xp.saturating_add(amount),
+            Self::Take => total_xp.saturating_sub(amount).max(0),
+            Self::Set => amount.max(0),
+            Self::Reset => 0,
+        }
+    }
+}
+
+impl Display for XpAdminAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Self::Give => "give",
+            Self::Take => "take",
+            Self::Set => "set",
+            Self::Reset => "reset",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+pub struct XpAuditEntry<'a> {
+    pub guild_id: GuildId,
+    pub user_id: UserId,
+    pub moderator_id: UserId,
+    pub action: XpAdminAction,
+    pub amount: Option<i32>,
+    pub old_total_xp: i32,
+    pub new_total_xp: i32,
+    pub reason: Option<&'a str>,
+}
+
+pub struct XpAuditTable;
+
+impl XpAuditTable {
+    pub async fn insert(pool: impl PgExecutor<'_>, entry: &XpAuditEntry<'_>) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO xp_audit (guild_id, user_id, moderator_id, action, amount, old_total_xp, new_total_xp, reason) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+            entry.guild_id.get() as i64,
+            entry.user_id.get() as i64,
+            entry.moderator_id.get() as i64,
+            entry.action.to_string(),
+            entry.amount,
+            entry.old_total_xp,
+            entry.new_total_xp,
+            entry.reason
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+}
+
+pub async fn admin(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    mut options: Vec<ResolvedOption<'_>>,
+    pool: &PgPool,
+) -> Result<()> {
+    interaction.defer_ephemeral(ctx).await.unwrap();
+
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    check_permissions(interaction, Permissions::MANAGE_GUILD)?;
+
+    let command = options.remove(0);
+    let ResolvedValue::SubCommand(options) = command.value else {
+        unreachable!("Subcommand is required");
+    };
+    let mut options = parse_options(options);
+
+    let action = XpAdminAction::from_name(command.name).expect("Unknown subcommand");
+
+    let Some(ResolvedValue::User(user, _)) = options.remove("user") else {
+        unreachable!("User option is required");
+    };
+
+    let amount = match options.remove("amount") {
+        Some(ResolvedValue::Integer(amount)) => Some(amount.clamp(0, i32::MAX as i64) as i32),
+        _ => None,
+    };
+
+    let reason = match options.remove("reason") {
+        Some(ResolvedValue::String(reason)) => Some(reason),
+        _ => None,
+    };
+
+    let member = guild_id
+        .member(ctx, user.id)
+        .await
+        .map_err(|_| Error::MemberNotFound)?;
+
+    let policy = GuildTable::get_xp_policy(pool, guild_id).await?;
+    let level_data = get_user_level_data(pool, guild_id, user.id).await?;
+
+    let old_total_xp = level_data.total_xp;
+    let new_total_xp = action.apply(old_total_xp, amount.unwrap_or_default());
+
+    let (level, xp) = policy.curve.level_from_total_xp(new_total_xp);
+
+    // The change and its audit row land together or not at all.
+    let mut tx = pool.begin().await.unwrap();
+
+    update_user_xp(&mut *tx, guild_id, &member.user, xp, new_total_xp, level).await?;
+
+    XpAuditTable::insert(
+        &mut *tx,
+        &XpAuditEntry {
+            guild_id,
+            user_id: user.id,
+            moderator_id: interaction.user.id,
+            action,
+            amount,
+            old_total_xp,
+            new_total_xp,
+            reason,
+        },
+    )
+    .await?;
+
+    tx.commit().await.unwrap();
+
+    update_member_roles(ctx, pool, &member, level).await?;
+
+    let content = format!(
+        "{}'s total XP went from {} to {}. They are now level {}.",
+        user.mention(),
+        old_total_xp,
+        new_total_xp,
+        level
+    );
+
+    interaction
+        .edit_response(ctx, EditInteractionResponse::new().content(content))
+        .await
+        .unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,366 @@
+use serenity::all::{
+    ChannelId, CommandInteraction, Context, CreateAllowedMentions, CreateEmbed, CreateMessage,
+    EditInteractionResponse, GuildId, Member, Mentionable, Permissions, ResolvedOption,
+    ResolvedValue, RoleId,
+};
+use sqlx::PgPool;
+use zayden_core::parse_options;
+
+use crate::modules::check_permissions;
+use crate::sqlx_lib::GuildTable;
+use crate::{Error, Result};
+
+use super::level_roles::LevelRolesTable;
+
+const DEFAULT_TEMPLATE: &str = "Congratulations {user}, you just reached level {level}!";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LevelUpMode {
+    #[default]
+    Off,
+    /// The channel the member earned the XP in.
+    SameChannel,
+    /// The guild's configured announcement channel.
+    Channel,
+    Dm,
+}
+
+impl LevelUpMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::SameChannel => "same_channel",
+            Self::Channel => "channel",
+            Self::Dm => "dm",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(Self::Off),
+            "same_channel" => Some(Self::SameChannel),
+            "channel" => Some(Self::Channel),
+            "dm" => Some(Self::Dm),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LevelUpSettings {
+    pub mode: LevelUpMode,
+    pub channel_id: Option<ChannelId>,
+    pub template: Option<String>,
+    /// Only these levels are announced. Empty means every level.
+    pub milestones: Vec<i32>,
+}
+
+impl LevelUpSettings {
+    /// Whether going from `old_level` to `new_level` passes a level that should be announced.
+    pub fn should_announce(&self, old_level: i32, new_level: i32) -> bool {
+        self.mode != LevelUpMode::Off
+            && new_level > old_level
+            && (self.milestones.is_empty()
+                || self
+                    .milestones
+                    .iter()
+                    .any(|&level| level > old_level && level <= new_level))
+    }
+
+    pub fn template(&self) -> &str {
+        self.template.as_deref().unwrap_or(DEFAULT_TEMPLATE)
+    }
+}
+
+/// Fills in `{user}`, `{username}`, `{level}` and `{role}`. `{role}` is empty when no level role
+/// was earned.
+pub fn render_template(
+    template: &str,
+    member: &Member,
+    level: i32,
+    role: Option<RoleId>,
+) -> String {
+    template
+        .replace("{user}", &member.mention().to_string())
+        .replace("{username}", member.display_name())
+        .replace("{level}", &level.to_string())
+        .replace(
+            "{role}",
+            &role
+                .map(|role| role.mention().to_string())
+                .unwrap_or_default(),
+        )
+}
+
+struct LevelUpSettingsRow {
+    level_up_mode: String,
+    level_up_channel_id: Option<i64>,
+    level_up_template: Option<String>,
+    level_up_milestones: Vec<i32>,
+}
+
+impl From<LevelUpSettingsRow> for LevelUpSettings {
+    fn from(row: LevelUpSettingsRow) -> Self {
+        Self {
+            mode: LevelUpMode::from_name(&row.level_up_mode).unwrap_or_default(),
+            channel_id: row.level_up_channel_id.map(|id| ChannelId::new(id as u64)),
+            template: row.level_up_template,
+            milestones: row.level_up_milestones,
+        }
+    }
+}
+
+impl GuildTable {
+    pub async fn get_level_up_settings(pool: &PgPool, id: GuildId) -> Result<LevelUpSettings> {
+        let row = sqlx::query_as!(
+            LevelUpSettingsRow,
+            "SELECT level_up_mode, level_up_channel_id, level_up_template, level_up_milestones FROM guilds WHERE id = $1",
+            id.get() as i64
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        Ok(row.map(LevelUpSettings::from).unwrap_or_default())
+    }
+
+    pub async fn save_level_up_settings(
+        pool: &PgPool,
+        id: GuildId,
+        settings: &LevelUpSettings,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO guilds (id, level_up_mode, level_up_channel_id, level_up_template, level_up_milestones)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (id) DO UPDATE
+             SET level_up_mode = $2, level_up_channel_id = $3, level_up_template = $4, level_up_milestones = $5",
+            id.get() as i64,
+            settings.mode.name(),
+            settings.channel_id.map(|id| id.get() as i64),
+            settings.template,
+            &settings.milestones
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+}
+
+pub async fn announce_level_up(
+    ctx: &Context,
+    pool: &PgPool,
+    member: &Member,
+    channel_id: ChannelId,
+    old_level: i32,
+    new_level: i32,
+) -> Result<()> {
+    let settings = GuildTable::get_level_up_settings(pool, member.guild_id).await?;
+
+    if !settings.should_announce(old_level, new_level) {
+        return Ok(());
+    }
+
+    let role = LevelRolesTable::get_guild_rows(pool, member.guild_id)
+        .await?
+        .into_iter()
+        .filter(|row| row.level > old_level && row.level <= new_level)
+        .max_by_key(|row| row.level)
+        .map(|row| row.role_id());
+
+    // Display names are user controlled, so only the member may be pinged. `{role}` still renders as
+    // a mention without notifying everyone who holds the role.
+    let message = CreateMessage::new()
+        .content(render_template(
+            settings.template(),
+            member,
+            new_level,
+            role,
+        ))
+        .allowed_mentions(CreateAllowedMentions::new().users([member.user.id]));
+
+    let channel_id = match settings.mode {
+        LevelUpMode::Off => return Ok(()),
+        LevelUpMode::SameChannel => channel_id,
+        LevelUpMode::Channel => settings.channel_id.unwrap_or(channel_id),
+        LevelUpMode::Dm => {
+            // Members with DMs closed just don't get the message.
+            let _ = member.user.direct_message(ctx, message).await;
+            return Ok(());
+        }
+    };
+
+    // A deleted channel or missing permissions shouldn't stop XP from being awarded.
+    if let Err(e) = channel_id.send_message(ctx, message).await {
+        eprintln!("Error announcing level up in {}: {:?}", channel_id, e);
+    }
+
+    Ok(())
+}
+
+fn parse_milestones(milestones: &str) -> Result<Vec<i32>> {
+    if milestones.trim().eq_ignore_ascii_case("all") {
+        return Ok(Vec::new());
+    }
+
+    let mut milestones = milestones
+        .split(',')
+        .map(str::trim)
+        .filter(|level| !level.is_empty())
+        .map(|level| level.parse::<i32>().map_err(|_| Error::InvalidMilestones))
+        .collect::<Result<Vec<_>>>()?;
+
+    milestones.sort_unstable();
+    milestones.dedup();
+
+    Ok(milestones)
+}
+
+pub async fn announcements(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    options: Vec<ResolvedOption<'_>>,
+    pool: &PgPool,
+) -> Result<()> {
+    interaction.defer_ephemeral(ctx).await.unwrap();
+
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    let mut options = parse_options(options);
+    let mut settings = GuildTable::get_level_up_settings(pool, guild_id).await?;
+
+    if !options.is_empty() {
+        check_permissions(interaction, Permissions::MANAGE_GUILD)?;
+
+        if let Some(ResolvedValue::String(mode)) = options.remove("mode") {
+            settings.mode = LevelUpMode::from_name(mode).unwrap_or_default();
+        }
+        if let Some(ResolvedValue::Channel(channel)) = options.remove("channel") {
+            settings.channel_id = Some(channel.id);
+        }
+        if let Some(ResolvedValue::String(template)) = options.remove("template") {
+            settings.template = Some(template.to_string());
+        }
+        if let Some(ResolvedValue::String(milestones)) = options.remove("milestones") {
+            settings.milestones = parse_milestones(milestones)?;
+        }
+
+        GuildTable::save_level_up_settings(pool, guild_id, &settings).await?;
+    }
+
+    let channel = match settings.channel_id {
+        Some(channel_id) => channel_id.mention().to_string(),
+        None => String::from("Not set"),
+    };
+
+    let milestones = if settings.milestones.is_empty() {
+        String::from("Every level")
+    } else {
+        settings
+            .milestones
+            .iter()
+            .map(|level| level.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let embed = CreateEmbed::new()
+        .title("Level-up Announcements")
+        .description(format!(
+            "Mode: {}\nChannel: {}\nLevels: {}\nTemplate: {}",
+            settings.mode.name(),
+            channel,
+            milestones,
+            settings.template()
+        ));
+
+    interaction
+        .edit_response(ctx, EditInteractionResponse::new().embed(embed))
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::all::{Member, RoleId, UserId};
+
+    use super::{
+        parse_milestones, render_template, LevelUpMode, LevelUpSettings, DEFAULT_TEMPLATE,
+    };
+
+    fn member() -> Member {
+        let mut member = Member::default();
+        member.user.id = UserId::new(1);
+        member.user.name = String::from("oscar");
+        member.nick = Some(String::from("Oscar"));
+        member
+    }
+
+    fn settings(milestones: Vec<i32>) -> LevelUpSettings {
+        LevelUpSettings {
+            mode: LevelUpMode::SameChannel,
+            milestones,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        let rendered = render_template(
+            "{user} ({username}) reached {level} and earned {role}",
+            &member(),
+            5,
+            Some(RoleId::new(2)),
+        );
+
+        assert_eq!(rendered, "<@1> (Oscar) reached 5 and earned <@&2>");
+    }
+
+    #[test]
+    fn missing_role_renders_empty() {
+        let rendered = render_template("Level {level}{role}", &member(), 3, None);
+
+        assert_eq!(rendered, "Level 3");
+        assert_eq!(
+            render_template(DEFAULT_TEMPLATE, &member(), 2, None),
+            "Congratulations <@1>, you just reached level 2!"
+        );
+    }
+
+    #[test]
+    fn off_never_announces() {
+        let settings = LevelUpSettings::default();
+
+        assert!(!settings.should_announce(1, 2));
+    }
+
+    #[test]
+    fn every_level_without_milestones() {
+        let settings = settings(Vec::new());
+
+        assert!(settings.should_announce(1, 2));
+        assert!(!settings.should_announce(2, 2));
+        assert!(!settings.should_announce(3, 2));
+    }
+
+    #[test]
+    fn milestones_passed_in_one_jump() {
+        let settings = settings(vec![5, 10]);
+
+        assert!(!settings.should_announce(1, 4));
+        assert!(settings.should_announce(4, 5));
+        assert!(settings.should_announce(3, 7));
+        assert!(!settings.should_announce(5, 9));
+        assert!(settings.should_announce(9, 12));
+    }
+
+    #[test]
+    fn parses_milestones() {
+        assert_eq!(parse_milestones("10, 5,,25, 5").unwrap(), vec![5, 10, 25]);
+        assert!(parse_milestones(" All ").unwrap().is_empty());
+        assert!(parse_milestones("5, ten").is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use serenity::all::{
+    ChannelId, CommandInteraction, Context, EditInteractionResponse, GuildId, Mentionable,
+    Permissions, ResolvedOption, ResolvedValue,
+};
+use serenity::prelude::TypeMapKey;
+use sqlx::PgPool;
+use zayden_core::parse_options;
+
+use crate::modules::check_permissions;
+use crate::sqlx_lib::GuildTable;
+use crate::{Error, Result};
+
+pub struct XpBlockedChannels;
+
+impl TypeMapKey for XpBlockedChannels {
+    type Value = HashMap<GuildId, Vec<ChannelId>>;
+}
+
+impl XpBlockedChannels {
+    pub async fn get(ctx: &Context, pool: &PgPool, guild_id: GuildId) -> Result<Vec<ChannelId>> {
+        {
+            let data = ctx.data.read().await;
+            if let Some(channel_ids) = data
+                .get::<XpBlockedChannels>()
+                .and_then(|cache| cache.get(&guild_id))
+            {
+                return Ok(channel_ids.clone());
+            }
+        }
+
+        let channel_ids = GuildTable::get_xp_blocked_channels(pool, guild_id).await?;
+
+        let mut data = ctx.data.write().await;
+        if let Some(cache) = data.get_mut::<XpBlockedChannels>() {
+            cache.insert(guild_id, channel_ids.clone());
+        }
+
+        Ok(channel_ids)
+    }
+
+    async fn invalidate(ctx: &Context, guild_id: GuildId) {
+        let mut data = ctx.data.write().await;
+        if let Some(cache) = data.get_mut::<XpBlockedChannels>() {
+            cache.remove(&guild_id);
+        }
+    }
+}
+
+impl GuildTable {
+    pub async fn get_xp_blocked_channels(pool: &PgPool, id: GuildId) -> Result<Vec<ChannelId>> {
+        let row = sqlx::query!(
+            "SELECT xp_blocked_channels FROM guilds WHERE id = $1",
+            id.get() as i64
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        let channel_ids = row
+            .map(|row| row.xp_blocked_channels)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|id| ChannelId::new(id as u64))
+            .collect();
+
+        Ok(channel_ids)
+    }
+
+    pub async fn add_xp_blocked_channel(
+        pool: &PgPool,
+        id: GuildId,
+        channel_id: ChannelId,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO guilds (id, xp_blocked_channels) VALUES ($1, ARRAY[$2::BIGINT])
+             ON CONFLICT (id) DO UPDATE
+             SET xp_blocked_channels = array_append(guilds.xp_blocked_channels, $2)
+             WHERE NOT $2 = ANY(guilds.xp_blocked_channels)",
+            id.get() as i64,
+            channel_id.get() as i64
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+
+    pub async fn remove_xp_blocked_channel(
+        pool: &PgPool,
+        id: GuildId,
+        channel_id: ChannelId,
+    ) -> Result<()> {
+        sqlx::query!(
+            "UPDATE guilds SET xp_blocked_channels = array_remove(xp_blocked_channels, $2) WHERE id = $1",
+            id.get() as i64,
+            channel_id.get() as i64
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+}
+
+/// Returns true if the channel, its parent channel or its category is blocked.
+pub async fn is_xp_blocked(
+    ctx: &Context,
+    pool: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Result<bool> {
+    let blocked = XpBlockedChannels::get(ctx, pool, guild_id).await?;
+
+    if blocked.is_empty() {
+        return Ok(false);
+    }
+
+    let blocked = channel_ancestry(ctx, guild_id, channel_id)
+        .await
+        .iter()
+        .any(|id| blocked.contains(id));
+
+    Ok(blocked)
+}
+
+/// The channel followed by its parents. Threads yield the thread, the channel
+/// it was created in and that channel's category.
+pub async fn channel_ancestry(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Vec<ChannelId> {
+    let mut ancestry = vec![channel_id];
+
+    while ancestry.len() < 3 {
+        match parent_id(ctx, guild_id, ancestry[ancestry.len() - 1]).await {
+            Some(parent_id) => ancestry.push(parent_id),
+            None => break,
+        }
+    }
+
+    ancestry
+}
+
+async fn parent_id(ctx: &Context, guild_id: GuildId, channel_id: ChannelId) -> Option<ChannelId> {
+    let cached = ctx.cache.guild(guild_id).and_then(|guild| {
+        guild
+            .channels
+            .get(&channel_id)
+            .or_else(|| guild.threads.iter().find(|thread| thread.id == channel_id))
+            .map(|channel| channel.parent_id)
+    });
+
+    match cached {
+        Some(parent_id) => parent_id,
+        None => channel_id.to_channel(ctx).await.ok()?.guild()?.parent_id,
+    }
+}
+
+pub async fn block_channel(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    options: Vec<ResolvedOption<'_>>,
+    pool: &PgPool,
+    block: bool,
+) -> Result<()> {
+    interaction.defer_ephemeral(ctx).await.unwrap();
+
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    check_permissions(interaction, Permissions::MANAGE_CHANNELS)?;
+
+    let mut options = parse_options(options);
+
+    let Some(ResolvedValue::Channel(channel)) = options.remove("channel") else {
+        unreachable!("Channel option is required");
+    };
+
+    let content = if block {
+        GuildTable::add_xp_blocked_channel(pool, guild_id, channel.id).await?;
+        format!(
+            "Members will no longer earn XP in {}.",
+            channel.id.mention()
+        )
+    } else {
+        GuildTable::remove_xp_blocked_channel(pool, guild_id, channel.id).await?;
+        format!("Members can now earn XP in {}.", channel.id.mention())
+    };
+
+    XpBlockedChannels::invalidate(ctx, guild_id).await;
+
+    interaction
+        .edit_response(ctx, EditInteractionResponse::new().content(content))
+        .await
+        .unwrap();
+
+    Ok(())
+}
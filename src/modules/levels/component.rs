@@ -1,84 +1,82 @@
 use async_trait::async_trait;
 use serenity::all::{
-    ComponentInteraction, Context, CreateEmbed, CreateEmbedFooter, EditInteractionResponse,
-    MessageInteractionMetadata,
+    ComponentInteraction, ComponentInteractionDataKind, Context, EditInteractionResponse,
 };
 use sqlx::{PgPool, Postgres};
 use zayden_core::Component;
 
 use crate::{Error, Result};
 
-use super::{get_user_row_number, get_users, Levels};
-
-const LIMIT: i64 = 10;
+use super::leaderboard::{
+    get_user_page, jump_modal, leaderboard_page, LeaderboardSort, LeaderboardState,
+};
+use super::Levels;
 
 #[async_trait]
 impl Component<Error, Postgres> for Levels {
     async fn run(ctx: &Context, interaction: &ComponentInteraction, pool: &PgPool) -> Result<()> {
-        interaction.defer(ctx).await.unwrap();
+        let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
 
-        let action = interaction.data.custom_id.split_once('_').unwrap().1;
+        let Some((action, mut state)) = LeaderboardState::parse(&interaction.data.custom_id) else {
+            // Buttons on leaderboards sent before the state moved into the custom_id.
+            interaction.defer(ctx).await.unwrap();
+            return Ok(());
+        };
 
-        if let Some(MessageInteractionMetadata::Component(metadata)) =
-            interaction.message.interaction_metadata.as_deref()
-        {
-            if metadata.user != interaction.user {
-                return Err(Error::NotInteractionAuthor);
-            }
+        if interaction.user.id != state.invoker {
+            interaction.defer(ctx).await.unwrap();
+            return Err(Error::NotInteractionAuthor);
         }
 
-        let mut old_embed = interaction.message.embeds[0].clone();
-
-        let mut page_number: i64 = old_embed
-            .footer
-            .clone()
-            .unwrap()
-            .text
-            .strip_prefix("Page ")
-            .unwrap()
-            .parse()
-            .unwrap();
+        if action == "jump" {
+            interaction
+                .create_response(ctx, jump_modal(&state))
+                .await
+                .unwrap();
+            return Ok(());
+        }
 
-        old_embed.fields = Vec::new();
-        let mut new_embed: CreateEmbed = old_embed.into();
+        interaction.defer(ctx).await.unwrap();
 
         match action {
             "previous" => {
-                page_number = (page_number - 1).max(1);
+                state.page -= 1;
             }
             "user" => {
-                let row_number = get_user_row_number(pool, interaction.user.id)
-                    .await
-                    .unwrap()
-                    .unwrap();
-
-                page_number = row_number / LIMIT + 1;
+                if let Some(page) =
+                    get_user_page(pool, guild_id, state.sort, interaction.user.id).await?
+                {
+                    state.page = page;
+                }
             }
             "next" => {
-                page_number += 1;
+                state.page += 1;
+            }
+            "sort" => {
+                if let ComponentInteractionDataKind::StringSelect { values } =
+                    &interaction.data.kind
+                {
+                    if let Some(sort) = values
+                        .first()
+                        .and_then(|value| LeaderboardSort::from_name(value))
+                    {
+                        state.sort = sort;
+                        state.page = 1;
+                    }
+                }
             }
             _ => unreachable!(),
         };
 
-        let fields = get_users(ctx, pool, page_number, LIMIT)
-            .await?
-            .into_iter()
-            .map(|level_data| {
-                (
-                    level_data.user.name,
-                    format!(
-                        "Messages: {} | Total XP: {} | Level: {}",
-                        level_data.message_count, level_data.xp, level_data.level
-                    ),
-                    false,
-                )
-            });
-
-        new_embed = new_embed.footer(CreateEmbedFooter::new(format!("Page {}", page_number)));
-        new_embed = new_embed.fields(fields);
+        let (embed, components) = leaderboard_page(ctx, pool, guild_id, state).await?;
 
         interaction
-            .edit_response(ctx, EditInteractionResponse::new().embed(new_embed))
+            .edit_response(
+                ctx,
+                EditInteractionResponse::new()
+                    .embed(embed)
+                    .components(components),
+            )
             .await
             .unwrap();
 
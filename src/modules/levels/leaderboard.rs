@@ -0,0 +1,321 @@
+use serenity::all::{
+    ButtonStyle, CommandInteraction, Context, CreateActionRow, CreateButton, CreateEmbed,
+    CreateEmbedFooter, CreateInputText, CreateInteractionResponse, CreateModal, CreateSelectMenu,
+    CreateSelectMenuKind, CreateSelectMenuOption, EditInteractionResponse, GuildId, InputTextStyle,
+    ModalInteraction, ResolvedOption, ResolvedValue, UserId,
+};
+use sqlx::PgPool;
+use zayden_core::{parse_modal_data, parse_options};
+
+use crate::{Error, Result};
+
+use super::Level;
+
+pub const LIMIT: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaderboardSort {
+    TotalXp,
+    Messages,
+    Level,
+}
+
+impl LeaderboardSort {
+    const ALL: [Self; 3] = [Self::TotalXp, Self::Messages, Self::Level];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::TotalXp => "xp",
+            Self::Messages => "messages",
+            Self::Level => "level",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::TotalXp => "Total XP",
+            Self::Messages => "Messages",
+            Self::Level => "Level",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|sort| sort.name() == name)
+    }
+}
+
+/// Everything needed to redraw a leaderboard page, carried in each component's custom_id as
+/// `levels_{action}_{sort}_{page}_{invoker}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeaderboardState {
+    pub sort: LeaderboardSort,
+    pub page: i64,
+    pub invoker: UserId,
+}
+
+impl LeaderboardState {
+    pub fn custom_id(&self, action: &str) -> String {
+        format!(
+            "levels_{}_{}_{}_{}",
+            action,
+            self.sort.name(),
+            self.page,
+            self.invoker
+        )
+    }
+
+    pub fn parse(custom_id: &str) -> Option<(&str, Self)> {
+        let mut parts = custom_id.strip_prefix("levels_")?.split('_');
+
+        let action = parts.next()?;
+        let sort = LeaderboardSort::from_name(parts.next()?)?;
+        let page = parts.next()?.parse().ok()?;
+        let invoker = parts.next()?.parse().ok()?;
+
+        Some((
+            action,
+            Self {
+                sort,
+                page,
+                invoker,
+            },
+        ))
+    }
+}
+
+pub async fn get_page(
+    pool: &PgPool,
+    guild_id: GuildId,
+    sort: LeaderboardSort,
+    page: i64,
+) -> Result<Vec<Level>> {
+    let rows = sqlx::query_as!(
+        Level,
+        "SELECT * FROM levels WHERE guild_id = $1
+         ORDER BY CASE $2 WHEN 'messages' THEN message_count WHEN 'level' THEN level ELSE total_xp END DESC, total_xp DESC
+         LIMIT $3 OFFSET $4",
+        guild_id.get() as i64,
+        sort.name(),
+        LIMIT,
+        (page - 1) * LIMIT
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap();
+
+    Ok(rows)
+}
+
+pub async fn get_page_count(pool: &PgPool, guild_id: GuildId) -> Result<i64> {
+    let row = sqlx::query!(
+        "SELECT COUNT(*) FROM levels WHERE guild_id = $1",
+        guild_id.get() as i64
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+
+    let count = row.count.unwrap_or_default();
+
+    Ok(((count - 1) / LIMIT + 1).max(1))
+}
+
+pub async fn get_user_page(
+    pool: &PgPool,
+    guild_id: GuildId,
+    sort: LeaderboardSort,
+    user_id: UserId,
+) -> Result<Option<i64>> {
+    let row = sqlx::query!(
+        "SELECT row_number FROM (
+            SELECT user_id, ROW_NUMBER() OVER (ORDER BY CASE $2 WHEN 'messages' THEN message_count WHEN 'level' THEN level ELSE total_xp END DESC, total_xp DESC)
+            FROM levels WHERE guild_id = $1
+         ) AS ranked WHERE user_id = $3",
+        guild_id.get() as i64,
+        sort.name(),
+        user_id.get() as i64
+    )
+    .fetch_optional(pool)
+    .await
+    .unwrap();
+
+    Ok(row
+        .and_then(|row| row.row_number)
+        .map(|row_number| (row_number - 1) / LIMIT + 1))
+}
+
+/// Prefers the cached member's display name, then the last name we stored for them.
+fn display_name(ctx: &Context, guild_id: GuildId, row: &Level) -> String {
+    let user_id = UserId::new(row.user_id as u64);
+
+    ctx.cache
+        .guild(guild_id)
+        .and_then(|guild| {
+            guild
+                .members
+                .get(&user_id)
+                .map(|member| member.display_name().to_string())
+        })
+        .or_else(|| row.username.clone())
+        .unwrap_or_else(|| format!("Unknown user ({})", user_id))
+}
+
+/// Builds the page for `state`, clamping its page number to the pages that exist.
+pub async fn leaderboard_page(
+    ctx: &Context,
+    pool: &PgPool,
+    guild_id: GuildId,
+    mut state: LeaderboardState,
+) -> Result<(CreateEmbed, Vec<CreateActionRow>)> {
+    let page_count = get_page_count(pool, guild_id).await?;
+    state.page = state.page.clamp(1, page_count);
+
+    let rows = get_page(pool, guild_id, state.sort, state.page).await?;
+
+    let fields = rows.iter().enumerate().map(|(i, row)| {
+        (
+            format!(
+                "#{} {}",
+                (state.page - 1) * LIMIT + i as i64 + 1,
+                display_name(ctx, guild_id, row)
+            ),
+            format!(
+                "Messages: {} | Total XP: {} | Level: {}",
+                row.message_count, row.total_xp, row.level
+            ),
+            false,
+        )
+    });
+
+    let embed = CreateEmbed::new()
+        .title(format!("Leaderboard - {}", state.sort.label()))
+        .fields(fields)
+        .footer(CreateEmbedFooter::new(format!(
+            "Page {}/{}",
+            state.page, page_count
+        )));
+
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new(state.custom_id("previous"))
+            .label("<")
+            .disabled(state.page <= 1),
+        CreateButton::new(state.custom_id("user")).emoji('🎯'),
+        CreateButton::new(state.custom_id("jump"))
+            .label("Go to page")
+            .style(ButtonStyle::Secondary),
+        CreateButton::new(state.custom_id("next"))
+            .label(">")
+            .disabled(state.page >= page_count),
+    ]);
+
+    let sort_options = LeaderboardSort::ALL
+        .into_iter()
+        .map(|sort| {
+            CreateSelectMenuOption::new(sort.label(), sort.name())
+                .default_selection(sort == state.sort)
+        })
+        .collect();
+
+    let sort_menu = CreateActionRow::SelectMenu(
+        CreateSelectMenu::new(
+            state.custom_id("sort"),
+            CreateSelectMenuKind::String {
+                options: sort_options,
+            },
+        )
+        .placeholder("Sort by"),
+    );
+
+    Ok((embed, vec![buttons, sort_menu]))
+}
+
+pub async fn leaderboard(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    options: Vec<ResolvedOption<'_>>,
+    pool: &PgPool,
+) -> Result<()> {
+    interaction.defer(&ctx).await.unwrap();
+
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    let mut options = parse_options(options);
+
+    let sort = match options.remove("sort") {
+        Some(ResolvedValue::String(sort)) => {
+            LeaderboardSort::from_name(sort).unwrap_or(LeaderboardSort::TotalXp)
+        }
+        _ => LeaderboardSort::TotalXp,
+    };
+
+    let state = LeaderboardState {
+        sort,
+        page: 1,
+        invoker: interaction.user.id,
+    };
+
+    let (embed, components) = leaderboard_page(ctx, pool, guild_id, state).await?;
+
+    interaction
+        .edit_response(
+            &ctx,
+            EditInteractionResponse::new()
+                .embed(embed)
+                .components(components),
+        )
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+pub fn jump_modal(state: &LeaderboardState) -> CreateInteractionResponse {
+    let page_input = CreateInputText::new(InputTextStyle::Short, "Page", "page")
+        .placeholder(state.page.to_string())
+        .min_length(1)
+        .max_length(6);
+
+    CreateInteractionResponse::Modal(
+        CreateModal::new(state.custom_id("jump"), "Go to page")
+            .components(vec![CreateActionRow::InputText(page_input)]),
+    )
+}
+
+pub async fn jump_modal_submit(
+    ctx: &Context,
+    modal: &ModalInteraction,
+    pool: &PgPool,
+) -> Result<()> {
+    let guild_id = modal.guild_id.ok_or(Error::MissingGuildId)?;
+
+    let Some((_, mut state)) = LeaderboardState::parse(&modal.data.custom_id) else {
+        unreachable!("Leaderboard modal custom_id is always valid");
+    };
+
+    modal
+        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+        .await
+        .unwrap();
+
+    let mut data = parse_modal_data(&modal.data.components);
+    if let Some(page) = data
+        .remove("page")
+        .and_then(|page| page.trim().parse().ok())
+    {
+        state.page = page;
+    }
+
+    let (embed, components) = leaderboard_page(ctx, pool, guild_id, state).await?;
+
+    modal
+        .edit_response(
+            ctx,
+            EditInteractionResponse::new()
+                .embed(embed)
+                .components(components),
+        )
+        .await
+        .unwrap();
+
+    Ok(())
+}
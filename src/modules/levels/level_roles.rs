@@ -0,0 +1,152 @@
+use serenity::all::{
+    CommandInteraction, Context, CreateEmbed, EditInteractionResponse, GuildId, Mentionable,
+    Permissions, ResolvedOption, ResolvedValue, RoleId,
+};
+use sqlx::PgPool;
+use zayden_core::parse_options;
+
+use crate::modules::check_permissions;
+use crate::{Error, Result};
+
+#[allow(dead_code)]
+pub struct LevelRoleRow {
+    pub id: i64,
+    pub guild_id: i64,
+    pub level: i32,
+}
+
+impl LevelRoleRow {
+    pub fn role_id(&self) -> RoleId {
+        RoleId::new(self.id as u64)
+    }
+}
+
+pub struct LevelRolesTable;
+
+impl LevelRolesTable {
+    pub async fn get_guild_rows(pool: &PgPool, guild_id: GuildId) -> Result<Vec<LevelRoleRow>> {
+        let guild_id = guild_id.get() as i64;
+
+        let rows = sqlx::query_as!(
+            LevelRoleRow,
+            "SELECT * FROM level_roles WHERE guild_id = $1 ORDER BY level",
+            guild_id
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        Ok(rows)
+    }
+
+    pub async fn save_row(
+        pool: &PgPool,
+        guild_id: GuildId,
+        role_id: RoleId,
+        level: i32,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO level_roles (id, guild_id, level) VALUES ($1, $2, $3) ON CONFLICT (id) DO UPDATE SET level = EXCLUDED.level",
+            role_id.get() as i64,
+            guild_id.get() as i64,
+            level
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+
+    pub async fn delete_row(pool: &PgPool, guild_id: GuildId, role_id: RoleId) -> Result<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM level_roles WHERE id = $1 AND guild_id = $2",
+            role_id.get() as i64,
+            guild_id.get() as i64
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+pub async fn roles(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    mut options: Vec<ResolvedOption<'_>>,
+    pool: &PgPool,
+) -> Result<()> {
+    interaction.defer_ephemeral(ctx).await.unwrap();
+
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    check_permissions(interaction, Permissions::MANAGE_ROLES)?;
+
+    let command = options.remove(0);
+    let ResolvedValue::SubCommand(options) = command.value else {
+        unreachable!("Subcommand is required");
+    };
+    let mut options = parse_options(options);
+
+    let content = match command.name {
+        "add" => {
+            let Some(ResolvedValue::Role(role)) = options.remove("role") else {
+                unreachable!("Role option is required");
+            };
+            let Some(ResolvedValue::Integer(level)) = options.remove("level") else {
+                unreachable!("Level option is required");
+            };
+
+            LevelRolesTable::save_row(pool, guild_id, role.id, level as i32).await?;
+
+            format!("{} will now be given at level {}.", role.mention(), level)
+        }
+        "remove" => {
+            let Some(ResolvedValue::Role(role)) = options.remove("role") else {
+                unreachable!("Role option is required");
+            };
+
+            if LevelRolesTable::delete_row(pool, guild_id, role.id).await? {
+                format!("{} is no longer a level role.", role.mention())
+            } else {
+                format!("{} is not a level role.", role.mention())
+            }
+        }
+        "list" => {
+            let rows = LevelRolesTable::get_guild_rows(pool, guild_id).await?;
+
+            let description = if rows.is_empty() {
+                String::from("No level roles have been set up.")
+            } else {
+                rows.iter()
+                    .map(|row| format!("Level {}: {}", row.level, row.role_id().mention()))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+
+            interaction
+                .edit_response(
+                    ctx,
+                    EditInteractionResponse::new().embed(
+                        CreateEmbed::new()
+                            .title("Level Roles")
+                            .description(description),
+                    ),
+                )
+                .await
+                .unwrap();
+
+            return Ok(());
+        }
+        _ => unreachable!("Unknown subcommand"),
+    };
+
+    interaction
+        .edit_response(ctx, EditInteractionResponse::new().content(content))
+        .await
+        .unwrap();
+
+    Ok(())
+}
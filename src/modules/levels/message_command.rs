@@ -1,101 +1,154 @@
-use std::collections::HashMap;
-
 use async_trait::async_trait;
-use chrono::{TimeDelta, Utc};
-use lazy_static::lazy_static;
-use rand::Rng;
+use chrono::Utc;
 use serenity::all::{
-    ChannelId, Context, DiscordJsonError, ErrorResponse, HttpError, Message, RoleId,
+    ChannelId, Context, DiscordJsonError, ErrorResponse, HttpError, Member, Message, RoleId,
 };
 use sqlx::{PgPool, Postgres};
 use zayden_core::MessageCommand;
 
+use crate::sqlx_lib::GuildTable;
 use crate::{Error, Result};
 
-use super::{get_user_level_data, update_user_level_data, Levels};
-
-const BLOCKED_CHANNEL_IDS: [ChannelId; 1] = [ChannelId::new(776139754408247326)];
-
-lazy_static! {
-    static ref LEVEL_ROLES: HashMap<i32, RoleId> = {
-        let mut map = HashMap::new();
-        map.insert(5, RoleId::new(787443819024220210)); // New Fan | Level 5
-        map.insert(10, RoleId::new(787445571539304510)); // Active Fan | Level 10
-        map.insert(20, RoleId::new(787445900992577556)); // Big Fan | Level 20
-        map.insert(40, RoleId::new(787446715057831976)); // Super Fan | Level 40
-        map.insert(60, RoleId::new(787447090728796191)); // Mega Fan | Level 60
-        map.insert(80, RoleId::new(787447252783202326)); // Ultra Fan | Level 80
-        map
-    };
+use super::announcements::announce_level_up;
+use super::blocked_channels::{channel_ancestry, is_xp_blocked};
+use super::level_roles::LevelRolesTable;
+use super::multipliers::{combined_multiplier, XpMultipliersTable};
+use super::policy::XpPolicy;
+use super::{get_user_level_data, update_user_level_data, update_user_xp, Levels};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XpSource {
+    Message,
+    Voice,
 }
 
 #[async_trait]
 impl MessageCommand<Error, Postgres> for Levels {
     async fn run(ctx: &Context, message: &Message, pool: &PgPool) -> Result<()> {
-        if message.guild_id.is_none() {
+        let Some(guild_id) = message.guild_id else {
             return Ok(());
-        }
+        };
 
-        if BLOCKED_CHANNEL_IDS.contains(&message.channel_id) {
+        if is_xp_blocked(ctx, pool, guild_id, message.channel_id).await? {
             return Ok(());
         }
 
-        let level_data = get_user_level_data(pool, message.author.id).await.unwrap();
+        let policy = GuildTable::get_xp_policy(pool, guild_id).await?;
 
-        if level_data.last_xp >= (Utc::now().naive_utc() - TimeDelta::minutes(1)) {
+        let level_data = get_user_level_data(pool, guild_id, message.author.id)
+            .await
+            .unwrap();
+
+        if level_data.last_xp >= (Utc::now().naive_utc() - policy.cooldown) {
             return Ok(());
         }
 
-        let mut level = 0;
-        let rand_xp = rand::rng().random_range(15..25);
-        let total_xp = level_data.total_xp + rand_xp;
+        let member = message.member(ctx).await.unwrap();
+
+        award_xp(
+            ctx,
+            pool,
+            &policy,
+            &member,
+            message.channel_id,
+            policy.random_xp(),
+            XpSource::Message,
+        )
+        .await
+    }
+}
 
-        let mut xp_for_next_level = 100;
-        let mut current_total_xp = 0;
-        while total_xp >= current_total_xp + xp_for_next_level {
-            current_total_xp += xp_for_next_level;
-            level += 1;
-            xp_for_next_level = 5 * (level * level) + 50 * level + 100;
-        }
+/// Applies any active multipliers to `base_xp`, saves it and updates the member's level roles.
+pub async fn award_xp(
+    ctx: &Context,
+    pool: &PgPool,
+    policy: &XpPolicy,
+    member: &Member,
+    channel_id: ChannelId,
+    base_xp: i32,
+    source: XpSource,
+) -> Result<()> {
+    let guild_id = member.guild_id;
+
+    let multipliers = XpMultipliersTable::get_guild_rows(pool, guild_id).await?;
+    let multiplier = if multipliers.is_empty() {
+        1.0
+    } else {
+        let channel_ids = channel_ancestry(ctx, guild_id, channel_id).await;
+
+        combined_multiplier(&multipliers, &member.roles, &channel_ids)
+    };
 
-        let xp = total_xp - current_total_xp;
+    let level_data = get_user_level_data(pool, guild_id, member.user.id).await?;
 
-        update_user_level_data(pool, (level_data.id as u64).into(), xp, total_xp, level)
-            .await
-            .unwrap();
+    let gained_xp = (base_xp as f32 * multiplier).round() as i32;
+    let total_xp = level_data.total_xp.saturating_add(gained_xp);
 
-        update_member_roles(message, ctx, level).await?;
+    set_total_xp(ctx, pool, policy, member, total_xp, source).await?;
 
-        Ok(())
-    }
+    let (old_level, _) = policy.curve.level_from_total_xp(level_data.total_xp);
+    let (new_level, _) = policy.curve.level_from_total_xp(total_xp);
+
+    announce_level_up(ctx, pool, member, channel_id, old_level, new_level).await
 }
-async fn update_member_roles(msg: &Message, ctx: &Context, level: i32) -> Result<()> {
-    let member = msg.member(&ctx).await.unwrap();
 
-    let highest_qualifying_role_id = LEVEL_ROLES
-        .iter()
-        .filter(|(role_level, _)| **role_level <= level)
-        .max_by_key(|(role_level, _)| *role_level)
-        .map(|(_, &id)| id);
+/// Saves a new total XP for the member, recalculating their level and level roles.
+pub async fn set_total_xp(
+    ctx: &Context,
+    pool: &PgPool,
+    policy: &XpPolicy,
+    member: &Member,
+    total_xp: i32,
+    source: XpSource,
+) -> Result<()> {
+    let guild_id = member.guild_id;
+    let (level, xp) = policy.curve.level_from_total_xp(total_xp);
+
+    match source {
+        XpSource::Message => {
+            update_user_level_data(pool, guild_id, &member.user, xp, total_xp, level).await?
+        }
+        XpSource::Voice => {
+            update_user_xp(pool, guild_id, &member.user, xp, total_xp, level).await?
+        }
+    }
 
-    let highest_role_id = match highest_qualifying_role_id {
-        Some(id) => id,
-        None => return Ok(()),
-    };
+    update_member_roles(ctx, pool, member, level).await
+}
 
-    if let Err(serenity::Error::Http(HttpError::UnsuccessfulRequest(ErrorResponse {
-        error: DiscordJsonError { code: 10011, .. },
-        ..
-    }))) = member.add_role(&ctx, highest_role_id).await
-    {
-        return Ok(());
+pub async fn update_member_roles(
+    ctx: &Context,
+    pool: &PgPool,
+    member: &Member,
+    level: i32,
+) -> Result<()> {
+    let level_roles = LevelRolesTable::get_guild_rows(pool, member.guild_id).await?;
+
+    let highest_qualifying_role_id = level_roles
+        .iter()
+        .filter(|row| row.level <= level)
+        .max_by_key(|row| row.level)
+        .map(|row| row.role_id());
+
+    if let Some(highest_role_id) = highest_qualifying_role_id {
+        if !member.roles.contains(&highest_role_id) {
+            if let Err(serenity::Error::Http(HttpError::UnsuccessfulRequest(ErrorResponse {
+                error: DiscordJsonError { code: 10011, .. },
+                ..
+            }))) = member.add_role(&ctx, highest_role_id).await
+            {
+                return Ok(());
+            }
+        }
     }
 
+    // Members whose XP was taken away can fall below every level role.
     let roles_to_remove: Vec<&RoleId> = member
         .roles
         .iter()
         .filter(|&role_id| {
-            *role_id != highest_role_id && LEVEL_ROLES.iter().any(|(_, &id)| id == *role_id)
+            Some(*role_id) != highest_qualifying_role_id
+                && level_roles.iter().any(|row| row.role_id() == *role_id)
         })
         .collect();
 
@@ -1,37 +1,67 @@
+pub mod admin;
+pub mod announcements;
+pub mod blocked_channels;
 pub mod component;
+pub mod leaderboard;
+pub mod level_roles;
 pub mod message_command;
+pub mod multipliers;
+pub mod policy;
+pub mod rank_card;
 pub mod slash_commands;
+pub mod voice;
 
 use chrono::NaiveDateTime;
-use futures::{StreamExt, TryStreamExt};
-use serenity::all::{Context, User, UserId};
-use sqlx::PgPool;
+use serenity::all::{Context, CreateCommand, GuildId, Ready, User, UserId};
+use sqlx::{PgExecutor, PgPool};
+use zayden_core::SlashCommand;
 
-use crate::{Error, Result};
+use slash_commands::{Rank, Xp};
+
+use crate::Result;
+
+pub fn register(ctx: &Context, ready: &Ready) -> Result<Vec<CreateCommand>> {
+    let commands = vec![
+        Levels::register(ctx, ready)?,
+        Rank::register(ctx, ready)?,
+        Xp::register(ctx, ready)?,
+    ];
+
+    Ok(commands)
+}
 
 pub struct Levels;
 
 pub struct Level {
-    pub id: i64,
+    pub guild_id: i64,
+    pub user_id: i64,
     pub xp: i32,
     pub level: i32,
     pub total_xp: i32,
     pub message_count: i32,
     pub last_xp: NaiveDateTime,
+    pub username: Option<String>,
 }
 
-pub async fn get_user_level_data(pool: &PgPool, id: UserId) -> Result<Level> {
+pub async fn get_user_level_data(pool: &PgPool, guild_id: GuildId, id: UserId) -> Result<Level> {
+    let guild_id = guild_id.get() as i64;
     let user_id = id.get() as i64;
 
-    let data = match sqlx::query_as!(Level, "SELECT * FROM levels WHERE id = $1", user_id)
-        .fetch_optional(pool)
-        .await
-        .unwrap()
+    let data = match sqlx::query_as!(
+        Level,
+        "SELECT * FROM levels WHERE guild_id = $1 AND user_id = $2",
+        guild_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .unwrap()
     {
         Some(data) => data,
         None => sqlx::query_as!(
             Level,
-            "INSERT INTO levels (id) VALUES ($1) RETURNING *",
+            "INSERT INTO levels (guild_id, user_id) VALUES ($1, $2) RETURNING *",
+            guild_id,
             user_id,
         )
         .fetch_one(pool)
@@ -44,19 +74,23 @@ pub async fn get_user_level_data(pool: &PgPool, id: UserId) -> Result<Level> {
 
 pub async fn update_user_level_data(
     pool: &PgPool,
-    user_id: UserId,
+    guild_id: GuildId,
+    user: &User,
     xp: i32,
     total_xp: i32,
     level: i32,
 ) -> Result<()> {
-    let user_id = user_id.get() as i64;
+    let guild_id = guild_id.get() as i64;
+    let user_id = user.id.get() as i64;
 
     sqlx::query!(
-        "UPDATE levels SET xp = $1, total_xp = $2, level = $3, message_count = message_count + 1, last_xp = now() WHERE id = $4",
+        "UPDATE levels SET xp = $1, total_xp = $2, level = $3, message_count = message_count + 1, last_xp = now(), username = $6 WHERE guild_id = $4 AND user_id = $5",
         xp,
         total_xp,
         level,
-        user_id
+        guild_id,
+        user_id,
+        user.name
     )
     .execute(pool)
     .await.unwrap();
@@ -64,73 +98,46 @@ pub async fn update_user_level_data(
     Ok(())
 }
 
-pub async fn get_user_rank(pool: &PgPool, user_id: UserId) -> Result<Option<i64>> {
-    let user_id = user_id.get() as i64;
-
-    let data = sqlx::query!(
-        "SELECT rank FROM (SELECT id, RANK() OVER (ORDER BY total_xp DESC) FROM levels) AS ranked WHERE id = $1",
-        user_id
+/// Like `update_user_level_data`, but leaves the message count and cooldown untouched.
+pub async fn update_user_xp(
+    pool: impl PgExecutor<'_>,
+    guild_id: GuildId,
+    user: &User,
+    xp: i32,
+    total_xp: i32,
+    level: i32,
+) -> Result<()> {
+    sqlx::query!(
+        "UPDATE levels SET xp = $1, total_xp = $2, level = $3, username = $6 WHERE guild_id = $4 AND user_id = $5",
+        xp,
+        total_xp,
+        level,
+        guild_id.get() as i64,
+        user.id.get() as i64,
+        user.name
     )
-    .fetch_one(pool)
-    .await.unwrap();
+    .execute(pool)
+    .await
+    .unwrap();
 
-    Ok(data.rank)
+    Ok(())
 }
 
-pub async fn get_user_row_number(pool: &PgPool, user_id: UserId) -> Result<Option<i64>> {
+pub async fn get_user_rank(
+    pool: &PgPool,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Option<i64>> {
+    let guild_id = guild_id.get() as i64;
     let user_id = user_id.get() as i64;
 
     let data = sqlx::query!(
-        "SELECT row_number FROM (SELECT id, ROW_NUMBER() OVER (ORDER BY total_xp DESC) FROM levels) AS ranked WHERE id = $1",
+        "SELECT rank FROM (SELECT user_id, RANK() OVER (ORDER BY total_xp DESC) FROM levels WHERE guild_id = $1) AS ranked WHERE user_id = $2",
+        guild_id,
         user_id
     )
     .fetch_one(pool)
     .await.unwrap();
 
-    Ok(data.row_number)
-}
-
-#[allow(dead_code)]
-pub struct UserLevel {
-    pub user: User,
-    pub xp: i32,
-    pub level: i32,
-    pub total_xp: i32,
-    pub message_count: i32,
-    pub last_xp: NaiveDateTime,
-}
-
-pub async fn get_users(
-    ctx: &Context,
-    pool: &PgPool,
-    page: i64,
-    limit: i64,
-) -> Result<Vec<UserLevel>> {
-    let offset = (page - 1) * limit;
-
-    let data = sqlx::query_as!(
-        Level,
-        "SELECT * FROM levels ORDER BY total_xp DESC LIMIT $1 OFFSET $2",
-        limit,
-        offset
-    )
-    .fetch(pool)
-    .then(|level_result| async move {
-        let level = level_result.unwrap();
-
-        let userlevel = UserLevel {
-            user: UserId::new(level.id as u64).to_user(ctx).await.unwrap(),
-            xp: level.xp,
-            level: level.level,
-            total_xp: level.total_xp,
-            message_count: level.message_count,
-            last_xp: level.last_xp,
-        };
-
-        Ok::<_, Error>(userlevel)
-    })
-    .try_collect()
-    .await?;
-
-    Ok(data)
+    Ok(data.rank)
 }
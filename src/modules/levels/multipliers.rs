@@ -0,0 +1,301 @@
+use std::fmt::Display;
+
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use serenity::all::{
+    ChannelId, CommandInteraction, Context, CreateEmbed, EditInteractionResponse, GuildId,
+    Mentionable, Permissions, ResolvedOption, ResolvedValue, RoleId,
+};
+use sqlx::PgPool;
+use zayden_core::parse_options;
+
+use crate::modules::check_permissions;
+use crate::{Error, Result};
+
+/// The largest multiplier a single row can have, and the most that stacked multipliers add up to.
+pub const MAX_MULTIPLIER: f32 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultiplierKind {
+    Role,
+    Channel,
+    Event,
+}
+
+impl MultiplierKind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "role" => Some(Self::Role),
+            "channel" => Some(Self::Channel),
+            "event" => Some(Self::Event),
+            _ => None,
+        }
+    }
+}
+
+impl Display for MultiplierKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            MultiplierKind::Role => "role",
+            MultiplierKind::Channel => "channel",
+            MultiplierKind::Event => "event",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[allow(dead_code)]
+pub struct XpMultiplierRow {
+    pub id: i32,
+    pub guild_id: i64,
+    pub kind: String,
+    pub target_id: Option<i64>,
+    pub multiplier: f32,
+    pub starts_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+impl XpMultiplierRow {
+    pub fn kind(&self) -> Option<MultiplierKind> {
+        MultiplierKind::from_name(&self.kind)
+    }
+
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        self.starts_at <= now && self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+
+    fn applies_to(&self, role_ids: &[RoleId], channel_ids: &[ChannelId]) -> bool {
+        let target_id = self.target_id.unwrap_or_default() as u64;
+
+        match self.kind() {
+            Some(MultiplierKind::Role) => role_ids.iter().any(|id| id.get() == target_id),
+            Some(MultiplierKind::Channel) => channel_ids.iter().any(|id| id.get() == target_id),
+            Some(MultiplierKind::Event) => true,
+            None => false,
+        }
+    }
+
+    fn target(&self) -> String {
+        match (self.kind(), self.target_id) {
+            (Some(MultiplierKind::Role), Some(id)) => RoleId::new(id as u64).mention().to_string(),
+            (Some(MultiplierKind::Channel), Some(id)) => {
+                ChannelId::new(id as u64).mention().to_string()
+            }
+            _ => String::from("Everyone"),
+        }
+    }
+}
+
+/// Multipliers stack, so a 2x event and a 1.5x role give 3x XP.
+pub fn combined_multiplier(
+    rows: &[XpMultiplierRow],
+    role_ids: &[RoleId],
+    channel_ids: &[ChannelId],
+) -> f32 {
+    let now = Utc::now().naive_utc();
+
+    rows.iter()
+        .filter(|row| row.is_active(now) && row.applies_to(role_ids, channel_ids))
+        .map(|row| row.multiplier)
+        .product::<f32>()
+        .min(MAX_MULTIPLIER)
+}
+
+pub struct XpMultipliersTable;
+
+impl XpMultipliersTable {
+    /// Multipliers that are active now or scheduled to start later.
+    pub async fn get_guild_rows(pool: &PgPool, guild_id: GuildId) -> Result<Vec<XpMultiplierRow>> {
+        let rows = sqlx::query_as!(
+            XpMultiplierRow,
+            "SELECT * FROM xp_multipliers WHERE guild_id = $1 AND (expires_at IS NULL OR expires_at > now()) ORDER BY starts_at",
+            guild_id.get() as i64
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        Ok(rows)
+    }
+
+    pub async fn create(
+        pool: &PgPool,
+        guild_id: GuildId,
+        kind: MultiplierKind,
+        target_id: Option<u64>,
+        multiplier: f32,
+        starts_at: NaiveDateTime,
+        expires_at: Option<NaiveDateTime>,
+    ) -> Result<i32> {
+        let row = sqlx::query!(
+            "INSERT INTO xp_multipliers (guild_id, kind, target_id, multiplier, starts_at, expires_at) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+            guild_id.get() as i64,
+            kind.to_string(),
+            target_id.map(|id| id as i64),
+            multiplier,
+            starts_at,
+            expires_at
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        Ok(row.id)
+    }
+
+    pub async fn expire(pool: &PgPool, guild_id: GuildId, id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE xp_multipliers SET expires_at = now() WHERE id = $1 AND guild_id = $2 AND (expires_at IS NULL OR expires_at > now())",
+            id,
+            guild_id.get() as i64
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+pub async fn multipliers(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    mut options: Vec<ResolvedOption<'_>>,
+    pool: &PgPool,
+) -> Result<()> {
+    interaction.defer_ephemeral(ctx).await.unwrap();
+
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    check_permissions(interaction, Permissions::MANAGE_GUILD)?;
+
+    let command = options.remove(0);
+    let ResolvedValue::SubCommand(options) = command.value else {
+        unreachable!("Subcommand is required");
+    };
+    let mut options = parse_options(options);
+
+    let content = match command.name {
+        "create" => {
+            let Some(ResolvedValue::String(kind)) = options.remove("kind") else {
+                unreachable!("Kind option is required");
+            };
+            let Some(ResolvedValue::Number(multiplier)) = options.remove("multiplier") else {
+                unreachable!("Multiplier option is required");
+            };
+
+            let kind = MultiplierKind::from_name(kind).unwrap();
+
+            let target_id = match (kind, options.remove("role"), options.remove("channel")) {
+                (MultiplierKind::Role, Some(ResolvedValue::Role(role)), _) => Some(role.id.get()),
+                (MultiplierKind::Channel, _, Some(ResolvedValue::Channel(channel))) => {
+                    Some(channel.id.get())
+                }
+                (MultiplierKind::Event, _, _) => None,
+                _ => return Err(Error::InvalidMultiplier),
+            };
+
+            let hours = match options.remove("hours") {
+                Some(ResolvedValue::Integer(hours)) => Some(hours),
+                _ if kind == MultiplierKind::Event => return Err(Error::InvalidMultiplier),
+                _ => None,
+            };
+
+            let starts_in = match options.remove("starts_in") {
+                Some(ResolvedValue::Integer(starts_in)) => starts_in,
+                _ => 0,
+            };
+
+            let starts_at = TimeDelta::try_hours(starts_in)
+                .and_then(|starts_in| Utc::now().naive_utc().checked_add_signed(starts_in))
+                .ok_or(Error::InvalidMultiplier)?;
+            let expires_at = match hours {
+                Some(hours) => Some(
+                    TimeDelta::try_hours(hours)
+                        .and_then(|hours| starts_at.checked_add_signed(hours))
+                        .ok_or(Error::InvalidMultiplier)?,
+                ),
+                None => None,
+            };
+
+            let id = XpMultipliersTable::create(
+                pool,
+                guild_id,
+                kind,
+                target_id,
+                multiplier as f32,
+                starts_at,
+                expires_at,
+            )
+            .await?;
+
+            format!("Created {} XP multiplier #{} (x{}).", kind, id, multiplier)
+        }
+        "list" => {
+            let rows = XpMultipliersTable::get_guild_rows(pool, guild_id).await?;
+
+            let description = if rows.is_empty() {
+                String::from("There are no active XP multipliers.")
+            } else {
+                rows.iter()
+                    .map(|row| {
+                        let expires = match row.expires_at {
+                            Some(expires_at) => {
+                                format!("<t:{}:R>", expires_at.and_utc().timestamp())
+                            }
+                            None => String::from("never"),
+                        };
+                        let starts = if row.starts_at > Utc::now().naive_utc() {
+                            format!(" | Starts: <t:{}:R>", row.starts_at.and_utc().timestamp())
+                        } else {
+                            String::new()
+                        };
+
+                        format!(
+                            "**#{}** {} x{} for {}{} | Expires: {}",
+                            row.id,
+                            row.kind,
+                            row.multiplier,
+                            row.target(),
+                            starts,
+                            expires
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+
+            interaction
+                .edit_response(
+                    ctx,
+                    EditInteractionResponse::new().embed(
+                        CreateEmbed::new()
+                            .title("XP Multipliers")
+                            .description(description),
+                    ),
+                )
+                .await
+                .unwrap();
+
+            return Ok(());
+        }
+        "expire" => {
+            let Some(ResolvedValue::Integer(id)) = options.remove("id") else {
+                unreachable!("Id option is required");
+            };
+
+            if XpMultipliersTable::expire(pool, guild_id, id as i32).await? {
+                format!("XP multiplier #{} has been expired.", id)
+            } else {
+                format!("There is no active XP multiplier #{}.", id)
+            }
+        }
+        _ => unreachable!("Unknown subcommand"),
+    };
+
+    interaction
+        .edit_response(ctx, EditInteractionResponse::new().content(content))
+        .await
+        .unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,287 @@
+use chrono::TimeDelta;
+use rand::Rng;
+use serenity::all::{
+    CommandInteraction, Context, CreateEmbed, EditInteractionResponse, GuildId, Permissions,
+    ResolvedOption, ResolvedValue,
+};
+use sqlx::PgPool;
+use zayden_core::parse_options;
+
+use crate::modules::check_permissions;
+use crate::sqlx_lib::GuildTable;
+use crate::{Error, Result};
+
+/// The XP needed to advance from `level` to `level + 1` is `a * level^2 + b * level + c`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelCurve {
+    pub a: i32,
+    pub b: i32,
+    pub c: i32,
+}
+
+impl Default for LevelCurve {
+    fn default() -> Self {
+        Self {
+            a: 5,
+            b: 50,
+            c: 100,
+        }
+    }
+}
+
+impl LevelCurve {
+    /// Saturates at `i32::MAX` instead of overflowing, so a steep curve simply caps the level.
+    pub fn xp_for_level(&self, level: i32) -> i32 {
+        self.a
+            .checked_mul(level)
+            .and_then(|xp| xp.checked_mul(level))
+            .and_then(|xp| xp.checked_add(self.b.checked_mul(level)?))
+            .and_then(|xp| xp.checked_add(self.c))
+            .unwrap_or(i32::MAX)
+            .max(1)
+    }
+
+    /// Splits a total XP amount into the level reached and the XP earned towards the next level.
+    pub fn level_from_total_xp(&self, total_xp: i32) -> (i32, i32) {
+        let mut level = 0;
+        let mut remaining = total_xp.max(0);
+
+        while remaining >= self.xp_for_level(level) {
+            remaining -= self.xp_for_level(level);
+            level += 1;
+        }
+
+        (level, remaining)
+    }
+
+    /// The total XP required to reach `level` from zero.
+    pub fn total_xp_for_level(&self, level: i32) -> i32 {
+        (0..level).fold(0, |total: i32, l| {
+            total.saturating_add(self.xp_for_level(l))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XpPolicy {
+    pub curve: LevelCurve,
+    pub min_xp: i32,
+    pub max_xp: i32,
+    pub cooldown: TimeDelta,
+    /// XP given for each minute spent in a voice channel with others.
+    pub voice_xp: i32,
+}
+
+impl Default for XpPolicy {
+    fn default() -> Self {
+        Self {
+            curve: LevelCurve::default(),
+            min_xp: 15,
+            max_xp: 24,
+            cooldown: TimeDelta::minutes(1),
+            voice_xp: 10,
+        }
+    }
+}
+
+impl XpPolicy {
+    pub fn random_xp(&self) -> i32 {
+        rand::rng().random_range(self.min_xp..=self.max_xp.max(self.min_xp))
+    }
+}
+
+struct XpPolicyRow {
+    xp_curve_a: i32,
+    xp_curve_b: i32,
+    xp_curve_c: i32,
+    xp_min: i32,
+    xp_max: i32,
+    xp_cooldown_secs: i32,
+    xp_voice_per_minute: i32,
+}
+
+impl From<XpPolicyRow> for XpPolicy {
+    fn from(row: XpPolicyRow) -> Self {
+        Self {
+            curve: LevelCurve {
+                a: row.xp_curve_a,
+                b: row.xp_curve_b,
+                c: row.xp_curve_c,
+            },
+            min_xp: row.xp_min,
+            max_xp: row.xp_max,
+            cooldown: TimeDelta::seconds(row.xp_cooldown_secs as i64),
+            voice_xp: row.xp_voice_per_minute,
+        }
+    }
+}
+
+impl GuildTable {
+    pub async fn get_xp_policy(pool: &PgPool, id: GuildId) -> Result<XpPolicy> {
+        let row = sqlx::query_as!(
+            XpPolicyRow,
+            "SELECT xp_curve_a, xp_curve_b, xp_curve_c, xp_min, xp_max, xp_cooldown_secs, xp_voice_per_minute FROM guilds WHERE id = $1",
+            id.get() as i64
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        Ok(row.map(XpPolicy::from).unwrap_or_default())
+    }
+
+    pub async fn save_xp_policy(pool: &PgPool, id: GuildId, policy: &XpPolicy) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO guilds (id, xp_curve_a, xp_curve_b, xp_curve_c, xp_min, xp_max, xp_cooldown_secs, xp_voice_per_minute)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+             ON CONFLICT (id) DO UPDATE
+             SET xp_curve_a = $2, xp_curve_b = $3, xp_curve_c = $4, xp_min = $5, xp_max = $6, xp_cooldown_secs = $7, xp_voice_per_minute = $8",
+            id.get() as i64,
+            policy.curve.a,
+            policy.curve.b,
+            policy.curve.c,
+            policy.min_xp,
+            policy.max_xp,
+            policy.cooldown.num_seconds() as i32,
+            policy.voice_xp
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+}
+
+pub async fn policy(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    options: Vec<ResolvedOption<'_>>,
+    pool: &PgPool,
+) -> Result<()> {
+    interaction.defer_ephemeral(ctx).await.unwrap();
+
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    let mut options = parse_options(options);
+    let mut policy = GuildTable::get_xp_policy(pool, guild_id).await?;
+
+    if !options.is_empty() {
+        check_permissions(interaction, Permissions::MANAGE_GUILD)?;
+
+        if let Some(ResolvedValue::Integer(a)) = options.remove("curve_a") {
+            policy.curve.a = a as i32;
+        }
+        if let Some(ResolvedValue::Integer(b)) = options.remove("curve_b") {
+            policy.curve.b = b as i32;
+        }
+        if let Some(ResolvedValue::Integer(c)) = options.remove("curve_c") {
+            policy.curve.c = c as i32;
+        }
+        if let Some(ResolvedValue::Integer(min_xp)) = options.remove("min_xp") {
+            policy.min_xp = min_xp as i32;
+        }
+        if let Some(ResolvedValue::Integer(max_xp)) = options.remove("max_xp") {
+            policy.max_xp = max_xp as i32;
+        }
+        if let Some(ResolvedValue::Integer(cooldown)) = options.remove("cooldown") {
+            policy.cooldown = TimeDelta::seconds(cooldown);
+        }
+        if let Some(ResolvedValue::Integer(voice_xp)) = options.remove("voice_xp") {
+            policy.voice_xp = voice_xp as i32;
+        }
+
+        if policy.min_xp > policy.max_xp {
+            return Err(Error::InvalidXpRange);
+        }
+
+        GuildTable::save_xp_policy(pool, guild_id, &policy).await?;
+    }
+
+    let embed = CreateEmbed::new().title("XP Policy").description(format!(
+        "XP per level: `{}l² + {}l + {}`\nXP per message: {}-{}\nCooldown: {}s\nXP per voice minute: {}",
+        policy.curve.a,
+        policy.curve.b,
+        policy.curve.c,
+        policy.min_xp,
+        policy.max_xp,
+        policy.cooldown.num_seconds(),
+        policy.voice_xp
+    ));
+
+    interaction
+        .edit_response(ctx, EditInteractionResponse::new().embed(embed))
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LevelCurve;
+
+    // The loop previously inlined in the message handler.
+    fn legacy_level(total_xp: i32) -> (i32, i32) {
+        let mut level = 0;
+        let mut xp_for_next_level = 100;
+        let mut current_total_xp = 0;
+        while total_xp >= current_total_xp + xp_for_next_level {
+            current_total_xp += xp_for_next_level;
+            level += 1;
+            xp_for_next_level = 5 * (level * level) + 50 * level + 100;
+        }
+
+        (level, total_xp - current_total_xp)
+    }
+
+    #[test]
+    fn level_boundaries() {
+        let curve = LevelCurve::default();
+
+        assert_eq!(curve.level_from_total_xp(0), (0, 0));
+        assert_eq!(curve.level_from_total_xp(99), (0, 99));
+        assert_eq!(curve.level_from_total_xp(100), (1, 0));
+        assert_eq!(curve.level_from_total_xp(254), (1, 154));
+        assert_eq!(curve.level_from_total_xp(255), (2, 0));
+        assert_eq!(curve.level_from_total_xp(474), (2, 219));
+        assert_eq!(curve.level_from_total_xp(475), (3, 0));
+    }
+
+    #[test]
+    fn xp_for_level_matches_formula() {
+        let curve = LevelCurve::default();
+
+        assert_eq!(curve.xp_for_level(0), 100);
+        assert_eq!(curve.xp_for_level(1), 155);
+        assert_eq!(curve.xp_for_level(10), 1100);
+    }
+
+    #[test]
+    fn steep_curve_saturates() {
+        let curve = LevelCurve {
+            a: i32::MAX,
+            b: i32::MAX,
+            c: i32::MAX,
+        };
+
+        assert_eq!(curve.xp_for_level(2), i32::MAX);
+        assert_eq!(curve.level_from_total_xp(i32::MAX), (1, 0));
+        assert_eq!(curve.total_xp_for_level(5), i32::MAX);
+    }
+
+    #[test]
+    fn matches_legacy_formula() {
+        let curve = LevelCurve::default();
+
+        for total_xp in (0..200_000).step_by(7) {
+            assert_eq!(curve.level_from_total_xp(total_xp), legacy_level(total_xp));
+        }
+
+        for level in 1..100 {
+            let total_xp = curve.total_xp_for_level(level);
+            assert_eq!(curve.level_from_total_xp(total_xp), (level, 0));
+            assert_eq!(curve.level_from_total_xp(total_xp - 1).0, level - 1);
+        }
+    }
+}
@@ -0,0 +1,526 @@
+use ab_glyph::{point, Font, FontRef, GlyphId, PxScale, ScaleFont};
+use serenity::all::{
+    CommandInteraction, Context, CreateAttachment, EditInteractionResponse, GuildId,
+    ResolvedOption, ResolvedValue, User, UserId,
+};
+use sqlx::PgPool;
+use tiny_skia::{
+    Color, FillRule, FilterQuality, Paint, Path, PathBuilder, Pattern, Pixmap, SpreadMode,
+    Transform,
+};
+use zayden_core::parse_options;
+
+use crate::sqlx_lib::GuildTable;
+use crate::{Error, Result};
+
+use super::multipliers::{combined_multiplier, XpMultipliersTable};
+use super::{get_user_level_data, get_user_rank};
+
+const WIDTH: u32 = 934;
+const HEIGHT: u32 = 282;
+
+const AVATAR_X: f32 = 141.0;
+const AVATAR_Y: f32 = 141.0;
+const AVATAR_RADIUS: f32 = 90.0;
+
+const CONTENT_LEFT: f32 = 270.0;
+const CONTENT_RIGHT: f32 = 890.0;
+
+const TEXT: u32 = 0xFFFFFF;
+const MUTED_TEXT: u32 = 0xB9BBBE;
+
+static FONT: &[u8] = include_bytes!("../../../fonts/DejaVuSans-Bold.ttf");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CardStyle {
+    pub background: u32,
+    pub accent: u32,
+}
+
+impl Default for CardStyle {
+    fn default() -> Self {
+        Self {
+            background: 0x23272A,
+            accent: 0x5865F2,
+        }
+    }
+}
+
+pub struct RankCard<'a> {
+    pub username: &'a str,
+    pub avatar: Option<&'a [u8]>,
+    pub rank: i64,
+    pub level: i32,
+    pub xp: i32,
+    pub xp_for_next_level: i32,
+    pub multiplier: f32,
+    pub style: CardStyle,
+}
+
+impl RankCard<'_> {
+    /// Draws the card and encodes it as a PNG.
+    pub fn render(&self) -> Vec<u8> {
+        let font = FontRef::try_from_slice(FONT).unwrap();
+        let mut pixmap = Pixmap::new(WIDTH, HEIGHT).unwrap();
+
+        pixmap.fill(color(self.style.background, 255));
+
+        if let Some(panel) =
+            rounded_rect(20.0, 20.0, WIDTH as f32 - 40.0, HEIGHT as f32 - 40.0, 24.0)
+        {
+            pixmap.fill_path(
+                &panel,
+                &paint(0x000000, 90),
+                FillRule::Winding,
+                Transform::identity(),
+                None,
+            );
+        }
+
+        self.draw_avatar(&mut pixmap);
+
+        let level = self.level.to_string();
+        let level_x = CONTENT_RIGHT - text_width(&font, 52.0, &level);
+        draw_text(
+            &mut pixmap,
+            &font,
+            &level,
+            52.0,
+            level_x,
+            85.0,
+            self.style.accent,
+        );
+        let level_label_x = level_x - 10.0 - text_width(&font, 24.0, "LEVEL");
+        draw_text(
+            &mut pixmap,
+            &font,
+            "LEVEL",
+            24.0,
+            level_label_x,
+            85.0,
+            self.style.accent,
+        );
+
+        let rank = format!("#{}", self.rank);
+        let rank_x = level_label_x - 30.0 - text_width(&font, 52.0, &rank);
+        draw_text(&mut pixmap, &font, &rank, 52.0, rank_x, 85.0, TEXT);
+        let rank_label_x = rank_x - 10.0 - text_width(&font, 24.0, "RANK");
+        draw_text(&mut pixmap, &font, "RANK", 24.0, rank_label_x, 85.0, TEXT);
+
+        let xp = format!("{} / {} XP", self.xp, self.xp_for_next_level);
+        let xp_x = CONTENT_RIGHT - text_width(&font, 26.0, &xp);
+        draw_text(&mut pixmap, &font, &xp, 26.0, xp_x, 145.0, MUTED_TEXT);
+
+        let username = truncate(&font, 40.0, self.username, xp_x - CONTENT_LEFT - 20.0);
+        draw_text(
+            &mut pixmap,
+            &font,
+            &username,
+            40.0,
+            CONTENT_LEFT,
+            145.0,
+            TEXT,
+        );
+
+        let bar_width = CONTENT_RIGHT - CONTENT_LEFT;
+        fill_pill(
+            &mut pixmap,
+            CONTENT_LEFT,
+            165.0,
+            bar_width,
+            40.0,
+            &paint(TEXT, 40),
+        );
+
+        let progress = (self.xp as f32 / self.xp_for_next_level.max(1) as f32).clamp(0.0, 1.0);
+        if progress > 0.0 {
+            fill_pill(
+                &mut pixmap,
+                CONTENT_LEFT,
+                165.0,
+                (bar_width * progress).max(40.0),
+                40.0,
+                &paint(self.style.accent, 255),
+            );
+        }
+
+        if self.multiplier != 1.0 {
+            let multiplier = format!("x{} XP", self.multiplier);
+            draw_text(
+                &mut pixmap,
+                &font,
+                &multiplier,
+                22.0,
+                CONTENT_LEFT,
+                240.0,
+                self.style.accent,
+            );
+        }
+
+        pixmap.encode_png().unwrap()
+    }
+
+    fn draw_avatar(&self, pixmap: &mut Pixmap) {
+        if let Some(ring) = PathBuilder::from_circle(AVATAR_X, AVATAR_Y, AVATAR_RADIUS + 6.0) {
+            pixmap.fill_path(
+                &ring,
+                &paint(self.style.accent, 255),
+                FillRule::Winding,
+                Transform::identity(),
+                None,
+            );
+        }
+
+        let Some(circle) = PathBuilder::from_circle(AVATAR_X, AVATAR_Y, AVATAR_RADIUS) else {
+            return;
+        };
+
+        let avatar = self.avatar.and_then(|bytes| Pixmap::decode_png(bytes).ok());
+
+        let paint = match &avatar {
+            Some(avatar) => {
+                let scale = AVATAR_RADIUS * 2.0 / avatar.width().min(avatar.height()) as f32;
+
+                Paint {
+                    shader: Pattern::new(
+                        avatar.as_ref(),
+                        SpreadMode::Pad,
+                        FilterQuality::Bicubic,
+                        1.0,
+                        Transform::from_row(
+                            scale,
+                            0.0,
+                            0.0,
+                            scale,
+                            AVATAR_X - AVATAR_RADIUS,
+                            AVATAR_Y - AVATAR_RADIUS,
+                        ),
+                    ),
+                    anti_alias: true,
+                    ..Default::default()
+                }
+            }
+            None => paint(self.style.background, 255),
+        };
+
+        pixmap.fill_path(
+            &circle,
+            &paint,
+            FillRule::Winding,
+            Transform::identity(),
+            None,
+        );
+    }
+}
+
+fn color(rgb: u32, alpha: u8) -> Color {
+    Color::from_rgba8((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, alpha)
+}
+
+fn paint(rgb: u32, alpha: u8) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(color(rgb, alpha));
+    paint.anti_alias = true;
+    paint
+}
+
+fn rounded_rect(x: f32, y: f32, width: f32, height: f32, radius: f32) -> Option<Path> {
+    // Control point offset for approximating a quarter circle with a cubic curve.
+    let k = radius * 0.552_284_8;
+    let (right, bottom) = (x + width, y + height);
+
+    let mut pb = PathBuilder::new();
+    pb.move_to(x + radius, y);
+    pb.line_to(right - radius, y);
+    pb.cubic_to(
+        right - radius + k,
+        y,
+        right,
+        y + radius - k,
+        right,
+        y + radius,
+    );
+    pb.line_to(right, bottom - radius);
+    pb.cubic_to(
+        right,
+        bottom - radius + k,
+        right - radius + k,
+        bottom,
+        right - radius,
+        bottom,
+    );
+    pb.line_to(x + radius, bottom);
+    pb.cubic_to(
+        x + radius - k,
+        bottom,
+        x,
+        bottom - radius + k,
+        x,
+        bottom - radius,
+    );
+    pb.line_to(x, y + radius);
+    pb.cubic_to(x, y + radius - k, x + radius - k, y, x + radius, y);
+    pb.close();
+    pb.finish()
+}
+
+fn fill_pill(pixmap: &mut Pixmap, x: f32, y: f32, width: f32, height: f32, paint: &Paint) {
+    let radius = height / 2.0;
+
+    if let Some(pill) = rounded_rect(x, y, width.max(height), height, radius) {
+        pixmap.fill_path(&pill, paint, FillRule::Winding, Transform::identity(), None);
+    }
+}
+
+fn glyphs<'a>(
+    font: &'a FontRef<'a>,
+    size: f32,
+    text: &'a str,
+) -> impl Iterator<Item = (GlyphId, f32)> + 'a {
+    let font = font.as_scaled(PxScale::from(size));
+    let mut caret = 0.0;
+    let mut previous = None;
+
+    text.chars().map(move |c| {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += font.kern(previous, id);
+        }
+        let x = caret;
+        caret += font.h_advance(id);
+        previous = Some(id);
+        (id, x)
+    })
+}
+
+fn text_width(font: &FontRef, size: f32, text: &str) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+
+    glyphs(font, size, text)
+        .last()
+        .map(|(id, x)| x + scaled.h_advance(id))
+        .unwrap_or_default()
+}
+
+fn truncate(font: &FontRef, size: f32, text: &str, max_width: f32) -> String {
+    if text_width(font, size, text) <= max_width {
+        return text.to_string();
+    }
+
+    let mut truncated = text.to_string();
+    while !truncated.is_empty() && text_width(font, size, &format!("{}…", truncated)) > max_width
+    {
+        truncated.pop();
+    }
+
+    format!("{}…", truncated)
+}
+
+fn draw_text(
+    pixmap: &mut Pixmap,
+    font: &FontRef,
+    text: &str,
+    size: f32,
+    x: f32,
+    baseline: f32,
+    rgb: u32,
+) {
+    let (width, height) = (pixmap.width() as i32, pixmap.height() as i32);
+    let data = pixmap.data_mut();
+    let src = [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255];
+
+    for (id, offset) in glyphs(font, size, text) {
+        let glyph = id.with_scale_and_position(size, point(x + offset, baseline));
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px >= width || py >= height {
+                return;
+            }
+
+            // Pixels are premultiplied, so blending an opaque colour is a plain lerp.
+            let i = ((py * width + px) * 4) as usize;
+            let coverage = coverage.clamp(0.0, 1.0);
+            for (channel, src) in data[i..i + 4].iter_mut().zip(src) {
+                *channel =
+                    (src as f32 * coverage + *channel as f32 * (1.0 - coverage)).round() as u8;
+            }
+        });
+    }
+}
+
+struct RankCardSettingsRow {
+    background: Option<i32>,
+    accent: Option<i32>,
+}
+
+pub struct RankCardSettingsTable;
+
+impl RankCardSettingsTable {
+    pub async fn get_style(pool: &PgPool, user_id: UserId) -> Result<CardStyle> {
+        let row = sqlx::query_as!(
+            RankCardSettingsRow,
+            "SELECT background, accent FROM rank_card_settings WHERE user_id = $1",
+            user_id.get() as i64
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        let default = CardStyle::default();
+
+        let style = match row {
+            Some(row) => CardStyle {
+                background: row.background.map_or(default.background, |c| c as u32),
+                accent: row.accent.map_or(default.accent, |c| c as u32),
+            },
+            None => default,
+        };
+
+        Ok(style)
+    }
+
+    pub async fn save_style(
+        pool: &PgPool,
+        user_id: UserId,
+        background: Option<u32>,
+        accent: Option<u32>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO rank_card_settings (user_id, background, accent) VALUES ($1, $2, $3)
+             ON CONFLICT (user_id) DO UPDATE
+             SET background = COALESCE($2, rank_card_settings.background), accent = COALESCE($3, rank_card_settings.accent)",
+            user_id.get() as i64,
+            background.map(|c| c as i32),
+            accent.map(|c| c as i32)
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+
+    pub async fn delete_style(pool: &PgPool, user_id: UserId) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM rank_card_settings WHERE user_id = $1",
+            user_id.get() as i64
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+}
+
+pub async fn rank_card(
+    ctx: &Context,
+    pool: &PgPool,
+    guild_id: GuildId,
+    user: &User,
+) -> Result<CreateAttachment> {
+    let level_data = get_user_level_data(pool, guild_id, user.id).await?;
+    let policy = GuildTable::get_xp_policy(pool, guild_id).await?;
+    let rank = get_user_rank(pool, guild_id, user.id).await?.unwrap();
+    let style = RankCardSettingsTable::get_style(pool, user.id).await?;
+
+    let member = guild_id.member(ctx, user.id).await.ok();
+    let username = member
+        .as_ref()
+        .map(|member| member.display_name())
+        .unwrap_or(user.display_name());
+    let role_ids = member
+        .as_ref()
+        .map(|member| member.roles.clone())
+        .unwrap_or_default();
+
+    let multipliers = XpMultipliersTable::get_guild_rows(pool, guild_id).await?;
+    let multiplier = combined_multiplier(&multipliers, &role_ids, &[]);
+
+    let avatar = fetch_avatar(user).await;
+
+    let card = RankCard {
+        username,
+        avatar: avatar.as_deref(),
+        rank,
+        level: level_data.level,
+        xp: level_data.xp,
+        xp_for_next_level: policy.curve.xp_for_level(level_data.level),
+        multiplier,
+        style,
+    };
+
+    Ok(CreateAttachment::bytes(card.render(), "rank.png"))
+}
+
+async fn fetch_avatar(user: &User) -> Option<Vec<u8>> {
+    // The CDN serves webp by default, which the renderer can't decode.
+    let url = match &user.avatar {
+        Some(hash) => format!(
+            "https://cdn.discordapp.com/avatars/{}/{}.png?size=256",
+            user.id, hash
+        ),
+        None => user.default_avatar_url(),
+    };
+
+    let bytes = reqwest::get(url).await.ok()?.bytes().await.ok()?;
+
+    Some(bytes.to_vec())
+}
+
+fn parse_colour(hex: &str) -> Result<u32> {
+    let hex = hex.trim().trim_start_matches('#');
+
+    if hex.len() != 6 {
+        return Err(Error::InvalidColour);
+    }
+
+    u32::from_str_radix(hex, 16).map_err(|_| Error::InvalidColour)
+}
+
+pub async fn card(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    options: Vec<ResolvedOption<'_>>,
+    pool: &PgPool,
+) -> Result<()> {
+    interaction.defer_ephemeral(ctx).await.unwrap();
+
+    let mut options = parse_options(options);
+
+    let content = if let Some(ResolvedValue::Boolean(true)) = options.remove("reset") {
+        RankCardSettingsTable::delete_style(pool, interaction.user.id).await?;
+
+        "Your rank card has been reset."
+    } else {
+        let background = match options.remove("background") {
+            Some(ResolvedValue::String(hex)) => Some(parse_colour(hex)?),
+            _ => None,
+        };
+        let accent = match options.remove("accent") {
+            Some(ResolvedValue::String(hex)) => Some(parse_colour(hex)?),
+            _ => None,
+        };
+
+        RankCardSettingsTable::save_style(pool, interaction.user.id, background, accent).await?;
+
+        "Your rank card has been updated."
+    };
+
+    let mut response = EditInteractionResponse::new().content(content);
+
+    if let Some(guild_id) = interaction.guild_id {
+        response =
+            response.new_attachment(rank_card(ctx, pool, guild_id, &interaction.user).await?);
+    }
+
+    interaction.edit_response(ctx, response).await.unwrap();
+
+    Ok(())
+}
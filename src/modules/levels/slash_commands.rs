@@ -1,64 +1,397 @@
 use async_trait::async_trait;
 use serenity::all::{
-    CommandInteraction, CommandOptionType, Context, CreateButton, CreateCommand,
-    CreateCommandOption, CreateEmbed, CreateEmbedFooter, EditInteractionResponse, Ready,
-    ResolvedOption, ResolvedValue,
+    ChannelType, CommandInteraction, CommandOptionType, Context, CreateCommand,
+    CreateCommandOption, CreateEmbed, EditInteractionResponse, Ready, ResolvedOption,
+    ResolvedValue,
 };
 use sqlx::{PgPool, Postgres};
 use zayden_core::{parse_options, SlashCommand};
 
 use crate::{Error, Result};
 
-use super::{get_user_level_data, get_user_rank, get_users, Levels};
+use super::{
+    admin, announcements, blocked_channels, get_user_level_data, leaderboard, level_roles,
+    multipliers, policy, rank_card, Levels,
+};
 
 #[async_trait]
 impl SlashCommand<Error, Postgres> for Levels {
     async fn run(
         ctx: &Context,
         interaction: &CommandInteraction,
-        _options: Vec<ResolvedOption<'_>>,
+        mut options: Vec<ResolvedOption<'_>>,
         pool: &PgPool,
     ) -> Result<()> {
-        interaction.defer(&ctx).await.unwrap();
-
-        let page_number = 1;
-
-        let fields = get_users(ctx, pool, page_number, 10)
-            .await?
-            .into_iter()
-            .map(|level_data| {
-                (
-                    level_data.user.name,
-                    format!(
-                        "Messages: {} | Total XP: {} | Level: {}",
-                        level_data.message_count, level_data.xp, level_data.level
-                    ),
-                    false,
-                )
-            });
+        let command = options.remove(0);
 
-        let embed = CreateEmbed::new()
-            .title("Leaderboard")
-            .fields(fields)
-            .footer(CreateEmbedFooter::new(format!("Page {}", page_number)));
-
-        interaction
-            .edit_response(
-                &ctx,
-                EditInteractionResponse::new()
-                    .embed(embed)
-                    .button(CreateButton::new("levels_previous").label("<"))
-                    .button(CreateButton::new("levels_user").emoji('🎯'))
-                    .button(CreateButton::new("levels_next").label(">")),
-            )
-            .await
-            .unwrap();
+        match command.name {
+            "leaderboard" => {
+                let ResolvedValue::SubCommand(options) = command.value else {
+                    unreachable!("Subcommand is required");
+                };
+                leaderboard::leaderboard(ctx, interaction, options, pool).await?
+            }
+            "block-channel" | "unblock-channel" => {
+                let ResolvedValue::SubCommand(options) = command.value else {
+                    unreachable!("Subcommand is required");
+                };
+                blocked_channels::block_channel(
+                    ctx,
+                    interaction,
+                    options,
+                    pool,
+                    command.name == "block-channel",
+                )
+                .await?
+            }
+            "roles" => {
+                let ResolvedValue::SubCommandGroup(options) = command.value else {
+                    unreachable!("Subcommand group is required");
+                };
+                level_roles::roles(ctx, interaction, options, pool).await?
+            }
+            "multipliers" => {
+                let ResolvedValue::SubCommandGroup(options) = command.value else {
+                    unreachable!("Subcommand group is required");
+                };
+                multipliers::multipliers(ctx, interaction, options, pool).await?
+            }
+            "card" => {
+                let ResolvedValue::SubCommand(options) = command.value else {
+                    unreachable!("Subcommand is required");
+                };
+                rank_card::card(ctx, interaction, options, pool).await?
+            }
+            "announcements" => {
+                let ResolvedValue::SubCommand(options) = command.value else {
+                    unreachable!("Subcommand is required");
+                };
+                announcements::announcements(ctx, interaction, options, pool).await?
+            }
+            "policy" => {
+                let ResolvedValue::SubCommand(options) = command.value else {
+                    unreachable!("Subcommand is required");
+                };
+                policy::policy(ctx, interaction, options, pool).await?
+            }
+            _ => unreachable!("Unknown subcommand"),
+        };
 
         Ok(())
     }
 
     fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
-        let command = CreateCommand::new("levels").description("Get the leaderboard");
+        let command = CreateCommand::new("levels")
+            .description("Levels and leaderboard commands")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "leaderboard",
+                    "Get the leaderboard",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "sort",
+                        "What to rank members by",
+                    )
+                    .add_string_choice("Total XP", "xp")
+                    .add_string_choice("Messages", "messages")
+                    .add_string_choice("Level", "level"),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "card",
+                    "Customise the colours of your rank card",
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "background",
+                    "Background colour as a hex code, e.g. #23272A",
+                ))
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "accent",
+                    "Accent colour as a hex code, e.g. #5865F2",
+                ))
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "reset",
+                    "Go back to the default colours",
+                )),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "block-channel",
+                    "Stop members from earning XP in a channel, category or thread",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Channel,
+                        "channel",
+                        "The channel, category or thread to block",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "unblock-channel",
+                    "Allow members to earn XP in a channel again",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Channel,
+                        "channel",
+                        "The channel, category or thread to unblock",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "policy",
+                    "View or change how XP is earned",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "curve_a",
+                        "The a in a*l^2 + b*l + c XP needed per level",
+                    )
+                    .min_int_value(0)
+                    .max_int_value(1000),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "curve_b",
+                        "The b in a*l^2 + b*l + c XP needed per level",
+                    )
+                    .min_int_value(0)
+                    .max_int_value(10000),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "curve_c",
+                        "The c in a*l^2 + b*l + c XP needed per level",
+                    )
+                    .min_int_value(1)
+                    .max_int_value(100000),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "min_xp",
+                        "The minimum XP given per message",
+                    )
+                    .min_int_value(0)
+                    .max_int_value(10000),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "max_xp",
+                        "The maximum XP given per message",
+                    )
+                    .min_int_value(0)
+                    .max_int_value(10000),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "cooldown",
+                        "Seconds between messages that earn XP",
+                    )
+                    .min_int_value(0)
+                    .max_int_value(86400),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "voice_xp",
+                        "XP given per minute in voice, 0 to disable",
+                    )
+                    .min_int_value(0)
+                    .max_int_value(10000),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "announcements",
+                    "View or change how level-ups are announced",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "mode",
+                        "Where to announce level-ups",
+                    )
+                    .add_string_choice("Off", "off")
+                    .add_string_choice("Same channel", "same_channel")
+                    .add_string_choice("Announcement channel", "channel")
+                    .add_string_choice("Direct message", "dm"),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Channel,
+                        "channel",
+                        "The announcement channel",
+                    )
+                    .channel_types(vec![ChannelType::Text]),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "template",
+                    "The message, with {user}, {username}, {level} and {role} placeholders",
+                ))
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "milestones",
+                    "Comma separated levels to announce, or \"all\" to announce every level",
+                )),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommandGroup,
+                    "roles",
+                    "Manage the roles given out at each level",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "add",
+                        "Give a role when members reach a level",
+                    )
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::Role,
+                            "role",
+                            "The role to give",
+                        )
+                        .required(true),
+                    )
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::Integer,
+                            "level",
+                            "The level the role is given at",
+                        )
+                        .min_int_value(1)
+                        .required(true),
+                    ),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "remove",
+                        "Stop giving a role at a level",
+                    )
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::Role,
+                            "role",
+                            "The role to remove",
+                        )
+                        .required(true),
+                    ),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "list",
+                    "List the level roles",
+                )),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommandGroup,
+                    "multipliers",
+                    "Manage XP multipliers for roles, channels and events",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "create",
+                        "Create an XP multiplier",
+                    )
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "kind",
+                            "What the multiplier applies to",
+                        )
+                        .add_string_choice("Role", "role")
+                        .add_string_choice("Channel", "channel")
+                        .add_string_choice("Event", "event")
+                        .required(true),
+                    )
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::Number,
+                            "multiplier",
+                            "The XP multiplier, e.g. 2 for double XP",
+                        )
+                        .min_number_value(0.0)
+                        .max_number_value(multipliers::MAX_MULTIPLIER as f64)
+                        .required(true),
+                    )
+                    .add_sub_option(CreateCommandOption::new(
+                        CommandOptionType::Role,
+                        "role",
+                        "The role for a role multiplier",
+                    ))
+                    .add_sub_option(CreateCommandOption::new(
+                        CommandOptionType::Channel,
+                        "channel",
+                        "The channel for a channel multiplier",
+                    ))
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::Integer,
+                            "hours",
+                            "How long the multiplier lasts, required for events",
+                        )
+                        .min_int_value(1)
+                        .max_int_value(8760),
+                    )
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::Integer,
+                            "starts_in",
+                            "Hours until the multiplier starts",
+                        )
+                        .min_int_value(0)
+                        .max_int_value(8760),
+                    ),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "list",
+                    "List the active and upcoming XP multipliers",
+                ))
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "expire",
+                        "Expire an XP multiplier",
+                    )
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::Integer,
+                            "id",
+                            "The multiplier id",
+                        )
+                        .required(true),
+                    ),
+                ),
+            );
 
         Ok(command)
     }
@@ -86,25 +419,12 @@ impl SlashCommand<Error, Postgres> for Rank {
             _ => &interaction.user,
         };
 
-        let level_data = get_user_level_data(pool, user.id).await?;
-
-        let level = level_data.level;
-        let xp_for_next_level = 5 * (level * level) + 50 * level + 100;
-        let user_rank = get_user_rank(pool, user.id).await?.unwrap();
-
-        let embed = CreateEmbed::new()
-            .title(format!("XP stats for {}", user.name))
-            .description(format!(
-                "Rank: #{}\nLevel: {}\nXP: {}/{} ({}%)",
-                user_rank,
-                level,
-                level_data.xp,
-                xp_for_next_level,
-                (level_data.xp as f32 / xp_for_next_level as f32 * 100.0).round()
-            ));
+        let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+        let card = rank_card::rank_card(ctx, pool, guild_id, user).await?;
 
         interaction
-            .edit_response(ctx, EditInteractionResponse::new().embed(embed))
+            .edit_response(ctx, EditInteractionResponse::new().new_attachment(card))
             .await
             .unwrap();
 
@@ -136,40 +456,131 @@ impl SlashCommand<Error, Postgres> for Xp {
     async fn run(
         ctx: &Context,
         interaction: &CommandInteraction,
-        options: Vec<ResolvedOption<'_>>,
+        mut options: Vec<ResolvedOption<'_>>,
         pool: &PgPool,
     ) -> Result<()> {
-        let mut options = parse_options(options);
+        let command = options.remove(0);
 
-        match options.remove("ephemeral") {
-            Some(ResolvedValue::Boolean(true)) => interaction.defer_ephemeral(&ctx).await.unwrap(),
-            _ => interaction.defer(&ctx).await.unwrap(),
+        match (command.name, command.value) {
+            ("view", ResolvedValue::SubCommand(options)) => {
+                xp_view(ctx, interaction, options, pool).await
+            }
+            ("admin", ResolvedValue::SubCommandGroup(options)) => {
+                admin::admin(ctx, interaction, options, pool).await
+            }
+            _ => unreachable!("Unknown subcommand"),
         }
-
-        let level_data = get_user_level_data(pool, interaction.user.id).await?;
-
-        let embed = CreateEmbed::default().title("XP").description(format!(
-            "Current XP: {}\nLevel: {}\nTotal XP: {}",
-            level_data.xp, level_data.level, level_data.total_xp
-        ));
-
-        interaction
-            .edit_response(ctx, EditInteractionResponse::new().embed(embed))
-            .await
-            .unwrap();
-
-        Ok(())
     }
 
     fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
+        let user_option = || {
+            CreateCommandOption::new(CommandOptionType::User, "user", "The member to change")
+                .required(true)
+        };
+        let amount_option = |description: &str| {
+            CreateCommandOption::new(CommandOptionType::Integer, "amount", description)
+                .required(true)
+                .min_int_value(0)
+        };
+        let reason_option = || {
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "reason",
+                "Why the XP is being changed",
+            )
+        };
+
         let command = CreateCommand::new("xp")
-            .description("Get your current xp")
-            .add_option(CreateCommandOption::new(
-                CommandOptionType::Boolean,
-                "ephemeral",
-                "Whether the response should be ephemeral",
-            ));
+            .description("View or manage XP")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "view",
+                    "Get your current xp",
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "ephemeral",
+                    "Whether the response should be ephemeral",
+                )),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommandGroup,
+                    "admin",
+                    "Change a member's XP",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "give",
+                        "Give XP to a member",
+                    )
+                    .add_sub_option(user_option())
+                    .add_sub_option(amount_option("The XP to give"))
+                    .add_sub_option(reason_option()),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "take",
+                        "Take XP from a member",
+                    )
+                    .add_sub_option(user_option())
+                    .add_sub_option(amount_option("The XP to take"))
+                    .add_sub_option(reason_option()),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "set",
+                        "Set a member's total XP",
+                    )
+                    .add_sub_option(user_option())
+                    .add_sub_option(amount_option("The new total XP"))
+                    .add_sub_option(reason_option()),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "reset",
+                        "Reset a member's XP to zero",
+                    )
+                    .add_sub_option(user_option())
+                    .add_sub_option(reason_option()),
+                ),
+            );
 
         Ok(command)
     }
 }
+
+async fn xp_view(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    options: Vec<ResolvedOption<'_>>,
+    pool: &PgPool,
+) -> Result<()> {
+    let mut options = parse_options(options);
+
+    match options.remove("ephemeral") {
+        Some(ResolvedValue::Boolean(true)) => interaction.defer_ephemeral(&ctx).await.unwrap(),
+        _ => interaction.defer(&ctx).await.unwrap(),
+    }
+
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    let level_data = get_user_level_data(pool, guild_id, interaction.user.id).await?;
+
+    let embed = CreateEmbed::default().title("XP").description(format!(
+        "Current XP: {}\nLevel: {}\nTotal XP: {}",
+        level_data.xp, level_data.level, level_data.total_xp
+    ));
+
+    interaction
+        .edit_response(ctx, EditInteractionResponse::new().embed(embed))
+        .await
+        .unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,311 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use serenity::all::{ChannelId, Context, GuildId, Ready, UserId, VoiceState};
+use sqlx::PgPool;
+
+use crate::handler::OnReady;
+use crate::sqlx_lib::{GuildTable, PostgresPool};
+use crate::Result;
+
+use super::blocked_channels::is_xp_blocked;
+use super::message_command::{award_xp, XpSource};
+
+static TICKER_STARTED: AtomicBool = AtomicBool::new(false);
+
+pub struct VoiceXp;
+
+#[allow(dead_code)]
+pub struct VoiceSessionRow {
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub channel_id: i64,
+    pub eligible: bool,
+    pub joined_at: NaiveDateTime,
+    pub last_accrued_at: NaiveDateTime,
+    pub checked_at: NaiveDateTime,
+}
+
+impl VoiceSessionRow {
+    pub fn user_id(&self) -> UserId {
+        UserId::new(self.user_id as u64)
+    }
+
+    pub fn channel_id(&self) -> ChannelId {
+        ChannelId::new(self.channel_id as u64)
+    }
+}
+
+pub struct VoiceSessionsTable;
+
+impl VoiceSessionsTable {
+    pub async fn get_guild_rows(pool: &PgPool, guild_id: GuildId) -> Result<Vec<VoiceSessionRow>> {
+        let rows = sqlx::query_as!(
+            VoiceSessionRow,
+            "SELECT * FROM voice_sessions WHERE guild_id = $1",
+            guild_id.get() as i64
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        Ok(rows)
+    }
+
+    pub async fn insert(
+        pool: &PgPool,
+        guild_id: GuildId,
+        user_id: UserId,
+        channel_id: ChannelId,
+        eligible: bool,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO voice_sessions (guild_id, user_id, channel_id, eligible) VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING",
+            guild_id.get() as i64,
+            user_id.get() as i64,
+            channel_id.get() as i64,
+            eligible
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+
+    /// Returns false if the session was settled by another update first, in which case no XP
+    /// should be given.
+    pub async fn advance(
+        pool: &PgPool,
+        row: &VoiceSessionRow,
+        channel_id: ChannelId,
+        eligible: bool,
+        last_accrued_at: NaiveDateTime,
+    ) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE voice_sessions SET channel_id = $3, eligible = $4, last_accrued_at = $5, checked_at = now() WHERE guild_id = $1 AND user_id = $2 AND last_accrued_at = $6",
+            row.guild_id,
+            row.user_id,
+            channel_id.get() as i64,
+            eligible,
+            last_accrued_at,
+            row.last_accrued_at
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn delete(pool: &PgPool, row: &VoiceSessionRow) -> Result<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM voice_sessions WHERE guild_id = $1 AND user_id = $2 AND last_accrued_at = $3",
+            row.guild_id,
+            row.user_id,
+            row.last_accrued_at
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Shifts every session forward by the time the bot was offline, so the unpaid part of a
+    /// minute is kept but the downtime itself is never paid out.
+    pub async fn resume(pool: &PgPool) -> Result<()> {
+        sqlx::query!(
+            "UPDATE voice_sessions SET last_accrued_at = now() - (checked_at - last_accrued_at), checked_at = now()"
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VoiceMember {
+    pub user_id: UserId,
+    pub channel_id: ChannelId,
+    /// Bots and deafened members never earn voice XP or count as company.
+    pub listening: bool,
+}
+
+impl VoiceMember {
+    fn new(state: &VoiceState, bot: bool) -> Option<Self> {
+        Some(Self {
+            user_id: state.user_id,
+            channel_id: state.channel_id?,
+            listening: !bot && !state.self_deaf && !state.deaf,
+        })
+    }
+}
+
+/// Members who are listening, outside the AFK channel and not alone in their channel.
+pub fn eligible_members(members: &[VoiceMember], afk_channel_id: Option<ChannelId>) -> Vec<UserId> {
+    members
+        .iter()
+        .filter(|member| member.listening && Some(member.channel_id) != afk_channel_id)
+        .filter(|member| {
+            members.iter().any(|other| {
+                other.user_id != member.user_id
+                    && other.channel_id == member.channel_id
+                    && other.listening
+            })
+        })
+        .map(|member| member.user_id)
+        .collect()
+}
+
+/// The guild's voice members from the cache, with `update` applied in case the cache hasn't
+/// caught up with it yet. Returns `None` until the guild has been cached.
+fn voice_members(
+    ctx: &Context,
+    guild_id: GuildId,
+    update: Option<&VoiceState>,
+) -> Option<(Option<ChannelId>, Vec<VoiceMember>)> {
+    let guild = ctx.cache.guild(guild_id)?;
+
+    let is_bot = |state: &VoiceState| {
+        state
+            .member
+            .as_ref()
+            .or_else(|| guild.members.get(&state.user_id))
+            .is_some_and(|member| member.user.bot)
+    };
+
+    let members = guild
+        .voice_states
+        .values()
+        .filter(|state| update.is_none_or(|update| update.user_id != state.user_id))
+        .chain(update)
+        .filter_map(|state| VoiceMember::new(state, is_bot(state)))
+        .collect();
+
+    let afk_channel_id = guild.afk_metadata.as_ref().map(|afk| afk.afk_channel_id);
+
+    Some((afk_channel_id, members))
+}
+
+/// Pays out the whole minutes each eligible session has earned since it was last settled, then
+/// opens, moves or closes sessions to match who is in voice now.
+pub async fn sync_guild(
+    ctx: &Context,
+    pool: &PgPool,
+    guild_id: GuildId,
+    update: Option<&VoiceState>,
+) -> Result<()> {
+    let Some((afk_channel_id, members)) = voice_members(ctx, guild_id, update) else {
+        return Ok(());
+    };
+
+    let sessions = VoiceSessionsTable::get_guild_rows(pool, guild_id).await?;
+
+    if members.is_empty() && sessions.is_empty() {
+        return Ok(());
+    }
+
+    let policy = GuildTable::get_xp_policy(pool, guild_id).await?;
+    let eligible = eligible_members(&members, afk_channel_id);
+    let now = Utc::now().naive_utc();
+
+    for session in &sessions {
+        let minutes = if session.eligible {
+            (now - session.last_accrued_at).num_minutes()
+        } else {
+            0
+        };
+
+        let saved = match members
+            .iter()
+            .find(|member| member.user_id == session.user_id())
+        {
+            Some(member) => {
+                let is_eligible = eligible.contains(&member.user_id)
+                    && !is_xp_blocked(ctx, pool, guild_id, member.channel_id).await?;
+                let last_accrued_at = if session.eligible {
+                    session.last_accrued_at + TimeDelta::minutes(minutes)
+                } else {
+                    now
+                };
+
+                VoiceSessionsTable::advance(
+                    pool,
+                    session,
+                    member.channel_id,
+                    is_eligible,
+                    last_accrued_at,
+                )
+                .await?
+            }
+            None => VoiceSessionsTable::delete(pool, session).await?,
+        };
+
+        if !saved || minutes <= 0 || policy.voice_xp <= 0 {
+            continue;
+        }
+
+        let Ok(member) = guild_id.member(ctx, session.user_id()).await else {
+            continue;
+        };
+
+        award_xp(
+            ctx,
+            pool,
+            &policy,
+            &member,
+            session.channel_id(),
+            minutes as i32 * policy.voice_xp,
+            XpSource::Voice,
+        )
+        .await?;
+    }
+
+    for member in members.iter().filter(|member| {
+        !sessions
+            .iter()
+            .any(|session| session.user_id() == member.user_id)
+    }) {
+        let is_eligible = eligible.contains(&member.user_id)
+            && !is_xp_blocked(ctx, pool, guild_id, member.channel_id).await?;
+
+        VoiceSessionsTable::insert(
+            pool,
+            guild_id,
+            member.user_id,
+            member.channel_id,
+            is_eligible,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl OnReady for VoiceXp {
+    async fn on_ready(ctx: Context, _ready: Ready) -> Result<()> {
+        if TICKER_STARTED.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let pool = PostgresPool::get(&ctx).await;
+
+        VoiceSessionsTable::resume(&pool).await?;
+
+        loop {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+
+            for guild_id in ctx.cache.guilds() {
+                if let Err(e) = sync_guild(&ctx, &pool, guild_id, None).await {
+                    eprintln!("Error syncing voice XP for {}: {:?}", guild_id, e);
+                }
+            }
+        }
+    }
+}
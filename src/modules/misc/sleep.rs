@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 use async_trait::async_trait;
+use chrono::TimeDelta;
 use futures::{StreamExt, TryStreamExt};
 use serenity::all::{
     ButtonStyle, CommandInteraction, CommandOptionType, Context, CreateButton, CreateCommand,
@@ -13,9 +14,12 @@ use zayden_core::SlashCommand;
 
 use crate::guilds::ServersTable;
 use crate::handler::OnReady;
+use crate::modules::moderation::mod_log::{post_mod_log, ModLogEntry};
 use crate::sqlx_lib::PostgresPool;
 use crate::{Error, Result};
 
+const SLEEP_LOG_COLOUR: u32 = 0x95A5A6;
+
 pub struct Sleep;
 
 #[async_trait]
@@ -153,15 +157,51 @@ async fn sleep_user(ctx: Context, member: Member, hours: u64) -> Result<()> {
         .sleep_role_id()
         .unwrap();
 
-    drop(pool);
-
     member.add_role(&ctx, sleep_role_id).await.unwrap();
 
+    post_mod_log(
+        &ctx,
+        &pool,
+        member.guild_id,
+        &ModLogEntry {
+            action: "Sleep Role Added",
+            case_id: None,
+            target: member.user.id,
+            moderator: Some(member.user.id),
+            reason: Some("Used /sleep"),
+            duration: Some(TimeDelta::hours(hours as i64)),
+            jump_url: None,
+            colour: SLEEP_LOG_COLOUR,
+        },
+    )
+    .await?;
+
+    drop(pool);
+
     tokio::time::sleep(Duration::from_secs(hours * 60 * 60)).await;
 
     println!("Waking up {}", member.user.name);
 
     member.remove_role(&ctx, sleep_role_id).await.unwrap();
 
+    let pool = PostgresPool::get(&ctx).await;
+
+    post_mod_log(
+        &ctx,
+        &pool,
+        member.guild_id,
+        &ModLogEntry {
+            action: "Sleep Role Removed",
+            case_id: None,
+            target: member.user.id,
+            moderator: None,
+            reason: Some("Sleep expired"),
+            duration: None,
+            jump_url: None,
+            colour: SLEEP_LOG_COLOUR,
+        },
+    )
+    .await?;
+
     Ok(())
 }
@@ -1,5 +1,6 @@
-use serenity::all::{Context, CreateCommand, Ready};
+use serenity::all::{CommandInteraction, Context, CreateCommand, Permissions, Ready};
 
+pub mod automod;
 mod bunny;
 // pub mod family;
 pub mod gold_star;
@@ -11,11 +12,13 @@ pub mod reaction_roles;
 pub mod suggestions;
 pub mod ticket;
 
-use crate::Result;
+use crate::{Error, Result};
 
 pub fn global_register(ctx: &Context, ready: &Ready) -> Result<Vec<CreateCommand>> {
     let commands = [
+        automod::register(ctx, ready)?,
         gold_star::register(ctx, ready)?,
+        levels::register(ctx, ready)?,
         misc::register(ctx, ready)?,
         moderation::register(ctx, ready)?,
         patreon::register(ctx, ready)?,
@@ -26,3 +29,17 @@ pub fn global_register(ctx: &Context, ready: &Ready) -> Result<Vec<CreateCommand
 
     Ok(commands)
 }
+
+/// Checks the invoking member's permissions, for subcommands that need more than the command's
+/// default permissions.
+pub fn check_permissions(interaction: &CommandInteraction, permissions: Permissions) -> Result<()> {
+    if interaction.member.as_ref().is_some_and(|member| {
+        member
+            .permissions
+            .is_some_and(|perms| perms.contains(permissions))
+    }) {
+        Ok(())
+    } else {
+        Err(Error::MissingPermissions)
+    }
+}
@@ -0,0 +1,390 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use serenity::all::{
+    CommandInteraction, CommandOptionType, Context, CreateCommand, CreateCommandOption,
+    CreateEmbed, EditInteractionResponse, GuildId, Permissions, Ready, ResolvedOption,
+    ResolvedValue, User,
+};
+use sqlx::{PgPool, Postgres};
+use zayden_core::{parse_options, SlashCommand};
+
+use crate::modules::check_permissions;
+use crate::{Error, Result};
+
+use super::InfractionRow;
+
+const HISTORY_LIMIT: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseRevisionKind {
+    EditReason,
+    Void,
+    Delete,
+}
+
+impl CaseRevisionKind {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::EditReason => "edit_reason",
+            Self::Void => "void",
+            Self::Delete => "delete",
+        }
+    }
+
+    fn label(name: &str) -> &str {
+        match name {
+            "edit_reason" => "Reason edited",
+            "void" => "Voided",
+            "delete" => "Deleted",
+            name => name,
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub struct CaseRevisionRow {
+    pub id: i32,
+    pub infraction_id: i32,
+    pub guild_id: i64,
+    pub moderator_id: i64,
+    pub moderator_username: String,
+    pub action: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+pub struct CaseRevisionsTable;
+
+impl CaseRevisionsTable {
+    pub async fn get_case_rows(
+        pool: &PgPool,
+        guild_id: GuildId,
+        infraction_id: i32,
+    ) -> Result<Vec<CaseRevisionRow>> {
+        let rows = sqlx::query_as!(
+            CaseRevisionRow,
+            "SELECT * FROM infraction_revisions WHERE guild_id = $1 AND infraction_id = $2 ORDER BY created_at",
+            guild_id.get() as i64,
+            infraction_id
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        Ok(rows)
+    }
+
+    pub async fn insert(
+        pool: &PgPool,
+        case: &InfractionRow,
+        moderator: &User,
+        kind: CaseRevisionKind,
+        old_value: Option<&str>,
+        new_value: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO infraction_revisions (infraction_id, guild_id, moderator_id, moderator_username, action, old_value, new_value) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            case.id,
+            case.guild_id,
+            moderator.id.get() as i64,
+            moderator.name,
+            kind.name(),
+            old_value,
+            new_value
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+}
+
+impl InfractionRow {
+    pub async fn get(pool: &PgPool, guild_id: GuildId, id: i32) -> Result<InfractionRow> {
+        let row = sqlx::query_as!(
+            InfractionRow,
+            "SELECT * FROM infractions WHERE guild_id = $1 AND id = $2",
+            guild_id.get() as i64,
+            id
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        row.ok_or(Error::CaseNotFound)
+    }
+
+    async fn set_reason(&self, pool: &PgPool, reason: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE infractions SET reason = $1 WHERE id = $2",
+            reason,
+            self.id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+
+    async fn void(&self, pool: &PgPool) -> Result<()> {
+        sqlx::query!(
+            "UPDATE infractions SET voided = TRUE WHERE id = $1",
+            self.id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+
+    async fn delete(&self, pool: &PgPool) -> Result<()> {
+        sqlx::query!("DELETE FROM infractions WHERE id = $1", self.id)
+            .execute(pool)
+            .await
+            .unwrap();
+
+        Ok(())
+    }
+}
+
+/// Voids the case and records who did it. Cases that are already void are left alone.
+pub async fn void_case(
+    pool: &PgPool,
+    case: &mut InfractionRow,
+    moderator: &User,
+    note: Option<&str>,
+) -> Result<()> {
+    if case.voided {
+        return Ok(());
+    }
+
+    case.void(pool).await?;
+    CaseRevisionsTable::insert(pool, case, moderator, CaseRevisionKind::Void, None, note).await?;
+    case.voided = true;
+
+    Ok(())
+}
+
+fn case_embed(case: &InfractionRow, revisions: &[CaseRevisionRow]) -> CreateEmbed {
+    let mut title = format!("Case #{}", case.id);
+    if case.voided {
+        title.push_str(" (voided)");
+    }
+
+    let mut embed = CreateEmbed::new()
+        .title(title)
+        .field("Type", &case.infraction_type, true)
+        .field("Points", case.points.to_string(), true)
+        .field(
+            "Created",
+            case.created_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+            true,
+        )
+        .field(
+            "User",
+            format!("({}) {}", case.user_id, case.username),
+            false,
+        )
+        .field(
+            "Moderator",
+            format!("({}) {}", case.moderator_id, case.moderator_username),
+            false,
+        )
+        .field("Reason", &case.reason, false);
+
+    if !revisions.is_empty() {
+        // The description fits far more than a field, but long reasons still add up.
+        let history = revisions
+            .iter()
+            .skip(revisions.len().saturating_sub(HISTORY_LIMIT))
+            .map(|revision| {
+                let mut line = format!(
+                    "`{}` **{}** by {}",
+                    revision.created_at.format("%Y-%m-%d %H:%M"),
+                    CaseRevisionKind::label(&revision.action),
+                    revision.moderator_username
+                );
+
+                match (&revision.old_value, &revision.new_value) {
+                    (Some(old), Some(new)) => line.push_str(&format!(": {} → {}", old, new)),
+                    (None, Some(new)) => line.push_str(&format!(": {}", new)),
+                    _ => {}
+                }
+
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        embed = embed.description(format!("**History**\n{}", history));
+    }
+
+    embed
+}
+
+pub struct Case;
+
+#[async_trait]
+impl SlashCommand<Error, Postgres> for Case {
+    async fn run(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        mut options: Vec<ResolvedOption<'_>>,
+        pool: &PgPool,
+    ) -> Result<()> {
+        interaction.defer_ephemeral(ctx).await.unwrap();
+
+        let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+        let command = options.remove(0);
+        let ResolvedValue::SubCommand(options) = command.value else {
+            unreachable!("Subcommand is required");
+        };
+        let mut options = parse_options(options);
+
+        let Some(ResolvedValue::Integer(id)) = options.remove("id") else {
+            unreachable!("Id option is required");
+        };
+
+        let mut case = InfractionRow::get(pool, guild_id, id as i32).await?;
+        let moderator = &interaction.user;
+
+        let content = match command.name {
+            "view" => None,
+            "edit-reason" => {
+                let Some(ResolvedValue::String(reason)) = options.remove("reason") else {
+                    unreachable!("Reason option is required");
+                };
+
+                case.set_reason(pool, reason).await?;
+                CaseRevisionsTable::insert(
+                    pool,
+                    &case,
+                    moderator,
+                    CaseRevisionKind::EditReason,
+                    Some(case.reason.as_str()),
+                    Some(reason),
+                )
+                .await?;
+                case.reason = reason.to_string();
+
+                Some(format!("Updated the reason for case #{}.", case.id))
+            }
+            "void" => {
+                let note = match options.remove("reason") {
+                    Some(ResolvedValue::String(note)) => Some(note),
+                    _ => None,
+                };
+
+                void_case(pool, &mut case, moderator, note).await?;
+
+                Some(format!(
+                    "Case #{} is void and no longer counts towards escalation.",
+                    case.id
+                ))
+            }
+            "delete" => {
+                check_permissions(interaction, Permissions::MANAGE_GUILD)?;
+
+                // The revision outlives the case so the deletion stays on record.
+                let summary = format!(
+                    "{} against {} ({} points): {}",
+                    case.infraction_type, case.username, case.points, case.reason
+                );
+                case.delete(pool).await?;
+                CaseRevisionsTable::insert(
+                    pool,
+                    &case,
+                    moderator,
+                    CaseRevisionKind::Delete,
+                    Some(summary.as_str()),
+                    None,
+                )
+                .await?;
+
+                interaction
+                    .edit_response(
+                        ctx,
+                        EditInteractionResponse::new()
+                            .content(format!("Deleted case #{}.", case.id)),
+                    )
+                    .await
+                    .unwrap();
+
+                return Ok(());
+            }
+            _ => unreachable!("Unknown subcommand"),
+        };
+
+        let revisions = CaseRevisionsTable::get_case_rows(pool, guild_id, case.id).await?;
+
+        let mut response = EditInteractionResponse::new().embed(case_embed(&case, &revisions));
+        if let Some(content) = content {
+            response = response.content(content);
+        }
+
+        interaction.edit_response(ctx, response).await.unwrap();
+
+        Ok(())
+    }
+
+    fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
+        let id = || {
+            CreateCommandOption::new(CommandOptionType::Integer, "id", "The case number")
+                .min_int_value(1)
+                .required(true)
+        };
+
+        let command = CreateCommand::new("case")
+            .description("View and manage moderation cases")
+            .default_member_permissions(Permissions::MODERATE_MEMBERS)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "view",
+                    "View a case and its history",
+                )
+                .add_sub_option(id()),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "edit-reason",
+                    "Change the reason on a case",
+                )
+                .add_sub_option(id())
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "reason", "The new reason")
+                        .max_length(255)
+                        .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "void",
+                    "Void a case, such as after a successful appeal",
+                )
+                .add_sub_option(id())
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "reason",
+                    "Why the case was voided",
+                )),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "delete",
+                    "Permanently delete a case",
+                )
+                .add_sub_option(id()),
+            );
+
+        Ok(command)
+    }
+}
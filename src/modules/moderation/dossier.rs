@@ -0,0 +1,194 @@
+use async_trait::async_trait;
+use serenity::all::{
+    CommandInteraction, CommandOptionType, Context, CreateCommand, CreateCommandOption,
+    CreateEmbed, CreateEmbedFooter, Permissions, Ready, ResolvedOption, ResolvedValue, Timestamp,
+    User,
+};
+use sqlx::{PgPool, Postgres};
+use zayden_core::{parse_options, SlashCommand};
+
+use crate::modules::levels::{get_user_level_data, get_user_rank};
+use crate::modules::patreon::patreon_member;
+use crate::{Error, Result};
+
+use super::note::{UserNoteRow, UserNotesTable};
+use super::pager::{paginate, PAGE_SIZE};
+use super::{InfractionKind, InfractionRow};
+
+fn timestamp(timestamp: Timestamp) -> String {
+    format!("<t:{0}:f> (<t:{0}:R>)", timestamp.unix_timestamp())
+}
+
+fn infraction_field(infraction: &InfractionRow) -> (String, String, bool) {
+    let title = if infraction.voided {
+        format!("Case #{} (voided)", infraction.id)
+    } else {
+        format!("Case #{}", infraction.id)
+    };
+
+    let value = format!(
+        "**{}** ({} points) <t:{}:d> by {}\n{}",
+        infraction.infraction_type,
+        infraction.points,
+        infraction.created_at.and_utc().timestamp(),
+        infraction.moderator_username,
+        infraction.reason
+    );
+
+    (title, value, false)
+}
+
+pub struct Dossier;
+
+#[async_trait]
+impl SlashCommand<Error, Postgres> for Dossier {
+    async fn run(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: Vec<ResolvedOption<'_>>,
+        pool: &PgPool,
+    ) -> Result<()> {
+        interaction.defer_ephemeral(ctx).await.unwrap();
+
+        let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+        let mut options = parse_options(options);
+
+        let Some(ResolvedValue::User(user, member)) = options.remove("user") else {
+            unreachable!("User option is required");
+        };
+
+        let mut infractions = InfractionRow::user_infractions(pool, guild_id, user.id).await?;
+        infractions.reverse();
+        let notes = UserNotesTable::get_user_rows(pool, guild_id, user.id).await?;
+
+        let level = get_user_level_data(pool, guild_id, user.id).await?;
+        let rank = get_user_rank(pool, guild_id, user.id).await?;
+
+        let patreon = match patreon_member(pool, &user.id.to_string(), false).await? {
+            Some(member) => {
+                let member = member.data.attributes;
+                format!(
+                    "Linked\nCurrent Tier: **${}**\nLifetime Support: **${}**",
+                    member.currently_entitled_amount_cents / 100,
+                    member.campaign_lifetime_support_cents / 100
+                )
+            }
+            None => String::from("Not linked"),
+        };
+
+        let joined = match member {
+            Some(member) => member
+                .joined_at
+                .map_or_else(|| String::from("Unknown"), timestamp),
+            None => String::from("Not in the server"),
+        };
+
+        let overview = overview_page(
+            user,
+            &infractions,
+            &notes,
+            joined,
+            format!(
+                "Level {} ({} XP)\nRank #{}\n{} messages",
+                level.level,
+                level.total_xp,
+                rank.unwrap_or_default(),
+                level.message_count
+            ),
+            patreon,
+        );
+
+        let mut pages = vec![overview];
+        pages.extend(infractions.chunks(PAGE_SIZE).map(|chunk| {
+            CreateEmbed::new()
+                .title(format!("Cases for {}", user.name))
+                .fields(chunk.iter().map(infraction_field))
+        }));
+        pages.extend(notes.chunks(PAGE_SIZE).map(|chunk| {
+            CreateEmbed::new()
+                .title(format!("Notes for {}", user.name))
+                .description(
+                    chunk
+                        .iter()
+                        .map(UserNoteRow::line)
+                        .collect::<Vec<_>>()
+                        .join("\n\n"),
+                )
+        }));
+
+        let page_count = pages.len();
+        let page_embed = |page: usize| {
+            pages[page].clone().footer(CreateEmbedFooter::new(format!(
+                "Page {}/{}",
+                page + 1,
+                page_count
+            )))
+        };
+
+        paginate(ctx, interaction, "dossier", page_count, page_embed).await
+    }
+
+    fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
+        let command = CreateCommand::new("dossier")
+            .description("Everything on record about a member")
+            .default_member_permissions(Permissions::MODERATE_MEMBERS)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::User, "user", "The member")
+                    .required(true),
+            );
+
+        Ok(command)
+    }
+}
+
+fn overview_page(
+    user: &User,
+    infractions: &[InfractionRow],
+    notes: &[UserNoteRow],
+    joined: String,
+    level: String,
+    patreon: String,
+) -> CreateEmbed {
+    let active = infractions.iter().filter(|infraction| !infraction.voided);
+
+    let mut cases = InfractionKind::ALL
+        .iter()
+        .map(|kind| {
+            let kind = kind.to_string();
+            let count = active
+                .clone()
+                .filter(|infraction| infraction.infraction_type == kind)
+                .count();
+            (kind, count)
+        })
+        .filter(|(_, count)| *count > 0)
+        .map(|(kind, count)| format!("{}: {}", kind, count))
+        .collect::<Vec<_>>();
+
+    let voided = infractions.len() - active.count();
+    if voided > 0 {
+        cases.push(format!("Voided: {}", voided));
+    }
+
+    let cases = if cases.is_empty() {
+        String::from("None")
+    } else {
+        cases.join("\n")
+    };
+
+    let latest_note = notes
+        .first()
+        .map_or_else(|| String::from("None"), UserNoteRow::line);
+
+    CreateEmbed::new()
+        .title(format!("Dossier for {}", user.name))
+        .description(format!("({}) {}", user.id, user.name))
+        .thumbnail(user.face())
+        .field("Account created", timestamp(user.id.created_at()), false)
+        .field("Joined", joined, false)
+        .field("Levels", level, true)
+        .field("Patreon", patreon, true)
+        .field(format!("Cases ({})", infractions.len()), cases, true)
+        .field(format!("Latest note ({})", notes.len()), latest_note, false)
+}
@@ -0,0 +1,550 @@
+use std::fmt::Display;
+
+use chrono::{NaiveDateTime, TimeDelta};
+use serenity::all::{
+    CommandInteraction, Context, CreateEmbed, EditInteractionResponse, GuildId, Mentionable,
+    Permissions, ResolvedOption, ResolvedValue,
+};
+use sqlx::PgPool;
+use zayden_core::parse_options;
+
+use crate::modules::check_permissions;
+use crate::sqlx_lib::GuildTable;
+use crate::{Error, Result};
+
+use super::InfractionRow;
+
+/// The longest timeout Discord allows.
+pub const MAX_MUTE: TimeDelta = TimeDelta::days(28);
+
+/// Ten years, well past any sensible decay.
+pub const MAX_DECAY_DAYS: u64 = 3650;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscalationAction {
+    Warn,
+    Mute(TimeDelta),
+    Ban,
+}
+
+impl EscalationAction {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Warn => "warn",
+            Self::Mute(_) => "mute",
+            Self::Ban => "ban",
+        }
+    }
+
+    fn duration(&self) -> Option<TimeDelta> {
+        match self {
+            Self::Mute(duration) => Some(*duration),
+            _ => None,
+        }
+    }
+
+    fn from_parts(name: &str, duration: Option<TimeDelta>) -> Option<Self> {
+        match (name, duration) {
+            ("warn", _) => Some(Self::Warn),
+            ("mute", Some(duration)) => Some(Self::Mute(duration)),
+            ("ban", _) => Some(Self::Ban),
+            _ => None,
+        }
+    }
+}
+
+impl Display for EscalationAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mute(duration) => write!(f, "Mute for {}", format_duration(*duration)),
+            Self::Warn => write!(f, "Warn"),
+            Self::Ban => write!(f, "Ban"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EscalationStep {
+    /// The active points at which this step starts to apply.
+    pub threshold: i32,
+    pub action: EscalationAction,
+}
+
+/// The ladder of actions taken as a member's active points grow. Points older than `decay` no
+/// longer count. Guilds without any saved steps use the default ladder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscalationPolicy {
+    pub steps: Vec<EscalationStep>,
+    pub decay: TimeDelta,
+}
+
+impl Default for EscalationPolicy {
+    fn default() -> Self {
+        Self {
+            steps: vec![
+                EscalationStep {
+                    threshold: 1,
+                    action: EscalationAction::Warn,
+                },
+                EscalationStep {
+                    threshold: 2,
+                    action: EscalationAction::Mute(TimeDelta::hours(1)),
+                },
+                EscalationStep {
+                    threshold: 3,
+                    action: EscalationAction::Mute(TimeDelta::hours(8)),
+                },
+                EscalationStep {
+                    threshold: 4,
+                    action: EscalationAction::Mute(TimeDelta::days(28)),
+                },
+                EscalationStep {
+                    threshold: 5,
+                    action: EscalationAction::Ban,
+                },
+            ],
+            decay: TimeDelta::days(182),
+        }
+    }
+}
+
+impl EscalationPolicy {
+    /// The action for the highest threshold reached. Totals below every threshold are warned.
+    pub fn resolve(&self, points: i32) -> EscalationAction {
+        self.steps
+            .iter()
+            .filter(|step| step.threshold <= points)
+            .max_by_key(|step| step.threshold)
+            .map(|step| step.action)
+            .unwrap_or(EscalationAction::Warn)
+    }
+
+    /// Infractions created before this have decayed by `now`.
+    pub fn cutoff(&self, now: NaiveDateTime) -> NaiveDateTime {
+        now.checked_sub_signed(self.decay)
+            .unwrap_or(NaiveDateTime::MIN)
+    }
+
+    /// Sums the points of infractions that haven't been voided or decayed by `now`.
+    pub fn active_points(&self, infractions: &[InfractionRow], now: NaiveDateTime) -> i32 {
+        let cutoff = self.cutoff(now);
+
+        infractions
+            .iter()
+            .filter(|infraction| !infraction.voided && infraction.created_at >= cutoff)
+            .map(|infraction| infraction.points)
+            .sum()
+    }
+
+    pub fn set_step(&mut self, threshold: i32, action: EscalationAction) {
+        self.steps.retain(|step| step.threshold != threshold);
+        self.steps.push(EscalationStep { threshold, action });
+        self.steps.sort_by_key(|step| step.threshold);
+    }
+
+    pub fn remove_step(&mut self, threshold: i32) -> bool {
+        let len = self.steps.len();
+        self.steps.retain(|step| step.threshold != threshold);
+        self.steps.len() != len
+    }
+}
+
+/// Parses durations such as `30m`, `8h`, `28d` or `2w`.
+pub fn parse_duration(duration: &str) -> Option<TimeDelta> {
+    let duration = duration.trim();
+    let unit_index = duration.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = duration.split_at(unit_index);
+    let amount = amount.parse::<i64>().ok().filter(|&amount| amount > 0)?;
+
+    match unit.trim() {
+        "m" | "min" | "mins" | "minutes" => TimeDelta::try_minutes(amount),
+        "h" | "hr" | "hrs" | "hours" => TimeDelta::try_hours(amount),
+        "d" | "day" | "days" => TimeDelta::try_days(amount),
+        "w" | "week" | "weeks" => TimeDelta::try_weeks(amount),
+        _ => None,
+    }
+}
+
+pub fn format_duration(duration: TimeDelta) -> String {
+    let (amount, unit) = if duration.num_days() > 0 && duration.num_hours() % 24 == 0 {
+        (duration.num_days(), "day")
+    } else if duration.num_hours() > 0 && duration.num_minutes() % 60 == 0 {
+        (duration.num_hours(), "hour")
+    } else {
+        (duration.num_minutes(), "minute")
+    };
+
+    if amount == 1 {
+        format!("{} {}", amount, unit)
+    } else {
+        format!("{} {}s", amount, unit)
+    }
+}
+
+struct EscalationStepRow {
+    points: i32,
+    action: String,
+    duration_secs: Option<i32>,
+}
+
+impl GuildTable {
+    pub async fn get_escalation_policy(pool: &PgPool, id: GuildId) -> Result<EscalationPolicy> {
+        let default = EscalationPolicy::default();
+
+        let decay = sqlx::query!(
+            "SELECT infraction_decay_days FROM guilds WHERE id = $1",
+            id.get() as i64
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap()
+        .map_or(default.decay, |row| {
+            TimeDelta::days(row.infraction_decay_days as i64)
+        });
+
+        let rows = sqlx::query_as!(
+            EscalationStepRow,
+            "SELECT points, action, duration_secs FROM infraction_escalation WHERE guild_id = $1 ORDER BY points",
+            id.get() as i64
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        let steps = if rows.is_empty() {
+            default.steps
+        } else {
+            rows.into_iter()
+                .filter_map(|row| {
+                    let duration = row
+                        .duration_secs
+                        .map(|secs| TimeDelta::seconds(secs as i64));
+
+                    Some(EscalationStep {
+                        threshold: row.points,
+                        action: EscalationAction::from_parts(&row.action, duration)?,
+                    })
+                })
+                .collect()
+        };
+
+        Ok(EscalationPolicy { steps, decay })
+    }
+
+    pub async fn save_escalation_policy(
+        pool: &PgPool,
+        id: GuildId,
+        policy: &EscalationPolicy,
+    ) -> Result<()> {
+        let guild_id = id.get() as i64;
+
+        let mut tx = pool.begin().await.unwrap();
+
+        sqlx::query!(
+            "INSERT INTO guilds (id, infraction_decay_days) VALUES ($1, $2)
+             ON CONFLICT (id) DO UPDATE SET infraction_decay_days = $2",
+            guild_id,
+            policy.decay.num_days() as i32
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+        sqlx::query!(
+            "DELETE FROM infraction_escalation WHERE guild_id = $1",
+            guild_id
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+        for step in &policy.steps {
+            sqlx::query!(
+                "INSERT INTO infraction_escalation (guild_id, points, action, duration_secs) VALUES ($1, $2, $3, $4)",
+                guild_id,
+                step.threshold,
+                step.action.name(),
+                step.action.duration().map(|duration| duration.num_seconds() as i32)
+            )
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        }
+
+        tx.commit().await.unwrap();
+
+        Ok(())
+    }
+}
+
+pub async fn policy(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    mut options: Vec<ResolvedOption<'_>>,
+    pool: &PgPool,
+) -> Result<()> {
+    interaction.defer_ephemeral(ctx).await.unwrap();
+
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    let command = options.remove(0);
+    let ResolvedValue::SubCommand(options) = command.value else {
+        unreachable!("Subcommand is required");
+    };
+    let mut options = parse_options(options);
+
+    let mut policy = GuildTable::get_escalation_policy(pool, guild_id).await?;
+
+    if command.name != "view" {
+        check_permissions(interaction, Permissions::MANAGE_GUILD)?;
+    }
+
+    match command.name {
+        "view" => {}
+        "set" => {
+            let Some(ResolvedValue::Integer(points)) = options.remove("points") else {
+                unreachable!("Points option is required");
+            };
+            let Some(ResolvedValue::String(action)) = options.remove("action") else {
+                unreachable!("Action option is required");
+            };
+
+            let duration = match options.remove("duration") {
+                Some(ResolvedValue::String(duration)) => {
+                    Some(parse_duration(duration).ok_or(Error::InvalidDuration)?)
+                }
+                _ => None,
+            };
+
+            let action =
+                EscalationAction::from_parts(action, duration).ok_or(Error::InvalidDuration)?;
+
+            if action
+                .duration()
+                .is_some_and(|duration| duration > MAX_MUTE)
+            {
+                return Err(Error::InvalidDuration);
+            }
+
+            policy.set_step(points as i32, action);
+        }
+        "remove" => {
+            let Some(ResolvedValue::Integer(points)) = options.remove("points") else {
+                unreachable!("Points option is required");
+            };
+
+            policy.remove_step(points as i32);
+        }
+        "decay" => {
+            let Some(ResolvedValue::Integer(days)) = options.remove("days") else {
+                unreachable!("Days option is required");
+            };
+
+            policy.decay = TimeDelta::days(days.clamp(1, MAX_DECAY_DAYS as i64));
+        }
+        "mute-role" => {
+            let Some(ResolvedValue::Role(role)) = options.remove("role") else {
+                unreachable!("Role option is required");
+            };
+
+            GuildTable::save_mute_role(pool, guild_id, role.id).await?;
+        }
+        "log-channel" => {
+            let Some(ResolvedValue::Channel(channel)) = options.remove("channel") else {
+                unreachable!("Channel option is required");
+            };
+
+            GuildTable::save_mod_log_channel(pool, guild_id, channel.id).await?;
+        }
+        "report-channel" => {
+            let Some(ResolvedValue::Channel(channel)) = options.remove("channel") else {
+                unreachable!("Channel option is required");
+            };
+
+            GuildTable::save_report_channel(pool, guild_id, channel.id).await?;
+        }
+        _ => unreachable!("Unknown subcommand"),
+    }
+
+    if matches!(command.name, "set" | "remove" | "decay") {
+        GuildTable::save_escalation_policy(pool, guild_id, &policy).await?;
+        // Removing every step falls back to the default ladder.
+        policy = GuildTable::get_escalation_policy(pool, guild_id).await?;
+    }
+
+    let steps = policy
+        .steps
+        .iter()
+        .map(|step| format!("**{} points:** {}", step.threshold, step.action))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mute_role = match GuildTable::get_mute_role(pool, guild_id).await? {
+        Some(role_id) => role_id.mention().to_string(),
+        None => String::from("Not set"),
+    };
+
+    let log_channel = match GuildTable::get_mod_log_channel(pool, guild_id).await? {
+        Some(channel_id) => channel_id.mention().to_string(),
+        None => String::from("Not set"),
+    };
+
+    let report_channel = match GuildTable::get_report_channel(pool, guild_id).await? {
+        Some(channel_id) => channel_id.mention().to_string(),
+        None => String::from("Mod log"),
+    };
+
+    let embed = CreateEmbed::new()
+        .title("Infraction Policy")
+        .description(format!(
+            "{}\n\nPoints expire after {} days.\nMute role: {}\nMod log: {}\nReports: {}",
+            steps,
+            policy.decay.num_days(),
+            mute_role,
+            log_channel,
+            report_channel
+        ));
+
+    interaction
+        .edit_response(ctx, EditInteractionResponse::new().embed(embed))
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDateTime, TimeDelta, Utc};
+
+    use super::{parse_duration, EscalationAction, EscalationPolicy, EscalationStep};
+    use crate::modules::moderation::InfractionRow;
+
+    fn infraction(points: i32, days_ago: i64) -> InfractionRow {
+        InfractionRow {
+            id: 0,
+            user_id: 1,
+            username: String::new(),
+            guild_id: 1,
+            infraction_type: String::from("Warn"),
+            moderator_id: 2,
+            moderator_username: String::new(),
+            points,
+            reason: String::new(),
+            created_at: Utc::now().naive_utc() - TimeDelta::days(days_ago),
+            voided: false,
+        }
+    }
+
+    #[test]
+    fn default_matches_legacy_ladder() {
+        let policy = EscalationPolicy::default();
+
+        assert_eq!(policy.resolve(1), EscalationAction::Warn);
+        assert_eq!(
+            policy.resolve(2),
+            EscalationAction::Mute(TimeDelta::hours(1))
+        );
+        assert_eq!(
+            policy.resolve(3),
+            EscalationAction::Mute(TimeDelta::hours(8))
+        );
+        assert_eq!(
+            policy.resolve(4),
+            EscalationAction::Mute(TimeDelta::days(28))
+        );
+        assert_eq!(policy.resolve(5), EscalationAction::Ban);
+        assert_eq!(policy.resolve(50), EscalationAction::Ban);
+    }
+
+    #[test]
+    fn below_lowest_threshold_is_a_warning() {
+        let policy = EscalationPolicy {
+            steps: vec![EscalationStep {
+                threshold: 3,
+                action: EscalationAction::Ban,
+            }],
+            decay: TimeDelta::days(30),
+        };
+
+        assert_eq!(policy.resolve(0), EscalationAction::Warn);
+        assert_eq!(policy.resolve(2), EscalationAction::Warn);
+        assert_eq!(policy.resolve(3), EscalationAction::Ban);
+    }
+
+    #[test]
+    fn gaps_use_the_highest_threshold_reached() {
+        let mut policy = EscalationPolicy::default();
+        policy.remove_step(3);
+        policy.remove_step(4);
+        policy.set_step(10, EscalationAction::Mute(TimeDelta::days(7)));
+        policy.set_step(5, EscalationAction::Mute(TimeDelta::days(1)));
+
+        assert_eq!(
+            policy.resolve(3),
+            EscalationAction::Mute(TimeDelta::hours(1))
+        );
+        assert_eq!(
+            policy.resolve(7),
+            EscalationAction::Mute(TimeDelta::days(1))
+        );
+        assert_eq!(
+            policy.resolve(12),
+            EscalationAction::Mute(TimeDelta::days(7))
+        );
+    }
+
+    #[test]
+    fn decayed_points_are_ignored() {
+        let policy = EscalationPolicy {
+            decay: TimeDelta::days(30),
+            ..Default::default()
+        };
+        let now = Utc::now().naive_utc();
+
+        let infractions = [infraction(1, 1), infraction(2, 29), infraction(3, 31)];
+
+        let points = policy.active_points(&infractions, now);
+        assert_eq!(points, 3);
+        assert_eq!(
+            policy.resolve(points + 1),
+            EscalationAction::Mute(TimeDelta::days(28))
+        );
+    }
+
+    #[test]
+    fn huge_decay_keeps_everything() {
+        let policy = EscalationPolicy {
+            decay: TimeDelta::days(100_000_000),
+            ..Default::default()
+        };
+        let now = Utc::now().naive_utc();
+
+        assert_eq!(policy.cutoff(now), NaiveDateTime::MIN);
+        assert_eq!(policy.active_points(&[infraction(2, 5000)], now), 2);
+    }
+
+    #[test]
+    fn voided_points_are_ignored() {
+        let policy = EscalationPolicy::default();
+        let now = Utc::now().naive_utc();
+
+        let mut voided = infraction(3, 1);
+        voided.voided = true;
+        let infractions = [infraction(1, 2), voided];
+
+        assert_eq!(policy.active_points(&infractions, now), 1);
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("30m"), Some(TimeDelta::minutes(30)));
+        assert_eq!(parse_duration("8h"), Some(TimeDelta::hours(8)));
+        assert_eq!(parse_duration("28 days"), Some(TimeDelta::days(28)));
+        assert_eq!(parse_duration("2w"), Some(TimeDelta::weeks(2)));
+        assert_eq!(parse_duration("0h"), None);
+        assert_eq!(parse_duration("soon"), None);
+        assert_eq!(parse_duration("12"), None);
+        assert_eq!(parse_duration("99999999999999w"), None);
+    }
+}
@@ -0,0 +1,293 @@
+use chrono::Utc;
+use serde_json::{json, Value};
+use serenity::all::{
+    CommandInteraction, Context, CreateAttachment, EditInteractionResponse, GuildId,
+    ResolvedOption, ResolvedValue, UserId,
+};
+use sqlx::PgPool;
+use zayden_core::parse_options;
+
+use crate::{Error, Result};
+
+use super::logs::LogsFilter;
+use super::InfractionRow;
+
+/// The export columns, in order. Add new columns to the end so existing spreadsheets and scripts
+/// keep working.
+pub const COLUMNS: [&str; 11] = [
+    "case_id",
+    "guild_id",
+    "user_id",
+    "username",
+    "infraction_type",
+    "points",
+    "reason",
+    "moderator_id",
+    "moderator_username",
+    "created_at",
+    "voided",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+
+    pub fn serialise(&self, rows: &[InfractionRow]) -> String {
+        match self {
+            Self::Csv => to_csv(rows),
+            Self::Json => to_json(rows),
+        }
+    }
+}
+
+fn created_at(row: &InfractionRow) -> String {
+    row.created_at.and_utc().to_rfc3339()
+}
+
+/// Quotes a field when it contains a delimiter, quote or line break, doubling any quotes.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn to_csv(rows: &[InfractionRow]) -> String {
+    let mut csv = COLUMNS.join(",");
+    csv.push_str("\r\n");
+
+    for row in rows {
+        let fields = [
+            row.id.to_string(),
+            row.guild_id.to_string(),
+            row.user_id.to_string(),
+            csv_field(&row.username),
+            csv_field(&row.infraction_type),
+            row.points.to_string(),
+            csv_field(&row.reason),
+            row.moderator_id.to_string(),
+            csv_field(&row.moderator_username),
+            created_at(row),
+            row.voided.to_string(),
+        ];
+
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+
+    csv
+}
+
+/// IDs are written as strings since snowflakes don't fit in a JavaScript number.
+pub fn to_json(rows: &[InfractionRow]) -> String {
+    let rows = rows
+        .iter()
+        .map(|row| {
+            json!({
+                "case_id": row.id,
+                "guild_id": row.guild_id.to_string(),
+                "user_id": row.user_id.to_string(),
+                "username": row.username,
+                "infraction_type": row.infraction_type,
+                "points": row.points,
+                "reason": row.reason,
+                "moderator_id": row.moderator_id.to_string(),
+                "moderator_username": row.moderator_username,
+                "created_at": created_at(row),
+                "voided": row.voided,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string_pretty(&Value::Array(rows)).unwrap()
+}
+
+impl InfractionRow {
+    async fn export_rows(
+        pool: &PgPool,
+        guild_id: GuildId,
+        user_id: Option<UserId>,
+        filter: &LogsFilter,
+    ) -> Result<Vec<InfractionRow>> {
+        let rows = sqlx::query_as!(
+            InfractionRow,
+            "SELECT * FROM infractions
+             WHERE guild_id = $1
+             AND ($2::BIGINT IS NULL OR user_id = $2)
+             AND ($3::BIGINT IS NULL OR moderator_id = $3)
+             AND ($4::TEXT IS NULL OR infraction_type = $4)
+             AND ($5::TIMESTAMP IS NULL OR created_at >= $5)
+             AND ($6::TIMESTAMP IS NULL OR created_at <= $6)
+             ORDER BY id",
+            guild_id.get() as i64,
+            user_id.map(|id| id.get() as i64),
+            filter.moderator.map(|id| id.get() as i64),
+            filter.kind,
+            filter.since,
+            filter.until
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        Ok(rows)
+    }
+}
+
+pub async fn export(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    options: Vec<ResolvedOption<'_>>,
+    pool: &PgPool,
+) -> Result<()> {
+    interaction.defer_ephemeral(ctx).await.unwrap();
+
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    let mut options = parse_options(options);
+
+    let Some(ResolvedValue::String(format)) = options.remove("format") else {
+        unreachable!("Format option is required");
+    };
+    let format = ExportFormat::from_name(format).expect("Unknown format");
+
+    let user_id = match options.remove("user") {
+        Some(ResolvedValue::User(user, _)) => Some(user.id),
+        _ => None,
+    };
+
+    let filter = LogsFilter::from_options(&mut options)?;
+
+    let rows = InfractionRow::export_rows(pool, guild_id, user_id, &filter).await?;
+
+    let scope = match user_id {
+        Some(user_id) => user_id.to_string(),
+        None => guild_id.to_string(),
+    };
+
+    let filename = format!(
+        "infractions-{}-{}.{}",
+        scope,
+        Utc::now().format("%Y%m%d"),
+        format.extension()
+    );
+
+    let attachment = CreateAttachment::bytes(format.serialise(&rows), filename);
+
+    interaction
+        .edit_response(
+            ctx,
+            EditInteractionResponse::new()
+                .content(format!("Exported {} cases.", rows.len()))
+                .new_attachment(attachment),
+        )
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use serde_json::Value;
+
+    use super::{to_csv, to_json, COLUMNS};
+    use crate::modules::moderation::InfractionRow;
+
+    fn infraction(id: i32, reason: &str) -> InfractionRow {
+        InfractionRow {
+            id,
+            user_id: 245_664_578_051_375_104,
+            username: String::from("oscar"),
+            guild_id: 745_662_812_335_898_806,
+            infraction_type: String::from("Mute"),
+            moderator_id: 211_486_447_369_322_506,
+            moderator_username: String::from("mod"),
+            points: 2,
+            reason: reason.to_string(),
+            created_at: NaiveDate::from_ymd_opt(2025, 2, 14)
+                .unwrap()
+                .and_hms_opt(9, 30, 0)
+                .unwrap(),
+            voided: false,
+        }
+    }
+
+    #[test]
+    fn csv_has_stable_header() {
+        let csv = to_csv(&[]);
+
+        assert_eq!(
+            csv,
+            "case_id,guild_id,user_id,username,infraction_type,points,reason,moderator_id,moderator_username,created_at,voided\r\n"
+        );
+    }
+
+    #[test]
+    fn csv_writes_one_line_per_case() {
+        let csv = to_csv(&[infraction(7, "Spamming")]);
+        let lines = csv.split("\r\n").collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            "7,745662812335898806,245664578051375104,oscar,Mute,2,Spamming,211486447369322506,mod,2025-02-14T09:30:00+00:00,false"
+        );
+        assert_eq!(lines[2], "");
+    }
+
+    #[test]
+    fn csv_quotes_special_characters() {
+        let csv = to_csv(&[infraction(1, "Said \"hi\", then\nleft")]);
+        let line = csv.split("\r\n").nth(1).unwrap();
+
+        assert!(line.contains(",\"Said \"\"hi\"\", then\nleft\","));
+    }
+
+    #[test]
+    fn json_uses_the_same_columns() {
+        let json = to_json(&[infraction(3, "Spamming"), infraction(4, "Spamming")]);
+        let value: Value = serde_json::from_str(&json).unwrap();
+        let rows = value.as_array().unwrap();
+
+        assert_eq!(rows.len(), 2);
+
+        let row = rows[0].as_object().unwrap();
+        let mut keys = row.keys().map(String::as_str).collect::<Vec<_>>();
+        let mut columns = COLUMNS.to_vec();
+        keys.sort_unstable();
+        columns.sort_unstable();
+        assert_eq!(keys, columns);
+
+        assert_eq!(row["case_id"], 3);
+        assert_eq!(row["user_id"], "245664578051375104");
+        assert_eq!(row["points"], 2);
+        assert_eq!(row["created_at"], "2025-02-14T09:30:00+00:00");
+        assert_eq!(row["voided"], false);
+    }
+
+    #[test]
+    fn json_of_no_cases_is_an_empty_array() {
+        assert_eq!(to_json(&[]), "[]");
+    }
+}
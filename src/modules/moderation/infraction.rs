@@ -1,21 +1,23 @@
-use std::cmp;
-
 use async_trait::async_trait;
-use chrono::{Months, TimeDelta, Utc};
+use chrono::Utc;
 use serenity::all::{
-    CommandInteraction, CommandOptionType, CreateEmbed, CreateMessage, EditInteractionResponse,
-    Message, Ready, ResolvedOption, ResolvedValue, User, UserId,
+    ChannelType, CommandInteraction, CommandOptionType, EditInteractionResponse, Ready,
+    ResolvedOption, ResolvedValue,
 };
 use serenity::builder::{CreateCommand, CreateCommandOption};
-use serenity::model::prelude::GuildId;
-use serenity::model::{Permissions, Timestamp};
+use serenity::model::Permissions;
 use serenity::prelude::Context;
-use sqlx::{PgPool, Pool, Postgres};
+use sqlx::{PgPool, Postgres};
 use zayden_core::{parse_options, SlashCommand};
 
+use crate::modules::check_permissions;
+use crate::sqlx_lib::GuildTable;
 use crate::{Error, Result};
 
-use super::{InfractionKind, InfractionRow};
+use super::escalation::{parse_duration, policy, MAX_DECAY_DAYS};
+use super::mod_log::response_link;
+use super::sanction::{apply_sanction, Sanction, SanctionRequest, NO_REASON};
+use super::InfractionRow;
 
 pub struct Infraction;
 
@@ -24,313 +26,368 @@ impl SlashCommand<Error, Postgres> for Infraction {
     async fn run(
         ctx: &Context,
         interaction: &CommandInteraction,
-        options: Vec<ResolvedOption<'_>>,
+        mut options: Vec<ResolvedOption<'_>>,
         pool: &PgPool,
     ) -> Result<()> {
-        interaction.defer(ctx).await.unwrap();
-
-        let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
-
-        let mut options = parse_options(options);
-
-        let Some(ResolvedValue::User(user, _)) = options.remove("user") else {
-            unreachable!("User option is required");
-        };
-
-        let points = match options.remove("points") {
-            Some(ResolvedValue::Integer(points)) => points as i32,
-            _ => 1,
-        };
-
-        let reason = match options.remove("reason") {
-            Some(ResolvedValue::String(reason)) => reason,
-            _ => "No reason provided.",
-        };
-
-        let infractions = InfractionRow::user_infractions(pool, user.id, false).await?;
-
-        let six_months_age = Utc::now()
-            .checked_sub_months(Months::new(6))
-            .unwrap()
-            .naive_utc();
-
-        let infractions: Vec<_> = infractions
-            .iter()
-            .filter(|infraction| infraction.created_at >= six_months_age)
-            .collect();
-        let infraction_count = infractions
-            .into_iter()
-            .map(|infraction| infraction.points)
-            .sum::<i32>();
-        let infraction_count = cmp::min(infraction_count + points, 5);
-
-        let embed = match infraction_count {
-            1 => warn(ctx, pool, guild_id, user, &interaction.user, points, reason).await?,
-            2 => {
-                mute(
-                    ctx,
-                    pool,
-                    guild_id,
-                    user,
-                    &interaction.user,
-                    TimeDelta::hours(1),
-                    points,
-                    reason,
-                )
-                .await?
+        let command = options.remove(0);
+
+        match (command.name, command.value) {
+            ("add", ResolvedValue::SubCommand(options)) => {
+                add(ctx, interaction, options, pool).await?
             }
-            3 => {
-                mute(
-                    ctx,
-                    pool,
-                    guild_id,
-                    user,
-                    &interaction.user,
-                    TimeDelta::hours(8),
-                    points,
-                    reason,
-                )
-                .await?
+            ("policy", ResolvedValue::SubCommandGroup(options)) => {
+                policy(ctx, interaction, options, pool).await?
             }
-            4 => {
-                mute(
-                    ctx,
-                    pool,
-                    guild_id,
-                    user,
-                    &interaction.user,
-                    TimeDelta::days(28),
-                    points,
-                    reason,
-                )
-                .await?
+            (name, ResolvedValue::SubCommand(options)) => {
+                sanction(ctx, interaction, name, options, pool).await?
             }
-            5 => ban(ctx, pool, guild_id, user, &interaction.user, points, reason).await?,
-            _ => unreachable!("Invalid infraction count"),
-        };
-
-        interaction
-            .edit_response(ctx, EditInteractionResponse::new().embed(embed))
-            .await
-            .unwrap();
+            _ => unreachable!("Unknown subcommand"),
+        }
 
         Ok(())
     }
 
     fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
-        let command = CreateCommand::new("infraction")
-            .name("infraction")
-            .description("Warn, mute, or ban a user")
-            .default_member_permissions(Permissions::MODERATE_MEMBERS)
-            .add_option(
-                CreateCommandOption::new(
-                    CommandOptionType::User,
-                    "user",
-                    "The user to warn, mute, or ban",
-                )
-                .required(true),
+        let add = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "add",
+            "Warn, mute, or ban a user",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::User,
+                "user",
+                "The user to warn, mute, or ban",
             )
-            .add_option(CreateCommandOption::new(
+            .required(true),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
                 CommandOptionType::Integer,
                 "points",
                 "The number of infractions to give the user",
-            ))
-            .add_option(CreateCommandOption::new(
+            )
+            .min_int_value(1),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
                 CommandOptionType::String,
                 "reason",
                 "The reason for the infraction",
-            ));
+            )
+            .max_length(255),
+        );
+
+        let kick = sanction_option(
+            "kick",
+            "Kick a user and record it as an infraction",
+            "The user to kick",
+        );
+
+        let softban = sanction_option(
+            "softban",
+            "Ban and unban a user to delete their recent messages",
+            "The user to soft ban",
+        );
+
+        let tempban = sanction_option(
+            "tempban",
+            "Ban a user until the duration expires",
+            "The user to ban",
+        )
+        .add_sub_option(duration_option());
+
+        let rolemute = sanction_option(
+            "rolemute",
+            "Give a user the mute role until the duration expires",
+            "The user to mute",
+        )
+        .add_sub_option(duration_option());
+
+        let policy = CreateCommandOption::new(
+            CommandOptionType::SubCommandGroup,
+            "policy",
+            "View or edit the escalation policy",
+        )
+        .add_sub_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "view",
+            "View the escalation policy",
+        ))
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "set",
+                "Set the action taken once a user reaches a number of points",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "points",
+                    "The active points that trigger the action",
+                )
+                .min_int_value(1)
+                .required(true),
+            )
+            .add_sub_option(
+                CreateCommandOption::new(CommandOptionType::String, "action", "The action to take")
+                    .add_string_choice("Warn", "warn")
+                    .add_string_choice("Mute", "mute")
+                    .add_string_choice("Ban", "ban")
+                    .required(true),
+            )
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "duration",
+                "How long to mute for, such as 30m, 8h or 7d",
+            )),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "remove",
+                "Remove the action at a number of points",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "points",
+                    "The points of the step to remove",
+                )
+                .required(true),
+            ),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "decay",
+                "Set how long infraction points count towards escalation",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "days",
+                    "The number of days points stay active",
+                )
+                .min_int_value(1)
+                .max_int_value(MAX_DECAY_DAYS)
+                .required(true),
+            ),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "mute-role",
+                "Set the role given to users muted with /infraction rolemute",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(CommandOptionType::Role, "role", "The mute role")
+                    .required(true),
+            ),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "log-channel",
+                "Set the channel moderation actions are logged to",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Channel,
+                    "channel",
+                    "The mod log channel",
+                )
+                .channel_types(vec![ChannelType::Text])
+                .required(true),
+            ),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "report-channel",
+                "Set the channel member reports are sent to. Defaults to the mod log",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Channel,
+                    "channel",
+                    "The report queue channel",
+                )
+                .channel_types(vec![ChannelType::Text])
+                .required(true),
+            ),
+        );
+
+        let command = CreateCommand::new("infraction")
+            .description("Warn, mute, or ban a user")
+            .default_member_permissions(Permissions::MODERATE_MEMBERS)
+            .add_option(add)
+            .add_option(kick)
+            .add_option(softban)
+            .add_option(tempban)
+            .add_option(rolemute)
+            .add_option(policy);
 
         Ok(command)
     }
 }
 
-async fn send_user_message(
+async fn add(
     ctx: &Context,
-    user_id: UserId,
-    kind: InfractionKind,
-    desc: impl Into<String>,
-) -> Result<Message> {
-    let title = match kind {
-        InfractionKind::Warn => "You have been warned",
-        InfractionKind::Mute => "You have been muted",
-        InfractionKind::Kick => "You have been kicked",
-        InfractionKind::SoftBan => "You have been softbanned",
-        InfractionKind::Ban => "You have been banned",
+    interaction: &CommandInteraction,
+    options: Vec<ResolvedOption<'_>>,
+    pool: &PgPool,
+) -> Result<()> {
+    interaction.defer(ctx).await.unwrap();
+
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    let mut options = parse_options(options);
+
+    let Some(ResolvedValue::User(user, _)) = options.remove("user") else {
+        unreachable!("User option is required");
+    };
+
+    let points = match options.remove("points") {
+        Some(ResolvedValue::Integer(points)) => points as i32,
+        _ => 1,
     };
 
-    let embed = CreateEmbed::new().title(title).description(desc);
+    let reason = match options.remove("reason") {
+        Some(ResolvedValue::String(reason)) => reason,
+        _ => NO_REASON,
+    };
 
-    let message = user_id
-        .direct_message(ctx, CreateMessage::new().embed(embed))
+    let policy = GuildTable::get_escalation_policy(pool, guild_id).await?;
+    let infractions = InfractionRow::user_infractions(pool, guild_id, user.id).await?;
+    let active_points = policy.active_points(&infractions, Utc::now().naive_utc()) + points;
+
+    let embed = apply_sanction(
+        ctx,
+        pool,
+        SanctionRequest {
+            guild_id,
+            user,
+            moderator: &interaction.user,
+            sanction: policy.resolve(active_points).into(),
+            points,
+            reason,
+            jump_url: response_link(ctx, interaction).await,
+        },
+    )
+    .await?
+    .embed;
+
+    interaction
+        .edit_response(ctx, EditInteractionResponse::new().embed(embed))
         .await
         .unwrap();
 
-    Ok(message)
+    Ok(())
 }
 
-async fn warn<'a>(
+async fn sanction(
     ctx: &Context,
+    interaction: &CommandInteraction,
+    name: &str,
+    options: Vec<ResolvedOption<'_>>,
     pool: &PgPool,
-    guild_id: GuildId,
-    user: &User,
-    moderator: &User,
-    points: i32,
-    reason: &str,
-) -> Result<CreateEmbed> {
-    InfractionRow::new(
-        user.id,
-        &user.name,
-        guild_id,
-        InfractionKind::Warn,
-        moderator,
-        points,
-        reason,
-    )?
-    .create(pool)
-    .await?;
-
-    let desc = if reason == "No reason provided." {
-        format!(
-            "You have been warned in {}.",
-            guild_id.to_partial_guild(ctx).await.unwrap().name
-        )
-    } else {
-        format!(
-            "You have been warned in {} for the following reason:\n{}",
-            guild_id.to_partial_guild(ctx).await.unwrap().name,
-            reason
-        )
-    };
+) -> Result<()> {
+    interaction.defer(ctx).await.unwrap();
 
-    send_user_message(ctx, user.id, InfractionKind::Warn, desc).await?;
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
 
-    let mut embed = CreateEmbed::new().title(format!("{} has been warned", user.name));
-    if reason != "No reason provided." {
-        embed = embed.description(reason);
-    }
-    Ok(embed)
-}
+    let mut options = parse_options(options);
 
-#[allow(clippy::too_many_arguments)]
-async fn mute<'a>(
-    ctx: &Context,
-    pool: &Pool<Postgres>,
-    guild_id: GuildId,
-    user: &User,
-    moderator: &User,
-    duration: TimeDelta,
-    points: i32,
-    reason: &str,
-) -> Result<CreateEmbed> {
-    let mut member = guild_id.member(ctx, user.id).await.unwrap();
-
-    let timestamp = (Utc::now() + duration).timestamp();
-    member
-        .disable_communication_until_datetime(
-            ctx,
-            Timestamp::from_unix_timestamp(timestamp).unwrap(),
-        )
-        .await
-        .unwrap();
+    let duration = match options.remove("duration") {
+        Some(ResolvedValue::String(duration)) => parse_duration(duration),
+        _ => None,
+    };
 
-    InfractionRow::new(
-        user.id,
-        &user.name,
-        guild_id,
-        InfractionKind::Ban,
-        moderator,
-        points,
-        reason,
-    )?
-    .create(pool)
-    .await?;
-
-    let days = duration.num_days();
-    let hours = duration.num_hours() % 24;
-
-    let mut duration_str = String::new();
-    if days > 0 {
-        duration_str.push_str(&format!("{} day", days));
-        if days > 1 {
-            duration_str.push('s');
-        }
-    } else if hours > 0 {
-        duration_str.push_str(&format!("{} hour", hours));
-        if hours > 1 {
-            duration_str.push('s');
+    let sanction = match (name, duration) {
+        ("kick", _) => Sanction::Kick,
+        ("softban", _) => Sanction::SoftBan,
+        ("tempban", Some(duration)) => Sanction::TempBan(duration),
+        ("rolemute", Some(duration)) => Sanction::RoleMute(duration),
+        ("tempban" | "rolemute", None) => return Err(Error::InvalidDuration),
+        _ => unreachable!("Unknown subcommand"),
+    };
+
+    match sanction {
+        Sanction::Kick => check_permissions(interaction, Permissions::KICK_MEMBERS)?,
+        Sanction::SoftBan | Sanction::TempBan(_) => {
+            check_permissions(interaction, Permissions::BAN_MEMBERS)?
         }
+        _ => {}
     }
 
-    let desc: String = if reason == "No reason provided." {
-        format!(
-            "You have been muted in {} for {}.",
-            guild_id.to_partial_guild(ctx).await.unwrap().name,
-            duration_str
-        )
-    } else {
-        format!(
-            "You have been muted in {} for {}\n{}",
-            guild_id.to_partial_guild(ctx).await.unwrap().name,
-            duration_str,
-            reason
-        )
+    let Some(ResolvedValue::User(user, _)) = options.remove("user") else {
+        unreachable!("User option is required");
     };
 
-    send_user_message(ctx, user.id, InfractionKind::Mute, &desc).await?;
-
-    let mut embed = CreateEmbed::new().title(format!("{} has been muted", user.name));
-    if reason != "No reason provided." {
-        embed = embed.description(reason);
-    }
-    Ok(embed)
-}
+    let points = match options.remove("points") {
+        Some(ResolvedValue::Integer(points)) => points as i32,
+        _ => 1,
+    };
 
-async fn ban<'a>(
-    ctx: &Context,
-    pool: &Pool<Postgres>,
-    guild_id: GuildId,
-    user: &User,
-    moderator: &User,
-    points: i32,
-    reason: &str,
-) -> Result<CreateEmbed> {
-    let member = guild_id.member(ctx, user.id).await.unwrap();
-
-    let desc = if reason == "No reason provided." {
-        format!(
-            "You have been banned from {}.",
-            guild_id.to_partial_guild(ctx).await.unwrap().name
-        )
-    } else {
-        format!(
-            "You have been banned from {} for the following reason:\n{}",
-            guild_id.to_partial_guild(ctx).await.unwrap().name,
-            reason
-        )
+    let reason = match options.remove("reason") {
+        Some(ResolvedValue::String(reason)) => reason,
+        _ => NO_REASON,
     };
 
-    send_user_message(ctx, user.id, InfractionKind::Ban, desc)
+    let embed = apply_sanction(
+        ctx,
+        pool,
+        SanctionRequest {
+            guild_id,
+            user,
+            moderator: &interaction.user,
+            sanction,
+            points,
+            reason,
+            jump_url: response_link(ctx, interaction).await,
+        },
+    )
+    .await?
+    .embed;
+
+    interaction
+        .edit_response(ctx, EditInteractionResponse::new().embed(embed))
         .await
         .unwrap();
 
-    member.ban_with_reason(ctx, 1, reason).await.unwrap();
-
-    InfractionRow::new(
-        user.id,
-        &user.name,
-        guild_id,
-        InfractionKind::Ban,
-        moderator,
-        points,
-        reason,
-    )?
-    .create(pool)
-    .await?;
-
-    let mut embed = CreateEmbed::new().title(format!("{} has been banned", user.name));
-    if reason != "No reason provided." {
-        embed = embed.description(reason);
-    }
-    Ok(embed)
+    Ok(())
+}
+
+fn sanction_option(name: &str, description: &str, user_description: &str) -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::SubCommand, name, description)
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::User, "user", user_description)
+                .required(true),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "points",
+                "The number of infractions to give the user",
+            )
+            .min_int_value(0),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "reason",
+                "The reason for the infraction",
+            )
+            .max_length(255),
+        )
+}
+
+fn duration_option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::String,
+        "duration",
+        "How long until it's lifted, such as 12h, 7d or 4w",
+    )
+    .required(true)
 }
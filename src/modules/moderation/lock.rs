@@ -0,0 +1,718 @@
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, TimeDelta};
+use serenity::all::{
+    ChannelId, ChannelType, CommandInteraction, CommandOptionType, Context, CreateCommand,
+    CreateCommandOption, CreateEmbed, CreateMessage, DiscordJsonError, EditChannel,
+    EditInteractionResponse, ErrorResponse, GuildChannel, GuildId, HttpError, Mentionable,
+    PermissionOverwrite, PermissionOverwriteType, Permissions, Ready, ResolvedOption,
+    ResolvedValue, RoleId, Timestamp, User,
+};
+use sqlx::{PgPool, Postgres};
+use zayden_core::{parse_options, SlashCommand};
+
+use crate::{Error, Result};
+
+use super::escalation::{format_duration, parse_duration};
+use super::mod_log::send_mod_log;
+use super::scheduled::{expires_at, ScheduledActionKind, ScheduledActionsTable};
+
+/// What a lock takes away from @everyone.
+const LOCKED: Permissions = Permissions::SEND_MESSAGES
+    .union(Permissions::SEND_MESSAGES_IN_THREADS)
+    .union(Permissions::CREATE_PUBLIC_THREADS)
+    .union(Permissions::CREATE_PRIVATE_THREADS)
+    .union(Permissions::ADD_REACTIONS);
+
+/// Discord's longest slowmode, 6 hours.
+const MAX_SLOWMODE: u16 = 21600;
+
+/// The @everyone overwrite while locked. Everything else the overwrite allowed or denied is kept.
+pub fn locked_overwrite(allow: Permissions, deny: Permissions) -> (Permissions, Permissions) {
+    (allow.difference(LOCKED), deny.union(LOCKED))
+}
+
+fn everyone(guild_id: GuildId) -> RoleId {
+    RoleId::new(guild_id.get())
+}
+
+fn everyone_overwrite(channel: &GuildChannel) -> Option<&PermissionOverwrite> {
+    channel.permission_overwrites.iter().find(|overwrite| {
+        overwrite.kind == PermissionOverwriteType::Role(everyone(channel.guild_id))
+    })
+}
+
+/// Channels members can see and talk in. Hidden channels are left alone by a server-wide lock.
+fn lockable(channel: &GuildChannel) -> bool {
+    matches!(
+        channel.kind,
+        ChannelType::Text
+            | ChannelType::News
+            | ChannelType::Forum
+            | ChannelType::Voice
+            | ChannelType::Stage
+    ) && !everyone_overwrite(channel)
+        .is_some_and(|overwrite| overwrite.deny.contains(Permissions::VIEW_CHANNEL))
+}
+
+#[allow(dead_code)]
+pub struct ChannelLockRow {
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub allow: Option<i64>,
+    pub deny: Option<i64>,
+    pub guild_wide: bool,
+    pub moderator_id: i64,
+    pub created_at: NaiveDateTime,
+}
+
+impl ChannelLockRow {
+    pub fn channel_id(&self) -> ChannelId {
+        ChannelId::new(self.channel_id as u64)
+    }
+
+    /// The @everyone overwrite before the lock, if the channel had one.
+    pub fn original(&self) -> Option<(Permissions, Permissions)> {
+        let allow = self.allow?;
+        let deny = self.deny?;
+
+        Some((
+            Permissions::from_bits_truncate(allow as u64),
+            Permissions::from_bits_truncate(deny as u64),
+        ))
+    }
+}
+
+pub struct ChannelLocksTable;
+
+impl ChannelLocksTable {
+    /// Returns false if the channel was already locked, keeping the original overwrite saved by
+    /// the first lock.
+    pub async fn insert(
+        pool: &PgPool,
+        channel: &GuildChannel,
+        guild_wide: bool,
+        moderator: &User,
+    ) -> Result<bool> {
+        let original = everyone_overwrite(channel);
+
+        let result = sqlx::query!(
+            "INSERT INTO channel_locks (guild_id, channel_id, allow, deny, guild_wide, moderator_id) VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT DO NOTHING",
+            channel.guild_id.get() as i64,
+            channel.id.get() as i64,
+            original.map(|overwrite| overwrite.allow.bits() as i64),
+            original.map(|overwrite| overwrite.deny.bits() as i64),
+            guild_wide,
+            moderator.id.get() as i64
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get(
+        pool: &PgPool,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+    ) -> Result<Option<ChannelLockRow>> {
+        let row = sqlx::query_as!(
+            ChannelLockRow,
+            "SELECT * FROM channel_locks WHERE guild_id = $1 AND channel_id = $2",
+            guild_id.get() as i64,
+            channel_id.get() as i64
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        Ok(row)
+    }
+
+    pub async fn delete(
+        pool: &PgPool,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+    ) -> Result<Option<ChannelLockRow>> {
+        let row = sqlx::query_as!(
+            ChannelLockRow,
+            "DELETE FROM channel_locks WHERE guild_id = $1 AND channel_id = $2 RETURNING *",
+            guild_id.get() as i64,
+            channel_id.get() as i64
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        Ok(row)
+    }
+
+    /// Every lock made by a server-wide lock. Channels locked on their own aren't included.
+    pub async fn get_guild_wide(pool: &PgPool, guild_id: GuildId) -> Result<Vec<ChannelLockRow>> {
+        let rows = sqlx::query_as!(
+            ChannelLockRow,
+            "SELECT * FROM channel_locks WHERE guild_id = $1 AND guild_wide",
+            guild_id.get() as i64
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        Ok(rows)
+    }
+}
+
+/// Fetches the channel to lock or unlock. Threads have no overwrites of their own, so a thread
+/// resolves to its parent channel.
+async fn target_channel(ctx: &Context, channel_id: ChannelId) -> Result<GuildChannel> {
+    let Some(channel) = channel_id.to_channel(ctx).await?.guild() else {
+        unreachable!("Guild commands always run in a server channel");
+    };
+
+    match (channel.kind, channel.parent_id) {
+        (
+            ChannelType::PublicThread | ChannelType::PrivateThread | ChannelType::NewsThread,
+            Some(parent_id),
+        ) => {
+            let Some(parent) = parent_id.to_channel(ctx).await?.guild() else {
+                unreachable!("Threads always belong to a server channel");
+            };
+
+            Ok(parent)
+        }
+        _ => Ok(channel),
+    }
+}
+
+/// Denies sending messages, then saves the channel's original @everyone overwrite so it can be
+/// restored.
+async fn lock_channel(
+    ctx: &Context,
+    pool: &PgPool,
+    channel: &GuildChannel,
+    guild_wide: bool,
+    moderator: &User,
+) -> Result<()> {
+    let (allow, deny) = match everyone_overwrite(channel) {
+        Some(overwrite) => locked_overwrite(overwrite.allow, overwrite.deny),
+        None => locked_overwrite(Permissions::empty(), Permissions::empty()),
+    };
+
+    channel
+        .id
+        .create_permission(
+            ctx,
+            PermissionOverwrite {
+                allow,
+                deny,
+                kind: PermissionOverwriteType::Role(everyone(channel.guild_id)),
+            },
+        )
+        .await?;
+
+    // A lock that ran at the same time saved the overwrite first, and its copy is the original.
+    if !ChannelLocksTable::insert(pool, channel, guild_wide, moderator).await? {
+        return Err(Error::ChannelAlreadyLocked);
+    }
+
+    Ok(())
+}
+
+/// Puts the @everyone overwrite back exactly as it was. Unknown Channel means the channel was
+/// deleted while locked, so there's nothing left to restore.
+async fn restore(ctx: &Context, guild_id: GuildId, row: &ChannelLockRow) -> Result<()> {
+    let kind = PermissionOverwriteType::Role(everyone(guild_id));
+
+    let result = match row.original() {
+        Some((allow, deny)) => {
+            row.channel_id()
+                .create_permission(ctx, PermissionOverwrite { allow, deny, kind })
+                .await
+        }
+        None => row.channel_id().delete_permission(ctx, kind).await,
+    };
+
+    match result {
+        Err(serenity::Error::Http(HttpError::UnsuccessfulRequest(ErrorResponse {
+            error: DiscordJsonError { code: 10003, .. },
+            ..
+        }))) => Ok(()),
+        result => result.map_err(Error::from),
+    }
+}
+
+fn log_embed(
+    title: &str,
+    colour: u32,
+    target: String,
+    moderator: Option<&User>,
+    duration: Option<TimeDelta>,
+    reason: &str,
+) -> CreateEmbed {
+    let moderator = match moderator {
+        Some(moderator) => format!("{} ({})", moderator.mention(), moderator.id),
+        None => String::from("Automatic"),
+    };
+
+    let mut embed = CreateEmbed::new()
+        .title(title)
+        .colour(colour)
+        .field("Target", target, true)
+        .field("Moderator", moderator, true);
+
+    if let Some(duration) = duration {
+        embed = embed.field("Duration", format_duration(duration), true);
+    }
+
+    embed
+        .field("Reason", reason, false)
+        .timestamp(Timestamp::now())
+}
+
+/// Restores the channel, or every channel of a server-wide lock when `channel_id` is `None`, and
+/// logs it. Returns how many channels were unlocked.
+///
+/// A lock is only forgotten once its channel is restored. If any channel fails, the rest are still
+/// unlocked and logged, pending automatic unlocks are kept and [`Error::UnlockFailed`] is returned
+/// so it can be retried. Otherwise pending automatic unlocks for the same target are cancelled.
+pub async fn unlock(
+    ctx: &Context,
+    pool: &PgPool,
+    guild_id: GuildId,
+    channel_id: Option<ChannelId>,
+    moderator: Option<&User>,
+    reason: &str,
+) -> Result<usize> {
+    let rows = match channel_id {
+        Some(channel_id) => ChannelLocksTable::get(pool, guild_id, channel_id)
+            .await?
+            .into_iter()
+            .collect(),
+        None => ChannelLocksTable::get_guild_wide(pool, guild_id).await?,
+    };
+
+    let mut unlocked = 0;
+    let mut failed = 0;
+
+    for row in &rows {
+        match restore(ctx, guild_id, row).await {
+            Ok(()) => {
+                ChannelLocksTable::delete(pool, guild_id, row.channel_id()).await?;
+                unlocked += 1;
+            }
+            Err(e) => {
+                eprintln!("Error unlocking {}: {:?}", row.channel_id(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    if unlocked > 0 {
+        let (title, target) = match channel_id {
+            Some(channel_id) => ("Channel Unlocked", channel_id.mention().to_string()),
+            None => ("Server Unlocked", format!("{} channels", unlocked)),
+        };
+
+        let embed = log_embed(title, 0x57F287, target, moderator, None, reason);
+        send_mod_log(ctx, pool, guild_id, CreateMessage::new().embed(embed)).await?;
+    }
+
+    if failed > 0 {
+        return Err(Error::UnlockFailed);
+    }
+
+    ScheduledActionsTable::delete_unlocks(pool, guild_id, channel_id).await?;
+
+    Ok(unlocked)
+}
+
+fn target_options(command: CreateCommand, action: &str) -> CreateCommand {
+    command
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Channel,
+                "channel",
+                format!("The channel to {}, defaults to this one", action),
+            )
+            .channel_types(vec![
+                ChannelType::Text,
+                ChannelType::News,
+                ChannelType::Forum,
+                ChannelType::Voice,
+                ChannelType::Stage,
+            ]),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "server",
+            format!("{} every channel members can see", action),
+        ))
+}
+
+pub struct Lock;
+
+#[async_trait]
+impl SlashCommand<Error, Postgres> for Lock {
+    async fn run(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: Vec<ResolvedOption<'_>>,
+        pool: &PgPool,
+    ) -> Result<()> {
+        interaction.defer_ephemeral(ctx).await.unwrap();
+
+        let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+        let mut options = parse_options(options);
+
+        let server = matches!(options.remove("server"), Some(ResolvedValue::Boolean(true)));
+
+        let duration = match options.remove("duration") {
+            Some(ResolvedValue::String(duration)) => {
+                Some(parse_duration(duration).ok_or(Error::InvalidDuration)?)
+            }
+            _ => None,
+        };
+
+        let reason = match options.remove("reason") {
+            Some(ResolvedValue::String(reason)) => reason,
+            _ => "No reason provided.",
+        };
+
+        let (target, title, mut content) = if server {
+            let channels = guild_id.channels(ctx).await?;
+
+            // One channel Discord refuses shouldn't leave the others locked without a log entry
+            // or a scheduled unlock.
+            let mut locked = 0;
+            let mut errors = Vec::new();
+            for channel in channels.values().filter(|channel| lockable(channel)) {
+                if ChannelLocksTable::get(pool, guild_id, channel.id)
+                    .await?
+                    .is_some()
+                {
+                    continue;
+                }
+
+                match lock_channel(ctx, pool, channel, true, &interaction.user).await {
+                    Ok(()) => locked += 1,
+                    Err(Error::ChannelAlreadyLocked) => {}
+                    Err(e) => {
+                        eprintln!("Error locking {}: {:?}", channel.id, e);
+                        errors.push(e);
+                    }
+                }
+            }
+
+            if locked == 0 && !errors.is_empty() {
+                return Err(errors.remove(0));
+            }
+
+            if locked == 0 {
+                interaction
+                    .edit_response(
+                        ctx,
+                        EditInteractionResponse::new().content("Every channel is already locked."),
+                    )
+                    .await
+                    .unwrap();
+
+                return Ok(());
+            }
+
+            let mut content = format!("Locked {} channels.", locked);
+            if !errors.is_empty() {
+                content.push_str(&format!(
+                    " {} channels couldn't be locked. Check the bot's Manage Channels permission in them.",
+                    errors.len()
+                ));
+            }
+
+            (None, "Server Locked", content)
+        } else {
+            let channel_id = match options.remove("channel") {
+                Some(ResolvedValue::Channel(channel)) => channel.id,
+                _ => interaction.channel_id,
+            };
+
+            let channel = target_channel(ctx, channel_id).await?;
+            let channel_id = channel.id;
+
+            if ChannelLocksTable::get(pool, guild_id, channel_id)
+                .await?
+                .is_some()
+            {
+                return Err(Error::ChannelAlreadyLocked);
+            }
+
+            lock_channel(ctx, pool, &channel, false, &interaction.user).await?;
+
+            let _ = channel_id
+                .send_message(
+                    ctx,
+                    CreateMessage::new().embed(
+                        CreateEmbed::new()
+                            .title("🔒 Channel locked")
+                            .description(reason)
+                            .colour(0xED4245),
+                    ),
+                )
+                .await;
+
+            (
+                Some(channel_id),
+                "Channel Locked",
+                format!("Locked {}.", channel_id.mention()),
+            )
+        };
+
+        if let Some(duration) = duration {
+            ScheduledActionsTable::insert(
+                pool,
+                guild_id,
+                interaction.user.id,
+                ScheduledActionKind::Unlock(target),
+                expires_at(duration)?,
+                &interaction.user,
+            )
+            .await?;
+
+            content.push_str(&format!(
+                " It unlocks automatically in {}.",
+                format_duration(duration)
+            ));
+        }
+
+        interaction
+            .edit_response(ctx, EditInteractionResponse::new().content(content))
+            .await
+            .unwrap();
+
+        let target = match target {
+            Some(channel_id) => channel_id.mention().to_string(),
+            None => String::from("Every visible channel"),
+        };
+
+        let embed = log_embed(
+            title,
+            0xED4245,
+            target,
+            Some(&interaction.user),
+            duration,
+            reason,
+        );
+
+        send_mod_log(ctx, pool, guild_id, CreateMessage::new().embed(embed)).await
+    }
+
+    fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
+        let command = CreateCommand::new("lock")
+            .description("Stop members from sending messages")
+            .default_member_permissions(Permissions::MANAGE_CHANNELS);
+
+        let command = target_options(command, "lock")
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "duration",
+                "Unlock automatically after this long, such as 30m, 8h or 7d",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "reason",
+                "Why the channel is locked",
+            ));
+
+        Ok(command)
+    }
+}
+
+pub struct Unlock;
+
+#[async_trait]
+impl SlashCommand<Error, Postgres> for Unlock {
+    async fn run(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: Vec<ResolvedOption<'_>>,
+        pool: &PgPool,
+    ) -> Result<()> {
+        interaction.defer_ephemeral(ctx).await.unwrap();
+
+        let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+        let mut options = parse_options(options);
+
+        let server = matches!(options.remove("server"), Some(ResolvedValue::Boolean(true)));
+
+        let content = if server {
+            let unlocked = unlock(
+                ctx,
+                pool,
+                guild_id,
+                None,
+                Some(&interaction.user),
+                "Unlocked by a moderator",
+            )
+            .await?;
+
+            if unlocked == 0 {
+                return Err(Error::NotLockedDown);
+            }
+
+            format!("Unlocked {} channels.", unlocked)
+        } else {
+            let channel_id = match options.remove("channel") {
+                Some(ResolvedValue::Channel(channel)) => channel.id,
+                _ => interaction.channel_id,
+            };
+            let channel_id = target_channel(ctx, channel_id).await?.id;
+
+            let unlocked = unlock(
+                ctx,
+                pool,
+                guild_id,
+                Some(channel_id),
+                Some(&interaction.user),
+                "Unlocked by a moderator",
+            )
+            .await?;
+
+            if unlocked == 0 {
+                return Err(Error::ChannelNotLocked);
+            }
+
+            let _ = channel_id
+                .send_message(
+                    ctx,
+                    CreateMessage::new().embed(
+                        CreateEmbed::new()
+                            .title("🔓 Channel unlocked")
+                            .colour(0x57F287),
+                    ),
+                )
+                .await;
+
+            format!("Unlocked {}.", channel_id.mention())
+        };
+
+        interaction
+            .edit_response(ctx, EditInteractionResponse::new().content(content))
+            .await
+            .unwrap();
+
+        Ok(())
+    }
+
+    fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
+        let command = CreateCommand::new("unlock")
+            .description("Let members send messages again")
+            .default_member_permissions(Permissions::MANAGE_CHANNELS);
+
+        Ok(target_options(command, "unlock"))
+    }
+}
+
+pub struct Slowmode;
+
+#[async_trait]
+impl SlashCommand<Error, Postgres> for Slowmode {
+    async fn run(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: Vec<ResolvedOption<'_>>,
+        _pool: &PgPool,
+    ) -> Result<()> {
+        interaction.defer_ephemeral(ctx).await.unwrap();
+
+        let mut options = parse_options(options);
+
+        let Some(ResolvedValue::Integer(seconds)) = options.remove("seconds") else {
+            unreachable!("Seconds option is required");
+        };
+
+        let channel_id = match options.remove("channel") {
+            Some(ResolvedValue::Channel(channel)) => channel.id,
+            _ => interaction.channel_id,
+        };
+
+        channel_id
+            .edit(ctx, EditChannel::new().rate_limit_per_user(seconds as u16))
+            .await?;
+
+        let content = if seconds == 0 {
+            format!("Turned off slowmode in {}.", channel_id.mention())
+        } else {
+            format!(
+                "Members in {} can now send one message every {} seconds.",
+                channel_id.mention(),
+                seconds
+            )
+        };
+
+        interaction
+            .edit_response(ctx, EditInteractionResponse::new().content(content))
+            .await
+            .unwrap();
+
+        Ok(())
+    }
+
+    fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
+        let command = CreateCommand::new("slowmode")
+            .description("Limit how often members can send messages")
+            .default_member_permissions(Permissions::MANAGE_CHANNELS)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "seconds",
+                    "Seconds between messages, 0 turns slowmode off",
+                )
+                .min_int_value(0)
+                .max_int_value(MAX_SLOWMODE as u64)
+                .required(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Channel,
+                    "channel",
+                    "The channel to change, defaults to this one",
+                )
+                // Announcement channels don't support slowmode.
+                .channel_types(vec![
+                    ChannelType::Text,
+                    ChannelType::Forum,
+                    ChannelType::Voice,
+                    ChannelType::Stage,
+                ]),
+            );
+
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::all::Permissions;
+
+    use super::{locked_overwrite, LOCKED};
+
+    #[test]
+    fn lock_without_overwrite() {
+        let (allow, deny) = locked_overwrite(Permissions::empty(), Permissions::empty());
+
+        assert_eq!(allow, Permissions::empty());
+        assert_eq!(deny, LOCKED);
+    }
+
+    #[test]
+    fn lock_keeps_other_permissions() {
+        let (allow, deny) = locked_overwrite(
+            Permissions::SEND_MESSAGES | Permissions::ATTACH_FILES,
+            Permissions::MENTION_EVERYONE,
+        );
+
+        assert_eq!(allow, Permissions::ATTACH_FILES);
+        assert!(deny.contains(LOCKED | Permissions::MENTION_EVERYONE));
+        assert!(!deny.contains(Permissions::ATTACH_FILES));
+    }
+}
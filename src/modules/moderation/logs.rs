@@ -1,14 +1,115 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
 use serenity::all::{
     CommandInteraction, CommandOptionType, Context, CreateCommand, CreateCommandOption,
-    CreateEmbed, EditInteractionResponse, Permissions, Ready, ResolvedOption, ResolvedValue,
+    CreateEmbed, CreateEmbedFooter, GuildId, Permissions, Ready, ResolvedOption, ResolvedValue,
+    User, UserId,
 };
 use sqlx::{PgPool, Postgres};
 use zayden_core::{parse_options, SlashCommand};
 
+use crate::sqlx_lib::GuildTable;
 use crate::{Error, Result};
 
-use super::InfractionRow;
+use super::export::export;
+use super::pager::{paginate, PAGE_SIZE};
+use super::{InfractionKind, InfractionRow};
+
+/// Which of a user's cases to show. Dates are inclusive.
+#[derive(Debug, Clone, Default)]
+pub struct LogsFilter {
+    pub kind: Option<String>,
+    pub moderator: Option<UserId>,
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+}
+
+impl LogsFilter {
+    pub fn matches(&self, infraction: &InfractionRow) -> bool {
+        self.kind
+            .as_ref()
+            .is_none_or(|kind| infraction.infraction_type == *kind)
+            && self
+                .moderator
+                .is_none_or(|moderator| infraction.moderator_id == moderator.get() as i64)
+            && self
+                .since
+                .is_none_or(|since| infraction.created_at >= since)
+            && self
+                .until
+                .is_none_or(|until| infraction.created_at <= until)
+    }
+
+    /// Reads the `kind`, `moderator`, `from` and `to` options.
+    pub fn from_options(options: &mut HashMap<&str, ResolvedValue<'_>>) -> Result<Self> {
+        let mut filter = Self::default();
+
+        if let Some(ResolvedValue::String(kind)) = options.remove("kind") {
+            filter.kind = Some(kind.to_string());
+        }
+        if let Some(ResolvedValue::User(moderator, _)) = options.remove("moderator") {
+            filter.moderator = Some(moderator.id);
+        }
+        if let Some(ResolvedValue::String(from)) = options.remove("from") {
+            filter.since = Some(parse_date(from)?.and_hms_opt(0, 0, 0).unwrap());
+        }
+        if let Some(ResolvedValue::String(to)) = options.remove("to") {
+            filter.until = Some(parse_date(to)?.and_hms_opt(23, 59, 59).unwrap());
+        }
+
+        Ok(filter)
+    }
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").map_err(|_| Error::InvalidDate)
+}
+
+fn logs_page(user: &User, infractions: &[InfractionRow], page: usize) -> CreateEmbed {
+    let page_count = infractions.len().div_ceil(PAGE_SIZE).max(1);
+
+    let fields = infractions
+        .iter()
+        .skip(page * PAGE_SIZE)
+        .take(PAGE_SIZE)
+        .map(|infraction| {
+            let title = if infraction.voided {
+                format!("Case #{} (voided)", infraction.id)
+            } else {
+                format!("Case #{}", infraction.id)
+            };
+
+            let value = format!(
+                "**Type:** {}\n**Points:** {}\n**Created:** <t:{}:f>\n**Moderator:** ({}) {}\n**Reason:** {}",
+                infraction.infraction_type,
+                infraction.points,
+                infraction.created_at.and_utc().timestamp(),
+                infraction.moderator_id,
+                infraction.moderator_username,
+                infraction.reason
+            );
+
+            (title, value, false)
+        });
+
+    let mut embed = CreateEmbed::new()
+        .title(format!("Logs for {}", user.name))
+        .fields(fields)
+        .footer(CreateEmbedFooter::new(format!(
+            "Page {}/{} | {} cases",
+            page + 1,
+            page_count,
+            infractions.len()
+        )));
+
+    if infractions.is_empty() {
+        embed = embed.description("No cases match these filters.");
+    }
+
+    embed
+}
 
 pub struct Logs;
 
@@ -17,76 +118,138 @@ impl SlashCommand<Error, Postgres> for Logs {
     async fn run(
         ctx: &Context,
         interaction: &CommandInteraction,
-        options: Vec<ResolvedOption<'_>>,
+        mut options: Vec<ResolvedOption<'_>>,
         pool: &PgPool,
     ) -> Result<()> {
-        interaction.defer(ctx).await.unwrap();
-
-        let mut options = parse_options(options);
-
-        let Some(ResolvedValue::User(user, _)) = options.remove("user") else {
-            unreachable!("User option is required");
+        let command = options.remove(0);
+        let ResolvedValue::SubCommand(options) = command.value else {
+            unreachable!("Subcommand is required");
         };
 
-        let filter = match options.remove("filter") {
-            Some(ResolvedValue::String(filter)) => filter,
-            _ => "recent",
-        };
-
-        let infractions =
-            InfractionRow::user_infractions(pool, user.id, filter == "recent").await?;
+        match command.name {
+            "view" => view(ctx, interaction, options, pool).await,
+            "export" => export(ctx, interaction, options, pool).await,
+            _ => unreachable!("Unknown subcommand"),
+        }
+    }
 
-        let fields = infractions.into_iter().map(|infraction| {
-            (
-                format!("Case #{}", infraction.id),
-                format!("**Type:** {}\n", infraction.infraction_type)
-                    + &format!(
-                        "**User:** ({}) {}\n",
-                        infraction.user_id, infraction.username
+    fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
+        let view =
+            CreateCommandOption::new(CommandOptionType::SubCommand, "view", "Get logs for a user")
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::User,
+                        "user",
+                        "The user to get logs for",
                     )
-                    + &format!(
-                        "**Moderator:** ({}) {}\n",
-                        infraction.moderator_id, infraction.moderator_username
+                    .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "filter",
+                        "The number of logs to get",
                     )
-                    + &format!("**Reason:** {}", infraction.reason),
-                false,
-            )
-        });
-
-        let embed = CreateEmbed::new()
-            .title(format!("Logs for {}", user.name))
-            .fields(fields);
+                    .add_string_choice("Recent (default without a date range)", "recent")
+                    .add_string_choice("All", "all"),
+                );
 
-        interaction
-            .edit_response(ctx, EditInteractionResponse::new().embed(embed))
-            .await
-            .unwrap();
-
-        Ok(())
-    }
+        let export = CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "export",
+            "Download cases as a CSV or JSON file",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "format", "The file format")
+                .add_string_choice("CSV", "csv")
+                .add_string_choice("JSON", "json")
+                .required(true),
+        )
+        .add_sub_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "user",
+            "Only export cases for this user. Leave empty for the whole server",
+        ));
 
-    fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
         let command = CreateCommand::new("logs")
             .description("Get logs for a user")
             .default_member_permissions(Permissions::MODERATE_MEMBERS)
-            .add_option(
-                CreateCommandOption::new(
-                    CommandOptionType::User,
-                    "user",
-                    "The user to get logs for",
-                )
-                .required(true),
-            )
-            .add_option(
-                CreateCommandOption::new(
-                    CommandOptionType::String,
-                    "filter",
-                    "The number of logs to get",
-                )
-                .add_string_choice("Recent (default)", "recent")
-                .add_string_choice("All", "all"),
-            );
+            .add_option(add_filter_options(view))
+            .add_option(add_filter_options(export));
 
         Ok(command)
     }
 }
+
+async fn view(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    options: Vec<ResolvedOption<'_>>,
+    pool: &PgPool,
+) -> Result<()> {
+    interaction.defer(ctx).await.unwrap();
+
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    let mut options = parse_options(options);
+
+    let Some(ResolvedValue::User(user, _)) = options.remove("user") else {
+        unreachable!("User option is required");
+    };
+
+    let mut filter = LogsFilter::from_options(&mut options)?;
+
+    // Recent only shows cases that still count towards escalation. An explicit date range shows
+    // everything in it unless recent was asked for too.
+    let recent = match options.remove("filter") {
+        Some(ResolvedValue::String(filter)) => filter == "recent",
+        _ => filter.since.is_none() && filter.until.is_none(),
+    };
+
+    if recent {
+        let policy = GuildTable::get_escalation_policy(pool, guild_id).await?;
+        let cutoff = policy.cutoff(Utc::now().naive_utc());
+        filter.since = Some(filter.since.map_or(cutoff, |since| since.max(cutoff)));
+    }
+
+    let mut infractions = InfractionRow::user_infractions(pool, guild_id, user.id).await?;
+    infractions.retain(|infraction| filter.matches(infraction));
+    infractions.reverse();
+
+    let page_count = infractions.len().div_ceil(PAGE_SIZE).max(1);
+
+    paginate(ctx, interaction, "logs", page_count, |page| {
+        logs_page(user, &infractions, page)
+    })
+    .await
+}
+
+fn add_filter_options(subcommand: CreateCommandOption) -> CreateCommandOption {
+    let mut kind = CreateCommandOption::new(
+        CommandOptionType::String,
+        "kind",
+        "Only include this kind of infraction",
+    );
+    for infraction_kind in InfractionKind::ALL {
+        let name = infraction_kind.to_string();
+        kind = kind.add_string_choice(&name, name);
+    }
+
+    subcommand
+        .add_sub_option(kind)
+        .add_sub_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "moderator",
+            "Only include cases from this moderator",
+        ))
+        .add_sub_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "from",
+            "Only include cases on or after this date (YYYY-MM-DD)",
+        ))
+        .add_sub_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "to",
+            "Only include cases on or before this date (YYYY-MM-DD)",
+        ))
+}
@@ -2,26 +2,56 @@ use std::fmt::Display;
 
 use chrono::NaiveDateTime;
 use serenity::all::{Context, CreateCommand, GuildId, Ready, User, UserId};
-use sqlx::{postgres::PgQueryResult, FromRow, PgPool};
+use sqlx::{FromRow, PgPool};
 use zayden_core::SlashCommand;
 
+pub use case::{void_case, Case};
+pub use dossier::Dossier;
+pub use escalation::{format_duration, parse_duration, MAX_MUTE};
 pub use infraction::Infraction;
+pub use lock::{Lock, Slowmode, Unlock};
 pub use logs::Logs;
+pub use note::Note;
+pub use purge::Purge;
+pub use report::ReportMessage;
 pub use rules::RulesCommand;
+pub use sanction::{apply_sanction, Sanction, SanctionOutcome, SanctionRequest};
+pub use scheduled::{Scheduled, ScheduledActions};
 
 use crate::Result;
 
+mod case;
+mod dossier;
+mod escalation;
+mod export;
 mod infraction;
 mod infraction_kind;
 mod infraction_row;
+mod lock;
 mod logs;
+pub mod mod_log;
+mod note;
+mod pager;
+mod purge;
+pub mod report;
 mod rules;
+mod sanction;
+mod scheduled;
 
 pub fn register(ctx: &Context, ready: &Ready) -> Result<Vec<CreateCommand>> {
     let commands = vec![
+        Case::register(ctx, ready)?,
+        Dossier::register(ctx, ready)?,
         Infraction::register(ctx, ready)?,
+        Lock::register(ctx, ready)?,
         Logs::register(ctx, ready)?,
+        Note::register(ctx, ready)?,
+        Purge::register(ctx, ready)?,
+        ReportMessage::register(ctx, ready)?,
         RulesCommand::register(ctx, ready)?,
+        Scheduled::register(ctx, ready)?,
+        Slowmode::register(ctx, ready)?,
+        Unlock::register(ctx, ready)?,
     ];
 
     Ok(commands)
@@ -39,6 +69,8 @@ pub struct InfractionRow {
     pub points: i32,
     pub reason: String,
     pub created_at: NaiveDateTime,
+    /// Voided cases stay in the history but no longer count towards escalation.
+    pub voided: bool,
 }
 
 impl InfractionRow {
@@ -62,50 +94,43 @@ impl InfractionRow {
             points,
             reason: reason.into(),
             created_at: chrono::Utc::now().naive_utc(),
+            voided: false,
         })
     }
 
     async fn user_infractions(
         pool: &PgPool,
+        guild_id: GuildId,
         user_id: UserId,
-        recent: bool,
     ) -> Result<Vec<InfractionRow>> {
-        let user_id = user_id.get() as i64;
-
-        let infractions = if recent {
-            sqlx::query_as!(
-                InfractionRow,
-                "SELECT * FROM infractions WHERE user_id = $1 AND created_at > CURRENT_DATE - INTERVAL '6 months'",
-                user_id
-            ).fetch_all(pool).await.unwrap()
-        } else {
-            sqlx::query_as!(
-                InfractionRow,
-                "SELECT * FROM infractions WHERE user_id = $1",
-                user_id
-            )
-            .fetch_all(pool)
-            .await
-            .unwrap()
-        };
+        let infractions = sqlx::query_as!(
+            InfractionRow,
+            "SELECT * FROM infractions WHERE guild_id = $1 AND user_id = $2 ORDER BY created_at",
+            guild_id.get() as i64,
+            user_id.get() as i64
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
 
         Ok(infractions)
     }
 
-    async fn create(&self, pool: &PgPool) -> Result<PgQueryResult> {
-        let result = sqlx::query!(
-            "INSERT INTO infractions (user_id, username, guild_id, infraction_type, moderator_id, moderator_username, points, reason) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    /// Inserts the infraction, returning its case number.
+    async fn create(&self, pool: &PgPool) -> Result<i32> {
+        let row = sqlx::query!(
+            "INSERT INTO infractions (user_id, username, guild_id, infraction_type, moderator_id, moderator_username, points, reason) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
             self.user_id, self.username, self.guild_id, self.infraction_type, self.moderator_id, self.moderator_username, self.points, self.reason
         )
-            .execute(pool)
+            .fetch_one(pool)
             .await.unwrap();
 
-        Ok(result)
+        Ok(row.id)
     }
 }
 
-#[allow(dead_code)]
-enum InfractionKind {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfractionKind {
     Warn,
     Mute,
     Kick,
@@ -113,6 +138,10 @@ enum InfractionKind {
     Ban,
 }
 
+impl InfractionKind {
+    pub const ALL: [Self; 5] = [Self::Warn, Self::Mute, Self::Kick, Self::SoftBan, Self::Ban];
+}
+
 impl Display for InfractionKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let str = match self {
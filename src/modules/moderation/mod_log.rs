@@ -0,0 +1,185 @@
+use chrono::TimeDelta;
+use serenity::all::{
+    Action, ChannelId, CommandInteraction, Context, CreateEmbed, CreateEmbedFooter, CreateMessage,
+    GuildAuditLogEntryCreateEvent, GuildId, MemberAction, Mentionable, Timestamp, UserId,
+};
+use sqlx::PgPool;
+
+use crate::sqlx_lib::GuildTable;
+use crate::Result;
+
+use super::escalation::format_duration;
+
+/// One moderation action, rendered the same way whichever path it came from.
+pub struct ModLogEntry<'a> {
+    pub action: &'a str,
+    pub case_id: Option<i32>,
+    pub target: UserId,
+    pub moderator: Option<UserId>,
+    pub reason: Option<&'a str>,
+    pub duration: Option<TimeDelta>,
+    pub jump_url: Option<String>,
+    pub colour: u32,
+}
+
+impl ModLogEntry<'_> {
+    pub fn embed(&self) -> CreateEmbed {
+        let title = match self.case_id {
+            Some(case_id) => format!("{} | Case #{}", self.action, case_id),
+            None => self.action.to_string(),
+        };
+
+        let moderator = match self.moderator {
+            Some(moderator) => format!("{} ({})", moderator.mention(), moderator),
+            None => String::from("Unknown"),
+        };
+
+        let mut embed = CreateEmbed::new()
+            .title(title)
+            .colour(self.colour)
+            .field(
+                "Target",
+                format!("{} ({})", self.target.mention(), self.target),
+                true,
+            )
+            .field("Moderator", moderator, true);
+
+        if let Some(duration) = self.duration {
+            embed = embed.field("Duration", format_duration(duration), true);
+        }
+
+        embed = embed.field(
+            "Reason",
+            self.reason.unwrap_or("No reason provided."),
+            false,
+        );
+
+        if let Some(jump_url) = &self.jump_url {
+            embed = embed.field("Context", format!("[Jump to message]({})", jump_url), false);
+        }
+
+        embed
+            .footer(CreateEmbedFooter::new(format!("User ID: {}", self.target)))
+            .timestamp(Timestamp::now())
+    }
+}
+
+impl GuildTable {
+    pub async fn get_mod_log_channel(pool: &PgPool, id: GuildId) -> Result<Option<ChannelId>> {
+        let row = sqlx::query!(
+            "SELECT mod_log_channel_id FROM guilds WHERE id = $1",
+            id.get() as i64
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        Ok(row
+            .and_then(|row| row.mod_log_channel_id)
+            .map(|id| ChannelId::new(id as u64)))
+    }
+
+    pub async fn save_mod_log_channel(
+        pool: &PgPool,
+        id: GuildId,
+        channel_id: ChannelId,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO guilds (id, mod_log_channel_id) VALUES ($1, $2)
+             ON CONFLICT (id) DO UPDATE SET mod_log_channel_id = $2",
+            id.get() as i64,
+            channel_id.get() as i64
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+}
+
+/// Posts the entry to the guild's mod-log channel, if it has one.
+pub async fn post_mod_log(
+    ctx: &Context,
+    pool: &PgPool,
+    guild_id: GuildId,
+    entry: &ModLogEntry<'_>,
+) -> Result<()> {
+    send_mod_log(
+        ctx,
+        pool,
+        guild_id,
+        CreateMessage::new().embed(entry.embed()),
+    )
+    .await
+}
+
+/// Sends a message to the guild's mod-log channel, if it has one. For alerts that need more than
+/// a [`ModLogEntry`], such as buttons. A missing channel or permission is only logged, since the
+/// action being recorded has already happened.
+pub async fn send_mod_log(
+    ctx: &Context,
+    pool: &PgPool,
+    guild_id: GuildId,
+    message: CreateMessage,
+) -> Result<()> {
+    let Some(channel_id) = GuildTable::get_mod_log_channel(pool, guild_id).await? else {
+        return Ok(());
+    };
+
+    if let Err(e) = channel_id.send_message(ctx, message).await {
+        eprintln!("Error posting to the mod log in {}: {:?}", channel_id, e);
+    }
+
+    Ok(())
+}
+
+/// A link to the deferred response of a command, for the mod log to jump to.
+pub async fn response_link(ctx: &Context, interaction: &CommandInteraction) -> Option<String> {
+    interaction
+        .get_response(ctx)
+        .await
+        .ok()
+        .map(|message| message.link())
+}
+
+/// Logs bans and kicks done by hand in Discord. The bot's own actions are logged when they're
+/// taken, so they're skipped here.
+pub async fn audit_log_entry(
+    ctx: &Context,
+    pool: &PgPool,
+    event: GuildAuditLogEntryCreateEvent,
+) -> Result<()> {
+    let entry = event.entry;
+
+    if entry.user_id == ctx.cache.current_user().id {
+        return Ok(());
+    }
+
+    let (action, colour) = match entry.action {
+        Action::Member(MemberAction::BanAdd) => ("Manual Ban", 0xED4245),
+        Action::Member(MemberAction::Kick) => ("Manual Kick", 0xE67E22),
+        _ => return Ok(()),
+    };
+
+    let Some(target) = entry.target_id else {
+        return Ok(());
+    };
+
+    post_mod_log(
+        ctx,
+        pool,
+        event.guild_id,
+        &ModLogEntry {
+            action,
+            case_id: None,
+            target: UserId::new(target.get()),
+            moderator: Some(entry.user_id),
+            reason: entry.reason.as_deref(),
+            duration: None,
+            jump_url: None,
+            colour,
+        },
+    )
+    .await
+}
@@ -0,0 +1,228 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use serenity::all::{
+    CommandInteraction, CommandOptionType, Context, CreateCommand, CreateCommandOption,
+    CreateEmbed, CreateEmbedFooter, EditInteractionResponse, GuildId, Permissions, Ready,
+    ResolvedOption, ResolvedValue, User, UserId,
+};
+use sqlx::{PgPool, Postgres};
+use zayden_core::{parse_options, SlashCommand};
+
+use crate::{Error, Result};
+
+const LIST_LIMIT: usize = 10;
+
+/// A private note on a member. Unlike infractions, notes carry no points and are never shown to
+/// the member.
+#[allow(dead_code)]
+pub struct UserNoteRow {
+    pub id: i32,
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub moderator_id: i64,
+    pub moderator_username: String,
+    pub content: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl UserNoteRow {
+    pub fn line(&self) -> String {
+        format!(
+            "`#{}` <t:{}:d> by {}: {}",
+            self.id,
+            self.created_at.and_utc().timestamp(),
+            self.moderator_username,
+            self.content
+        )
+    }
+}
+
+pub struct UserNotesTable;
+
+impl UserNotesTable {
+    /// The user's notes, newest first.
+    pub async fn get_user_rows(
+        pool: &PgPool,
+        guild_id: GuildId,
+        user_id: UserId,
+    ) -> Result<Vec<UserNoteRow>> {
+        let rows = sqlx::query_as!(
+            UserNoteRow,
+            "SELECT * FROM user_notes WHERE guild_id = $1 AND user_id = $2 ORDER BY created_at DESC",
+            guild_id.get() as i64,
+            user_id.get() as i64
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        Ok(rows)
+    }
+
+    pub async fn insert(
+        pool: &PgPool,
+        guild_id: GuildId,
+        user_id: UserId,
+        moderator: &User,
+        content: &str,
+    ) -> Result<i32> {
+        let row = sqlx::query!(
+            "INSERT INTO user_notes (guild_id, user_id, moderator_id, moderator_username, content) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+            guild_id.get() as i64,
+            user_id.get() as i64,
+            moderator.id.get() as i64,
+            moderator.name,
+            content
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        Ok(row.id)
+    }
+
+    pub async fn delete(pool: &PgPool, guild_id: GuildId, id: i32) -> Result<UserNoteRow> {
+        let row = sqlx::query_as!(
+            UserNoteRow,
+            "DELETE FROM user_notes WHERE guild_id = $1 AND id = $2 RETURNING *",
+            guild_id.get() as i64,
+            id
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        row.ok_or(Error::NoteNotFound)
+    }
+}
+
+pub struct Note;
+
+#[async_trait]
+impl SlashCommand<Error, Postgres> for Note {
+    async fn run(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        mut options: Vec<ResolvedOption<'_>>,
+        pool: &PgPool,
+    ) -> Result<()> {
+        interaction.defer_ephemeral(ctx).await.unwrap();
+
+        let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+        let command = options.remove(0);
+        let ResolvedValue::SubCommand(options) = command.value else {
+            unreachable!("Subcommand is required");
+        };
+        let mut options = parse_options(options);
+
+        let response = match command.name {
+            "add" => {
+                let Some(ResolvedValue::User(user, _)) = options.remove("user") else {
+                    unreachable!("User option is required");
+                };
+                let Some(ResolvedValue::String(content)) = options.remove("note") else {
+                    unreachable!("Note option is required");
+                };
+
+                let id =
+                    UserNotesTable::insert(pool, guild_id, user.id, &interaction.user, content)
+                        .await?;
+
+                EditInteractionResponse::new()
+                    .content(format!("Added note #{} to {}.", id, user.name))
+            }
+            "list" => {
+                let Some(ResolvedValue::User(user, _)) = options.remove("user") else {
+                    unreachable!("User option is required");
+                };
+
+                let notes = UserNotesTable::get_user_rows(pool, guild_id, user.id).await?;
+
+                let description = if notes.is_empty() {
+                    String::from("No notes.")
+                } else {
+                    notes
+                        .iter()
+                        .take(LIST_LIMIT)
+                        .map(UserNoteRow::line)
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                };
+
+                let mut embed = CreateEmbed::new()
+                    .title(format!("Notes for {}", user.name))
+                    .description(description);
+
+                if notes.len() > LIST_LIMIT {
+                    embed = embed.footer(CreateEmbedFooter::new(format!(
+                        "Showing the latest {} of {} notes. Use /dossier to see them all.",
+                        LIST_LIMIT,
+                        notes.len()
+                    )));
+                }
+
+                EditInteractionResponse::new().embed(embed)
+            }
+            "remove" => {
+                let Some(ResolvedValue::Integer(id)) = options.remove("id") else {
+                    unreachable!("Id option is required");
+                };
+
+                let note = UserNotesTable::delete(pool, guild_id, id as i32).await?;
+
+                EditInteractionResponse::new().content(format!("Removed note #{}.", note.id))
+            }
+            _ => unreachable!("Unknown subcommand"),
+        };
+
+        interaction.edit_response(ctx, response).await.unwrap();
+
+        Ok(())
+    }
+
+    fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
+        let user = || {
+            CreateCommandOption::new(CommandOptionType::User, "user", "The member").required(true)
+        };
+
+        let command = CreateCommand::new("note")
+            .description("Keep private notes on members")
+            .default_member_permissions(Permissions::MODERATE_MEMBERS)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "add",
+                    "Add a note to a member",
+                )
+                .add_sub_option(user())
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "note", "The note")
+                        .max_length(300)
+                        .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "list",
+                    "List a member's notes",
+                )
+                .add_sub_option(user()),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "remove", "Remove a note")
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::Integer,
+                            "id",
+                            "The note number",
+                        )
+                        .min_int_value(1)
+                        .required(true),
+                    ),
+            );
+
+        Ok(command)
+    }
+}
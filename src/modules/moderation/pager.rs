@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use serenity::all::{
+    CommandInteraction, Context, CreateActionRow, CreateButton, CreateEmbed,
+    CreateInteractionResponse, CreateInteractionResponseMessage, EditInteractionResponse,
+};
+
+use crate::Result;
+
+pub const PAGE_SIZE: usize = 5;
+
+/// How long the buttons stay live after the last click.
+const TIMEOUT: Duration = Duration::from_secs(300);
+
+/// `{prefix}_previous` and `{prefix}_next`. The component handler leaves these alone, since
+/// [`paginate`] answers them itself.
+fn page_buttons(prefix: &str, page: usize, page_count: usize) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{}_previous", prefix))
+            .label("<")
+            .disabled(page == 0),
+        CreateButton::new(format!("{}_next", prefix))
+            .label(">")
+            .disabled(page + 1 >= page_count),
+    ])]
+}
+
+/// Shows the first page of a deferred response and turns pages on the invoker's clicks. For
+/// views whose state doesn't fit in a custom_id, so the pages are only live while this waits.
+pub async fn paginate(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    prefix: &str,
+    page_count: usize,
+    render: impl Fn(usize) -> CreateEmbed,
+) -> Result<()> {
+    let mut page = 0;
+
+    let message = interaction
+        .edit_response(
+            ctx,
+            EditInteractionResponse::new()
+                .embed(render(page))
+                .components(page_buttons(prefix, page, page_count)),
+        )
+        .await
+        .unwrap();
+
+    while let Some(component) = message
+        .await_component_interaction(ctx)
+        .author_id(interaction.user.id)
+        .timeout(TIMEOUT)
+        .await
+    {
+        match component.data.custom_id.strip_prefix(prefix) {
+            Some("_previous") => page = page.saturating_sub(1),
+            Some("_next") => page = (page + 1).min(page_count - 1),
+            _ => {}
+        }
+
+        component
+            .create_response(
+                ctx,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .embed(render(page))
+                        .components(page_buttons(prefix, page, page_count)),
+                ),
+            )
+            .await
+            .unwrap();
+    }
+
+    interaction
+        .edit_response(ctx, EditInteractionResponse::new().components(Vec::new()))
+        .await
+        .unwrap();
+
+    Ok(())
+}
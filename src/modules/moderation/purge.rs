@@ -0,0 +1,415 @@
+use async_trait::async_trait;
+use chrono::{DateTime, TimeDelta, Utc};
+use serenity::all::{
+    ChannelId, CommandInteraction, CommandOptionType, Context, CreateAttachment, CreateCommand,
+    CreateCommandOption, CreateEmbed, CreateMessage, EditInteractionResponse, GetMessages,
+    Mentionable, Message, MessageId, Permissions, Ready, ResolvedOption, ResolvedValue, Timestamp,
+    UserId,
+};
+use sqlx::{PgPool, Postgres};
+use zayden_core::{parse_options, SlashCommand};
+
+use crate::modules::automod::scam::link_domains;
+use crate::{Error, Result};
+
+use super::mod_log::send_mod_log;
+
+/// How far back a purge looks for matching messages.
+const SCAN_LIMIT: usize = 2000;
+
+/// Discord only bulk deletes messages younger than 14 days. The margin covers clock skew and the
+/// time the purge itself takes.
+const BULK_DELETE_AGE: TimeDelta = TimeDelta::minutes(14 * 24 * 60 - 5);
+
+#[derive(Debug, Clone, Default)]
+pub struct PurgeFilter {
+    pub user: Option<UserId>,
+    /// Lowercase, matched anywhere in the content.
+    pub contains: Option<String>,
+    pub attachments: bool,
+    pub links: bool,
+    pub bots: bool,
+}
+
+impl PurgeFilter {
+    pub fn matches(
+        &self,
+        author_id: UserId,
+        author_bot: bool,
+        content: &str,
+        has_attachments: bool,
+    ) -> bool {
+        self.user.is_none_or(|user| user == author_id)
+            && self
+                .contains
+                .as_ref()
+                .is_none_or(|text| content.to_lowercase().contains(text))
+            && (!self.attachments || has_attachments)
+            && (!self.links || !link_domains(content).is_empty())
+            && (!self.bots || author_bot)
+    }
+
+    fn matches_message(&self, message: &Message) -> bool {
+        self.matches(
+            message.author.id,
+            message.author.bot,
+            &message.content,
+            !message.attachments.is_empty(),
+        )
+    }
+}
+
+/// Accepts a message ID or a message link.
+pub fn parse_message_id(input: &str) -> Option<MessageId> {
+    let id = input.trim().trim_end_matches('/').rsplit('/').next()?;
+
+    id.parse().ok().filter(|id| *id != 0).map(MessageId::new)
+}
+
+pub fn bulk_deletable(created_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    now - created_at < BULK_DELETE_AGE
+}
+
+fn sent_at(message: &Message) -> DateTime<Utc> {
+    DateTime::from_timestamp(message.timestamp.unix_timestamp(), 0).unwrap_or_default()
+}
+
+/// Oldest first, one line per message.
+fn transcript(messages: &[Message]) -> String {
+    messages
+        .iter()
+        .rev()
+        .map(|message| {
+            let mut line = format!(
+                "[{}] {} ({}): {}",
+                sent_at(message).format("%Y-%m-%d %H:%M:%S"),
+                message.author.name,
+                message.author.id,
+                message.content
+            );
+
+            for attachment in &message.attachments {
+                line.push_str(&format!(" [attachment: {}]", attachment.url));
+            }
+
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Newest first. Pinned messages are never purged.
+async fn find_messages(
+    ctx: &Context,
+    channel_id: ChannelId,
+    filter: &PurgeFilter,
+    count: usize,
+    before: Option<MessageId>,
+    after: Option<MessageId>,
+) -> Result<Vec<Message>> {
+    let mut matched = Vec::new();
+    let mut scanned = 0;
+    let mut cursor = before;
+
+    'scan: while matched.len() < count && scanned < SCAN_LIMIT {
+        let mut request = GetMessages::new().limit(100);
+        if let Some(cursor) = cursor {
+            request = request.before(cursor);
+        }
+
+        let batch = channel_id.messages(ctx, request).await?;
+        let Some(last) = batch.last() else {
+            break;
+        };
+        cursor = Some(last.id);
+
+        for message in batch {
+            scanned += 1;
+
+            if after.is_some_and(|after| message.id <= after) {
+                break 'scan;
+            }
+
+            if !message.pinned && filter.matches_message(&message) {
+                matched.push(message);
+
+                if matched.len() >= count {
+                    break 'scan;
+                }
+            }
+        }
+    }
+
+    Ok(matched)
+}
+
+pub struct Purge;
+
+#[async_trait]
+impl SlashCommand<Error, Postgres> for Purge {
+    async fn run(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: Vec<ResolvedOption<'_>>,
+        pool: &PgPool,
+    ) -> Result<()> {
+        interaction.defer_ephemeral(ctx).await.unwrap();
+
+        let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+        let mut options = parse_options(options);
+
+        let Some(ResolvedValue::Integer(count)) = options.remove("count") else {
+            unreachable!("Count option is required");
+        };
+
+        let mut filter = PurgeFilter::default();
+
+        if let Some(ResolvedValue::User(user, _)) = options.remove("user") {
+            filter.user = Some(user.id);
+        }
+        if let Some(ResolvedValue::String(contains)) = options.remove("contains") {
+            filter.contains = Some(contains.to_lowercase());
+        }
+        if let Some(ResolvedValue::Boolean(attachments)) = options.remove("attachments") {
+            filter.attachments = attachments;
+        }
+        if let Some(ResolvedValue::Boolean(links)) = options.remove("links") {
+            filter.links = links;
+        }
+        if let Some(ResolvedValue::Boolean(bots)) = options.remove("bots") {
+            filter.bots = bots;
+        }
+
+        let before = match options.remove("before") {
+            Some(ResolvedValue::String(before)) => {
+                Some(parse_message_id(before).ok_or(Error::InvalidMessageId)?)
+            }
+            _ => None,
+        };
+        let after = match options.remove("after") {
+            Some(ResolvedValue::String(after)) => {
+                Some(parse_message_id(after).ok_or(Error::InvalidMessageId)?)
+            }
+            _ => None,
+        };
+
+        let channel_id = interaction.channel_id;
+
+        let messages =
+            find_messages(ctx, channel_id, &filter, count as usize, before, after).await?;
+
+        let now = Utc::now();
+        let (recent, old): (Vec<_>, Vec<_>) = messages
+            .iter()
+            .partition(|message| bulk_deletable(sent_at(message), now));
+
+        let mut deleted = Vec::new();
+
+        for chunk in recent.chunks(100) {
+            // Bulk deletes need at least two messages.
+            let result = if let [message] = chunk {
+                channel_id.delete_message(ctx, message.id).await
+            } else {
+                channel_id
+                    .delete_messages(ctx, chunk.iter().map(|message| message.id))
+                    .await
+            };
+
+            match result {
+                Ok(()) => deleted.extend(chunk.iter().copied().cloned()),
+                Err(e) => eprintln!("Error bulk deleting in {}: {:?}", channel_id, e),
+            }
+        }
+
+        let mut old_deleted = 0;
+        for message in &old {
+            if channel_id.delete_message(ctx, message.id).await.is_ok() {
+                deleted.push((*message).clone());
+                old_deleted += 1;
+            }
+        }
+
+        let failed = messages.len() - deleted.len();
+
+        let mut content = format!("Removed {} messages.", deleted.len());
+        if old_deleted > 0 {
+            content.push_str(&format!(
+                " {} were older than 14 days and were deleted one at a time.",
+                old_deleted
+            ));
+        }
+        if failed > 0 {
+            content.push_str(&format!(
+                " {} couldn't be deleted, they may already be gone.",
+                failed
+            ));
+        }
+
+        interaction
+            .edit_response(ctx, EditInteractionResponse::new().content(content))
+            .await
+            .unwrap();
+
+        if deleted.is_empty() {
+            return Ok(());
+        }
+
+        // Newest first, like the scan.
+        deleted.sort_by(|a, b| b.id.cmp(&a.id));
+
+        let embed = CreateEmbed::new()
+            .title("Purge")
+            .colour(0x99AAB5)
+            .field("Channel", channel_id.mention().to_string(), true)
+            .field(
+                "Moderator",
+                format!("{} ({})", interaction.user.mention(), interaction.user.id),
+                true,
+            )
+            .field("Messages", deleted.len().to_string(), true)
+            .timestamp(Timestamp::now());
+
+        let attachment = CreateAttachment::bytes(
+            transcript(&deleted),
+            format!("purge-{}-{}.txt", channel_id, now.format("%Y%m%d%H%M%S")),
+        );
+
+        send_mod_log(
+            ctx,
+            pool,
+            guild_id,
+            CreateMessage::new().embed(embed).add_file(attachment),
+        )
+        .await
+    }
+
+    fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
+        let command = CreateCommand::new("purge")
+            .description("Delete messages in this channel")
+            .default_member_permissions(Permissions::MANAGE_MESSAGES)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "count",
+                    "How many matching messages to delete",
+                )
+                .min_int_value(1)
+                .max_int_value(1000)
+                .required(true),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::User,
+                "user",
+                "Only delete messages from this user",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "contains",
+                "Only delete messages containing this text",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "attachments",
+                "Only delete messages with attachments",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "links",
+                "Only delete messages with links",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "bots",
+                "Only delete messages from bots",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "before",
+                "Only delete messages before this message ID or link",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "after",
+                "Only delete messages after this message ID or link",
+            ));
+
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, Utc};
+    use serenity::all::{MessageId, UserId};
+
+    use super::{bulk_deletable, parse_message_id, PurgeFilter};
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let filter = PurgeFilter::default();
+
+        assert!(filter.matches(UserId::new(1), false, "hello", false));
+        assert!(filter.matches(UserId::new(2), true, "", true));
+    }
+
+    #[test]
+    fn filters_combine() {
+        let filter = PurgeFilter {
+            user: Some(UserId::new(1)),
+            contains: Some(String::from("free nitro")),
+            links: true,
+            ..Default::default()
+        };
+
+        assert!(filter.matches(
+            UserId::new(1),
+            false,
+            "FREE NITRO https://example.com",
+            false
+        ));
+        assert!(!filter.matches(UserId::new(1), false, "free nitro", false));
+        assert!(!filter.matches(
+            UserId::new(2),
+            false,
+            "free nitro https://example.com",
+            false
+        ));
+    }
+
+    #[test]
+    fn attachments_and_bots() {
+        let filter = PurgeFilter {
+            attachments: true,
+            bots: true,
+            ..Default::default()
+        };
+
+        assert!(filter.matches(UserId::new(1), true, "", true));
+        assert!(!filter.matches(UserId::new(1), false, "", true));
+        assert!(!filter.matches(UserId::new(1), true, "", false));
+    }
+
+    #[test]
+    fn message_ids_and_links() {
+        assert_eq!(
+            parse_message_id("1234567890"),
+            Some(MessageId::new(1234567890))
+        );
+        assert_eq!(
+            parse_message_id("https://discord.com/channels/1/2/1234567890"),
+            Some(MessageId::new(1234567890))
+        );
+        assert_eq!(parse_message_id("not an id"), None);
+        assert_eq!(parse_message_id("0"), None);
+    }
+
+    #[test]
+    fn bulk_delete_cutoff() {
+        let now = Utc::now();
+
+        assert!(bulk_deletable(now - TimeDelta::days(13), now));
+        assert!(!bulk_deletable(now - TimeDelta::days(14), now));
+        assert!(!bulk_deletable(now - TimeDelta::days(30), now));
+    }
+}
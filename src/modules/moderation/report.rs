@@ -0,0 +1,458 @@
+use async_trait::async_trait;
+use serenity::all::{
+    ButtonStyle, ChannelId, CommandInteraction, CommandType, ComponentInteraction, Context,
+    CreateActionRow, CreateButton, CreateCommand, CreateEmbed, CreateEmbedFooter, CreateInputText,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, CreateModal,
+    EditInteractionResponse, GuildId, InputTextStyle, Mentionable, MessageId, ModalInteraction,
+    Ready, ResolvedOption, ResolvedTarget, Timestamp, UserId,
+};
+use sqlx::{PgPool, Postgres};
+use zayden_core::{parse_modal_data, SlashCommand};
+
+use crate::sqlx_lib::GuildTable;
+use crate::{Error, Result};
+
+use super::{apply_sanction, Sanction, SanctionRequest};
+
+const CONTENT_LIMIT: usize = 1000;
+
+/// Embed field values hold 1024 characters.
+const FIELD_LIMIT: usize = 1024;
+
+impl GuildTable {
+    pub async fn get_report_channel(pool: &PgPool, id: GuildId) -> Result<Option<ChannelId>> {
+        let row = sqlx::query!(
+            "SELECT report_channel_id FROM guilds WHERE id = $1",
+            id.get() as i64
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        Ok(row
+            .and_then(|row| row.report_channel_id)
+            .map(|id| ChannelId::new(id as u64)))
+    }
+
+    pub async fn save_report_channel(
+        pool: &PgPool,
+        id: GuildId,
+        channel_id: ChannelId,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO guilds (id, report_channel_id) VALUES ($1, $2)
+             ON CONFLICT (id) DO UPDATE SET report_channel_id = $2",
+            id.get() as i64,
+            channel_id.get() as i64
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+}
+
+fn message_link(guild_id: GuildId, channel_id: ChannelId, message_id: MessageId) -> String {
+    format!(
+        "https://discord.com/channels/{}/{}/{}",
+        guild_id, channel_id, message_id
+    )
+}
+
+/// One link per line, cut short to fit in an embed field.
+fn attachment_list(links: &[String]) -> String {
+    let mut list = String::new();
+
+    for (shown, link) in links.iter().enumerate() {
+        let more = format!("\n…and {} more", links.len() - shown);
+
+        if list.chars().count() + link.chars().count() + 1 + more.chars().count() > FIELD_LIMIT {
+            list.push_str(more.trim_start());
+            break;
+        }
+
+        if !list.is_empty() {
+            list.push('\n');
+        }
+        list.push_str(link);
+    }
+
+    list
+}
+
+/// `report_modal_{channel}_{message}`
+fn parse_modal_custom_id(custom_id: &str) -> Option<(ChannelId, MessageId)> {
+    let mut ids = custom_id.strip_prefix("report_modal_")?.split('_');
+
+    let channel_id = ids.next()?.parse().ok()?;
+    let message_id = ids.next()?.parse().ok()?;
+
+    Some((ChannelId::new(channel_id), MessageId::new(message_id)))
+}
+
+/// The reported message, carried by each of the report's buttons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Reported {
+    author_id: UserId,
+    channel_id: ChannelId,
+    message_id: MessageId,
+}
+
+impl Reported {
+    /// `report_{action}_{author}_{channel}_{message}`
+    fn custom_id(&self, action: &str) -> String {
+        format!(
+            "report_{}_{}_{}_{}",
+            action, self.author_id, self.channel_id, self.message_id
+        )
+    }
+
+    fn parse(custom_id: &str) -> Option<(&str, Self)> {
+        let mut parts = custom_id.strip_prefix("report_")?.split('_');
+
+        let action = parts.next()?;
+        let author_id = parts.next()?.parse().ok()?;
+        let channel_id = parts.next()?.parse().ok()?;
+        let message_id = parts.next()?.parse().ok()?;
+
+        Some((
+            action,
+            Self {
+                author_id: UserId::new(author_id),
+                channel_id: ChannelId::new(channel_id),
+                message_id: MessageId::new(message_id),
+            },
+        ))
+    }
+
+    fn buttons(&self, claimed: bool) -> Vec<CreateActionRow> {
+        vec![CreateActionRow::Buttons(vec![
+            CreateButton::new(self.custom_id("claim"))
+                .label("Claim")
+                .style(ButtonStyle::Primary)
+                .disabled(claimed),
+            CreateButton::new(self.custom_id("dismiss"))
+                .label("Dismiss")
+                .style(ButtonStyle::Secondary),
+            CreateButton::new(self.custom_id("warn"))
+                .label("Warn author")
+                .style(ButtonStyle::Danger),
+        ])]
+    }
+}
+
+/// The "Report to moderators" entry in a message's Apps menu.
+pub struct ReportMessage;
+
+#[async_trait]
+impl SlashCommand<Error, Postgres> for ReportMessage {
+    async fn run(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        _options: Vec<ResolvedOption<'_>>,
+        _pool: &PgPool,
+    ) -> Result<()> {
+        interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+        let Some(ResolvedTarget::Message(message)) = interaction.data.target() else {
+            unreachable!("Message commands always target a message");
+        };
+
+        let reason = CreateInputText::new(InputTextStyle::Paragraph, "Reason", "reason")
+            .placeholder("What's wrong with this message?")
+            .max_length(500)
+            .required(true);
+
+        let modal = CreateModal::new(
+            format!("report_modal_{}_{}", message.channel_id, message.id),
+            "Report to moderators",
+        )
+        .components(vec![CreateActionRow::InputText(reason)]);
+
+        interaction
+            .create_response(ctx, CreateInteractionResponse::Modal(modal))
+            .await
+            .unwrap();
+
+        Ok(())
+    }
+
+    fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
+        let command = CreateCommand::new("Report to moderators")
+            .kind(CommandType::Message)
+            .dm_permission(false);
+
+        Ok(command)
+    }
+}
+
+/// Posts the report to the queue, falling back to the mod log.
+pub async fn submit(ctx: &Context, modal: &ModalInteraction, pool: &PgPool) -> Result<()> {
+    modal.defer_ephemeral(ctx).await.unwrap();
+
+    let guild_id = modal.guild_id.ok_or(Error::MissingGuildId)?;
+
+    let Some((channel_id, message_id)) = parse_modal_custom_id(&modal.data.custom_id) else {
+        unreachable!("Report modal custom_id is always valid");
+    };
+
+    let mut data = parse_modal_data(&modal.data.components);
+    let reason = data.remove("reason").unwrap_or_default();
+
+    let queue = match GuildTable::get_report_channel(pool, guild_id).await? {
+        Some(channel_id) => Some(channel_id),
+        None => GuildTable::get_mod_log_channel(pool, guild_id).await?,
+    };
+
+    let content = match (queue, channel_id.message(ctx, message_id).await) {
+        (None, _) => "This server isn't taking reports yet. Please contact a moderator directly.",
+        (Some(_), Err(_)) => "That message has already been deleted.",
+        (Some(queue), Ok(message)) => {
+            let mut text = message
+                .content
+                .chars()
+                .take(CONTENT_LIMIT)
+                .collect::<String>();
+            if message.content.chars().count() > CONTENT_LIMIT {
+                text.push('…');
+            }
+            if text.is_empty() {
+                text = String::from("*No text*");
+            }
+
+            let link = message_link(guild_id, channel_id, message_id);
+
+            let mut embed = CreateEmbed::new()
+                .title("Message Report")
+                .colour(0xFEE75C)
+                .description(text)
+                .field(
+                    "Author",
+                    format!("{} ({})", message.author.mention(), message.author.id),
+                    true,
+                )
+                .field(
+                    "Reporter",
+                    format!("{} ({})", modal.user.mention(), modal.user.id),
+                    true,
+                )
+                .field("Channel", channel_id.mention().to_string(), true)
+                .field("Reason", reason, false);
+
+            if !message.attachments.is_empty() {
+                let links = message
+                    .attachments
+                    .iter()
+                    .map(|attachment| format!("[{}]({})", attachment.filename, attachment.url))
+                    .collect::<Vec<_>>();
+
+                embed = embed.field("Attachments", attachment_list(&links), false);
+            }
+
+            embed = embed
+                .field("Context", format!("[Jump to message]({})", link), false)
+                .footer(CreateEmbedFooter::new(format!(
+                    "User ID: {}",
+                    message.author.id
+                )))
+                .timestamp(Timestamp::now());
+
+            let reported = Reported {
+                author_id: message.author.id,
+                channel_id,
+                message_id,
+            };
+
+            queue
+                .send_message(
+                    ctx,
+                    CreateMessage::new()
+                        .embed(embed)
+                        .components(reported.buttons(false)),
+                )
+                .await
+                .unwrap();
+
+            "Thanks, the moderators have been notified."
+        }
+    };
+
+    modal
+        .edit_response(ctx, EditInteractionResponse::new().content(content))
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+/// Handles the Claim, Dismiss and Warn author buttons on a report.
+pub async fn button(
+    ctx: &Context,
+    interaction: &ComponentInteraction,
+    pool: &PgPool,
+) -> Result<()> {
+    let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+    if !interaction
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.moderate_members())
+    {
+        // The error is sent as a followup, which needs the click acknowledged first.
+        interaction.defer_ephemeral(ctx).await.unwrap();
+        return Err(Error::MissingPermissions);
+    }
+
+    let report = interaction
+        .message
+        .embeds
+        .first()
+        .cloned()
+        .unwrap_or_default();
+
+    let Some((action, reported)) = Reported::parse(&interaction.data.custom_id) else {
+        return Ok(());
+    };
+
+    let moderator = format!("By {}", interaction.user.mention());
+
+    let (embed, components) = match action {
+        "claim" => {
+            let embed = CreateEmbed::from(report)
+                .colour(0x5865F2)
+                .field("Claimed", moderator, false);
+
+            (embed, reported.buttons(true))
+        }
+        "dismiss" => {
+            let embed =
+                CreateEmbed::from(report)
+                    .colour(0x99AAB5)
+                    .field("Dismissed", moderator, false);
+
+            (embed, Vec::new())
+        }
+        "warn" => {
+            interaction.defer(ctx).await.unwrap();
+
+            // Report reasons can be longer than an infraction's, so the case gets the start of it.
+            let reason = report
+                .fields
+                .iter()
+                .find(|field| field.name == "Reason")
+                .map(|field| format!("Reported message: {}", field.value))
+                .unwrap_or_else(|| String::from("Reported message"))
+                .chars()
+                .take(255)
+                .collect::<String>();
+
+            let user = reported.author_id.to_user(ctx).await.unwrap();
+
+            let outcome = apply_sanction(
+                ctx,
+                pool,
+                SanctionRequest {
+                    guild_id,
+                    user: &user,
+                    moderator: &interaction.user,
+                    sanction: Sanction::Warn,
+                    points: 1,
+                    reason: &reason,
+                    jump_url: Some(message_link(
+                        guild_id,
+                        reported.channel_id,
+                        reported.message_id,
+                    )),
+                },
+            )
+            .await?;
+
+            let embed = CreateEmbed::from(report).colour(0x57F287).field(
+                "Warned",
+                format!("{} | Case #{}", moderator, outcome.case_id),
+                false,
+            );
+
+            interaction
+                .edit_response(
+                    ctx,
+                    EditInteractionResponse::new()
+                        .embed(embed)
+                        .components(Vec::new()),
+                )
+                .await
+                .unwrap();
+
+            return Ok(());
+        }
+        _ => unreachable!("Unknown report action"),
+    };
+
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(embed)
+                    .components(components),
+            ),
+        )
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::all::{ChannelId, MessageId, UserId};
+
+    use super::{attachment_list, parse_modal_custom_id, Reported};
+
+    #[test]
+    fn button_custom_ids_round_trip() {
+        let reported = Reported {
+            author_id: UserId::new(1),
+            channel_id: ChannelId::new(10),
+            message_id: MessageId::new(20),
+        };
+
+        for action in ["claim", "dismiss", "warn"] {
+            assert_eq!(
+                Reported::parse(&reported.custom_id(action)),
+                Some((action, reported))
+            );
+        }
+    }
+
+    #[test]
+    fn attachment_list_fits_in_a_field() {
+        let links = (0..20)
+            .map(|i| {
+                format!(
+                    "[file{}.png](https://cdn.discordapp.com/{})",
+                    i,
+                    "x".repeat(100)
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let list = attachment_list(&links);
+
+        assert!(list.chars().count() <= 1024);
+        assert!(list.starts_with(&links[0]));
+        assert!(list.contains("more"));
+        assert_eq!(attachment_list(&links[..2]), links[..2].join("\n"));
+    }
+
+    #[test]
+    fn modal_custom_id() {
+        assert_eq!(
+            parse_modal_custom_id("report_modal_10_20"),
+            Some((ChannelId::new(10), MessageId::new(20)))
+        );
+        assert_eq!(parse_modal_custom_id("report_modal_x_20"), None);
+    }
+}
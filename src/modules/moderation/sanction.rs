@@ -0,0 +1,316 @@
+use chrono::{TimeDelta, Utc};
+use serenity::all::{
+    Context, CreateEmbed, CreateEmbedFooter, CreateMessage, DiscordJsonError, ErrorResponse,
+    GuildId, HttpError, RoleId, Timestamp, User,
+};
+use sqlx::PgPool;
+
+use crate::sqlx_lib::GuildTable;
+use crate::{Error, Result};
+
+use super::escalation::{format_duration, EscalationAction};
+use super::mod_log::{post_mod_log, ModLogEntry};
+use super::scheduled::{expires_at, ScheduledActionKind, ScheduledActionsTable};
+use super::{InfractionKind, InfractionRow};
+
+pub const NO_REASON: &str = "No reason provided.";
+
+/// A moderation action taken against a user. Every sanction is recorded as an infraction of the
+/// matching kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sanction {
+    Warn,
+    /// A Discord timeout, which lasts at most 28 days.
+    Mute(TimeDelta),
+    /// The guild's mute role, removed by the scheduler once it expires.
+    RoleMute(TimeDelta),
+    Kick,
+    SoftBan,
+    Ban,
+    /// A ban lifted by the scheduler once it expires.
+    TempBan(TimeDelta),
+}
+
+impl Sanction {
+    pub fn kind(&self) -> InfractionKind {
+        match self {
+            Self::Warn => InfractionKind::Warn,
+            Self::Mute(_) | Self::RoleMute(_) => InfractionKind::Mute,
+            Self::Kick => InfractionKind::Kick,
+            Self::SoftBan => InfractionKind::SoftBan,
+            Self::Ban | Self::TempBan(_) => InfractionKind::Ban,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Warn => "Warn",
+            Self::Mute(_) => "Mute",
+            Self::RoleMute(_) => "Role Mute",
+            Self::Kick => "Kick",
+            Self::SoftBan => "Soft Ban",
+            Self::Ban => "Ban",
+            Self::TempBan(_) => "Temp Ban",
+        }
+    }
+
+    fn colour(&self) -> u32 {
+        match self {
+            Self::Warn => 0xFEE75C,
+            Self::Mute(_) | Self::RoleMute(_) => 0x5865F2,
+            Self::Kick | Self::SoftBan => 0xE67E22,
+            Self::Ban | Self::TempBan(_) => 0xED4245,
+        }
+    }
+
+    fn past_tense(&self) -> &'static str {
+        match self {
+            Self::Warn => "warned",
+            Self::Mute(_) | Self::RoleMute(_) => "muted",
+            Self::Kick => "kicked",
+            Self::SoftBan => "soft banned",
+            Self::Ban | Self::TempBan(_) => "banned",
+        }
+    }
+
+    fn user_message(&self, guild_name: &str, reason: &str) -> String {
+        let preposition = match self {
+            Self::Warn | Self::Mute(_) | Self::RoleMute(_) => "in",
+            Self::Kick | Self::SoftBan | Self::Ban | Self::TempBan(_) => "from",
+        };
+
+        let mut desc = format!(
+            "You have been {} {} {}",
+            self.past_tense(),
+            preposition,
+            guild_name
+        );
+
+        if let Some(duration) = self.duration() {
+            desc.push_str(&format!(" for {}", format_duration(duration)));
+        }
+
+        desc.push('.');
+
+        if reason != NO_REASON {
+            desc.push_str(&format!("\nReason: {}", reason));
+        }
+
+        desc
+    }
+
+    fn duration(&self) -> Option<TimeDelta> {
+        match self {
+            Self::Mute(duration) | Self::RoleMute(duration) | Self::TempBan(duration) => {
+                Some(*duration)
+            }
+            Self::Warn | Self::Kick | Self::SoftBan | Self::Ban => None,
+        }
+    }
+
+    async fn enforce(
+        &self,
+        ctx: &Context,
+        guild_id: GuildId,
+        user: &User,
+        reason: &str,
+        mute_role: Option<RoleId>,
+    ) -> Result<()> {
+        match self {
+            Self::Warn => {}
+            Self::Mute(duration) => {
+                let mut member = guild_id
+                    .member(ctx, user.id)
+                    .await
+                    .map_err(|_| Error::MemberNotFound)?;
+
+                let timestamp = (Utc::now() + *duration).timestamp();
+                let until = Timestamp::from_unix_timestamp(timestamp)
+                    .map_err(|_| Error::InvalidDuration)?;
+
+                member
+                    .disable_communication_until_datetime(ctx, until)
+                    .await?;
+            }
+            Self::RoleMute(_) => {
+                let role_id = mute_role.expect("Role mutes always resolve a mute role");
+
+                ctx.http
+                    .add_member_role(guild_id, user.id, role_id, Some(reason))
+                    .await?;
+            }
+            Self::Kick => {
+                guild_id.kick_with_reason(ctx, user.id, reason).await?;
+            }
+            Self::SoftBan => {
+                guild_id.ban_with_reason(ctx, user.id, 1, reason).await?;
+                guild_id.unban(ctx, user.id).await?;
+            }
+            Self::Ban | Self::TempBan(_) => {
+                guild_id.ban_with_reason(ctx, user.id, 1, reason).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<EscalationAction> for Sanction {
+    fn from(action: EscalationAction) -> Self {
+        match action {
+            EscalationAction::Warn => Self::Warn,
+            EscalationAction::Mute(duration) => Self::Mute(duration),
+            EscalationAction::Ban => Self::Ban,
+        }
+    }
+}
+
+async fn send_user_message(
+    ctx: &Context,
+    user: &User,
+    sanction: Sanction,
+    desc: impl Into<String>,
+) -> Result<()> {
+    let title = match sanction.kind() {
+        InfractionKind::Warn => "You have been warned",
+        InfractionKind::Mute => "You have been muted",
+        InfractionKind::Kick => "You have been kicked",
+        InfractionKind::SoftBan => "You have been soft banned",
+        InfractionKind::Ban => "You have been banned",
+    };
+
+    let embed = CreateEmbed::new().title(title).description(desc);
+
+    match user
+        .direct_message(ctx, CreateMessage::new().embed(embed))
+        .await
+    {
+        // 50007: Cannot send messages to this user
+        Err(serenity::Error::Http(HttpError::UnsuccessfulRequest(ErrorResponse {
+            error: DiscordJsonError { code: 50007, .. },
+            ..
+        }))) => {}
+        result => {
+            result?;
+        }
+    }
+
+    Ok(())
+}
+
+pub struct SanctionOutcome {
+    pub case_id: i32,
+    /// A summary of the sanction to show the moderator.
+    pub embed: CreateEmbed,
+}
+
+pub struct SanctionRequest<'a> {
+    pub guild_id: GuildId,
+    pub user: &'a User,
+    pub moderator: &'a User,
+    pub sanction: Sanction,
+    pub points: i32,
+    pub reason: &'a str,
+    /// Where the sanction was issued from, linked in the mod log.
+    pub jump_url: Option<String>,
+}
+
+/// Messages the user, applies the sanction, records it as an infraction and posts it to the mod
+/// log. The user is messaged first so they can still be reached after a kick or ban. Temporary
+/// sanctions are handed to the scheduler to be lifted.
+pub async fn apply_sanction(
+    ctx: &Context,
+    pool: &PgPool,
+    request: SanctionRequest<'_>,
+) -> Result<SanctionOutcome> {
+    let SanctionRequest {
+        guild_id,
+        user,
+        moderator,
+        sanction,
+        points,
+        reason,
+        jump_url,
+    } = request;
+
+    if matches!(
+        sanction,
+        Sanction::Mute(_) | Sanction::RoleMute(_) | Sanction::Kick
+    ) && guild_id.member(ctx, user.id).await.is_err()
+    {
+        return Err(Error::MemberNotFound);
+    }
+
+    let scheduled = match sanction {
+        Sanction::TempBan(duration) => Some((ScheduledActionKind::Unban, expires_at(duration)?)),
+        Sanction::RoleMute(duration) => {
+            let role_id = GuildTable::get_mute_role(pool, guild_id)
+                .await?
+                .ok_or(Error::MuteRoleNotSet)?;
+
+            Some((
+                ScheduledActionKind::RemoveRole(role_id),
+                expires_at(duration)?,
+            ))
+        }
+        _ => None,
+    };
+
+    let guild_name = guild_id.to_partial_guild(ctx).await?.name;
+
+    send_user_message(
+        ctx,
+        user,
+        sanction,
+        sanction.user_message(&guild_name, reason),
+    )
+    .await?;
+
+    let mute_role = scheduled.and_then(|(kind, _)| kind.role_id());
+    // Nothing is recorded for a sanction Discord refused.
+    sanction
+        .enforce(ctx, guild_id, user, reason, mute_role)
+        .await?;
+
+    if let Some((kind, execute_at)) = scheduled {
+        ScheduledActionsTable::insert(pool, guild_id, user.id, kind, execute_at, moderator).await?;
+    }
+
+    let case_id = InfractionRow::new(
+        user.id,
+        &user.name,
+        guild_id,
+        sanction.kind(),
+        moderator,
+        points,
+        reason,
+    )?
+    .create(pool)
+    .await?;
+
+    post_mod_log(
+        ctx,
+        pool,
+        guild_id,
+        &ModLogEntry {
+            action: sanction.label(),
+            case_id: Some(case_id),
+            target: user.id,
+            moderator: Some(moderator.id),
+            reason: Some(reason),
+            duration: sanction.duration(),
+            jump_url,
+            colour: sanction.colour(),
+        },
+    )
+    .await?;
+
+    let mut embed = CreateEmbed::new()
+        .title(format!("{} has been {}", user.name, sanction.past_tense()))
+        .footer(CreateEmbedFooter::new(format!("Case #{}", case_id)));
+    if reason != NO_REASON {
+        embed = embed.description(reason);
+    }
+
+    Ok(SanctionOutcome { case_id, embed })
+}
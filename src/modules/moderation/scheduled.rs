@@ -0,0 +1,531 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use serenity::all::{
+    ChannelId, CommandInteraction, CommandOptionType, Context, CreateCommand, CreateCommandOption,
+    CreateEmbed, DiscordJsonError, EditInteractionResponse, ErrorResponse, GuildId, HttpError,
+    Mentionable, Permissions, Ready, ResolvedOption, ResolvedValue, RoleId, User, UserId,
+};
+use sqlx::{PgPool, Postgres};
+use zayden_core::{parse_options, SlashCommand};
+
+use crate::handler::OnReady;
+use crate::sqlx_lib::{GuildTable, PostgresPool};
+use crate::{Error, Result};
+
+use super::lock::unlock;
+use super::mod_log::{post_mod_log, ModLogEntry};
+
+static RUNNER_STARTED: AtomicBool = AtomicBool::new(false);
+
+const RETRY_DELAY: TimeDelta = TimeDelta::minutes(5);
+
+/// What to undo once a temporary punishment or lock expires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduledActionKind {
+    Unban,
+    RemoveRole(RoleId),
+    /// `None` unlocks every channel locked by a server-wide lock.
+    Unlock(Option<ChannelId>),
+}
+
+impl ScheduledActionKind {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Unban => "unban",
+            Self::RemoveRole(_) => "remove_role",
+            Self::Unlock(_) => "unlock",
+        }
+    }
+
+    pub fn role_id(&self) -> Option<RoleId> {
+        match self {
+            Self::RemoveRole(role_id) => Some(*role_id),
+            Self::Unban | Self::Unlock(_) => None,
+        }
+    }
+
+    pub fn channel_id(&self) -> Option<ChannelId> {
+        match self {
+            Self::Unlock(channel_id) => *channel_id,
+            Self::Unban | Self::RemoveRole(_) => None,
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub struct ScheduledActionRow {
+    pub id: i32,
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub action: String,
+    pub role_id: Option<i64>,
+    pub execute_at: NaiveDateTime,
+    pub moderator_id: i64,
+    pub created_at: NaiveDateTime,
+    pub channel_id: Option<i64>,
+}
+
+impl ScheduledActionRow {
+    pub fn guild_id(&self) -> GuildId {
+        GuildId::new(self.guild_id as u64)
+    }
+
+    pub fn user_id(&self) -> UserId {
+        UserId::new(self.user_id as u64)
+    }
+
+    pub fn kind(&self) -> Option<ScheduledActionKind> {
+        match (self.action.as_str(), self.role_id) {
+            ("unban", _) => Some(ScheduledActionKind::Unban),
+            ("remove_role", Some(role_id)) => {
+                Some(ScheduledActionKind::RemoveRole(RoleId::new(role_id as u64)))
+            }
+            ("unlock", channel_id) => Some(ScheduledActionKind::Unlock(
+                channel_id.map(|channel_id| ChannelId::new(channel_id as u64)),
+            )),
+            _ => None,
+        }
+    }
+
+    /// The member or channel the action applies to.
+    fn target(&self) -> String {
+        match self.kind() {
+            Some(ScheduledActionKind::Unlock(Some(channel_id))) => channel_id.mention().to_string(),
+            Some(ScheduledActionKind::Unlock(None)) => String::from("the server"),
+            _ => self.user_id().mention().to_string(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        let action = match self.kind() {
+            Some(ScheduledActionKind::Unban) => String::from("Unban"),
+            Some(ScheduledActionKind::RemoveRole(role_id)) => {
+                format!("Remove {}", role_id.mention())
+            }
+            Some(ScheduledActionKind::Unlock(_)) => String::from("Unlock"),
+            None => self.action.clone(),
+        };
+
+        format!(
+            "**#{}** {} {} <t:{}:R>",
+            self.id,
+            action,
+            self.target(),
+            self.execute_at.and_utc().timestamp()
+        )
+    }
+
+    /// Lifts the punishment and logs it. Members who already left or were unbanned by hand are
+    /// skipped, as are channels that were unlocked by hand. Any other Discord error is returned so
+    /// the action can be retried.
+    async fn execute(&self, ctx: &Context, pool: &PgPool) -> Result<()> {
+        let guild_id = self.guild_id();
+        let user_id = self.user_id();
+
+        let action = match self.kind() {
+            Some(ScheduledActionKind::Unban) => {
+                ignore_already_lifted(guild_id.unban(ctx, user_id).await)?;
+                "Unban"
+            }
+            Some(ScheduledActionKind::RemoveRole(role_id)) => {
+                ignore_already_lifted(
+                    ctx.http
+                        .remove_member_role(
+                            guild_id,
+                            user_id,
+                            role_id,
+                            Some("Temporary mute expired"),
+                        )
+                        .await,
+                )?;
+                "Unmute"
+            }
+            Some(ScheduledActionKind::Unlock(channel_id)) => {
+                unlock(ctx, pool, guild_id, channel_id, None, "Lock expired").await?;
+                return Ok(());
+            }
+            None => return Ok(()),
+        };
+
+        post_mod_log(
+            ctx,
+            pool,
+            guild_id,
+            &ModLogEntry {
+                action,
+                case_id: None,
+                target: user_id,
+                moderator: None,
+                reason: Some("Temporary punishment lifted"),
+                duration: None,
+                jump_url: None,
+                colour: 0x57F287,
+            },
+        )
+        .await
+    }
+}
+
+/// Unknown Member, Unknown Role and Unknown Ban mean there's nothing left to lift.
+fn ignore_already_lifted(result: serenity::Result<()>) -> Result<()> {
+    match result {
+        Err(serenity::Error::Http(HttpError::UnsuccessfulRequest(ErrorResponse {
+            error:
+                DiscordJsonError {
+                    code: 10007 | 10011 | 10026,
+                    ..
+                },
+            ..
+        }))) => Ok(()),
+        result => result.map_err(Error::from),
+    }
+}
+
+pub struct ScheduledActionsTable;
+
+impl ScheduledActionsTable {
+    pub async fn insert(
+        pool: &PgPool,
+        guild_id: GuildId,
+        user_id: UserId,
+        kind: ScheduledActionKind,
+        execute_at: NaiveDateTime,
+        moderator: &User,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO scheduled_actions (guild_id, user_id, action, role_id, channel_id, execute_at, moderator_id) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            guild_id.get() as i64,
+            user_id.get() as i64,
+            kind.name(),
+            kind.role_id().map(|role_id| role_id.get() as i64),
+            kind.channel_id().map(|channel_id| channel_id.get() as i64),
+            execute_at,
+            moderator.id.get() as i64
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+
+    pub async fn get_guild_rows(
+        pool: &PgPool,
+        guild_id: GuildId,
+        user_id: Option<UserId>,
+    ) -> Result<Vec<ScheduledActionRow>> {
+        let rows = sqlx::query_as!(
+            ScheduledActionRow,
+            "SELECT * FROM scheduled_actions WHERE guild_id = $1 AND ($2::BIGINT IS NULL OR user_id = $2) ORDER BY execute_at",
+            guild_id.get() as i64,
+            user_id.map(|user_id| user_id.get() as i64)
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        Ok(rows)
+    }
+
+    pub async fn get_due(pool: &PgPool, now: NaiveDateTime) -> Result<Vec<ScheduledActionRow>> {
+        let rows = sqlx::query_as!(
+            ScheduledActionRow,
+            "SELECT * FROM scheduled_actions WHERE execute_at <= $1 ORDER BY execute_at",
+            now
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        Ok(rows)
+    }
+
+    pub async fn postpone(pool: &PgPool, id: i32, execute_at: NaiveDateTime) -> Result<()> {
+        sqlx::query!(
+            "UPDATE scheduled_actions SET execute_at = $2 WHERE id = $1",
+            id,
+            execute_at
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+
+    /// Removes the action, returning it if it was still pending.
+    pub async fn get(
+        pool: &PgPool,
+        guild_id: GuildId,
+        id: i32,
+    ) -> Result<Option<ScheduledActionRow>> {
+        let row = sqlx::query_as!(
+            ScheduledActionRow,
+            "SELECT * FROM scheduled_actions WHERE guild_id = $1 AND id = $2",
+            guild_id.get() as i64,
+            id
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        Ok(row)
+    }
+
+    pub async fn delete(
+        pool: &PgPool,
+        guild_id: GuildId,
+        id: i32,
+    ) -> Result<Option<ScheduledActionRow>> {
+        let row = sqlx::query_as!(
+            ScheduledActionRow,
+            "DELETE FROM scheduled_actions WHERE guild_id = $1 AND id = $2 RETURNING *",
+            guild_id.get() as i64,
+            id
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        Ok(row)
+    }
+
+    /// Cancels pending automatic unlocks of the channel, or of the server-wide lock when
+    /// `channel_id` is `None`.
+    pub async fn delete_unlocks(
+        pool: &PgPool,
+        guild_id: GuildId,
+        channel_id: Option<ChannelId>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM scheduled_actions WHERE guild_id = $1 AND action = 'unlock' AND channel_id IS NOT DISTINCT FROM $2",
+            guild_id.get() as i64,
+            channel_id.map(|channel_id| channel_id.get() as i64)
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+}
+
+impl GuildTable {
+    pub async fn get_mute_role(pool: &PgPool, id: GuildId) -> Result<Option<RoleId>> {
+        let row = sqlx::query!(
+            "SELECT mute_role_id FROM guilds WHERE id = $1",
+            id.get() as i64
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        Ok(row
+            .and_then(|row| row.mute_role_id)
+            .map(|role_id| RoleId::new(role_id as u64)))
+    }
+
+    pub async fn save_mute_role(pool: &PgPool, id: GuildId, role_id: RoleId) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO guilds (id, mute_role_id) VALUES ($1, $2)
+             ON CONFLICT (id) DO UPDATE SET mute_role_id = $2",
+            id.get() as i64,
+            role_id.get() as i64
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Ok(())
+    }
+}
+
+/// When a punishment of `duration` starting now should be lifted.
+pub fn expires_at(duration: TimeDelta) -> Result<NaiveDateTime> {
+    Utc::now()
+        .naive_utc()
+        .checked_add_signed(duration)
+        .ok_or(Error::InvalidDuration)
+}
+
+/// Lifts expired punishments every 30 seconds. Pending actions live in the database, so anything
+/// that expired while the bot was offline runs on the first tick after a restart.
+pub struct ScheduledActions;
+
+#[async_trait]
+impl OnReady for ScheduledActions {
+    async fn on_ready(ctx: Context, _ready: Ready) -> Result<()> {
+        if RUNNER_STARTED.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let pool = PostgresPool::get(&ctx).await;
+
+        loop {
+            let due = match ScheduledActionsTable::get_due(&pool, Utc::now().naive_utc()).await {
+                Ok(due) => due,
+                Err(e) => {
+                    eprintln!("Error loading scheduled actions: {:?}", e);
+                    Vec::new()
+                }
+            };
+
+            for action in due {
+                if let Err(e) = lift(&ctx, &pool, &action).await {
+                    eprintln!("Error running scheduled action #{}: {:?}", action.id, e);
+                }
+            }
+
+            tokio::time::sleep(Duration::from_secs(30)).await;
+        }
+    }
+}
+
+/// Runs a due action, only removing it once it succeeded. Failed actions are retried later
+/// instead of being lost to a transient Discord error.
+async fn lift(ctx: &Context, pool: &PgPool, action: &ScheduledActionRow) -> Result<()> {
+    if let Err(e) = action.execute(ctx, pool).await {
+        ScheduledActionsTable::postpone(pool, action.id, Utc::now().naive_utc() + RETRY_DELAY)
+            .await?;
+
+        return Err(e);
+    }
+
+    ScheduledActionsTable::delete(pool, action.guild_id(), action.id).await?;
+
+    Ok(())
+}
+
+pub struct Scheduled;
+
+#[async_trait]
+impl SlashCommand<Error, Postgres> for Scheduled {
+    async fn run(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        mut options: Vec<ResolvedOption<'_>>,
+        pool: &PgPool,
+    ) -> Result<()> {
+        interaction.defer_ephemeral(ctx).await.unwrap();
+
+        let guild_id = interaction.guild_id.ok_or(Error::MissingGuildId)?;
+
+        let command = options.remove(0);
+        let ResolvedValue::SubCommand(options) = command.value else {
+            unreachable!("Subcommand is required");
+        };
+        let mut options = parse_options(options);
+
+        let response = match command.name {
+            "list" => {
+                let user_id = match options.remove("user") {
+                    Some(ResolvedValue::User(user, _)) => Some(user.id),
+                    _ => None,
+                };
+
+                let rows = ScheduledActionsTable::get_guild_rows(pool, guild_id, user_id).await?;
+
+                let description = if rows.is_empty() {
+                    String::from("There are no pending actions.")
+                } else {
+                    rows.iter()
+                        .take(25)
+                        .map(ScheduledActionRow::describe)
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                };
+
+                EditInteractionResponse::new().embed(
+                    CreateEmbed::new()
+                        .title("Scheduled Actions")
+                        .description(description),
+                )
+            }
+            "cancel" => {
+                let Some(ResolvedValue::Integer(id)) = options.remove("id") else {
+                    unreachable!("Id option is required");
+                };
+
+                let lift_now = matches!(
+                    options.remove("lift_now"),
+                    Some(ResolvedValue::Boolean(true))
+                );
+
+                let row = ScheduledActionsTable::get(pool, guild_id, id as i32)
+                    .await?
+                    .ok_or(Error::ScheduledActionNotFound)?;
+
+                // Like the runner, the row is only removed once the action has run, so a failed
+                // lift can be retried.
+                if lift_now {
+                    row.execute(ctx, pool).await?;
+                }
+
+                ScheduledActionsTable::delete(pool, guild_id, row.id).await?;
+
+                let unlock = matches!(row.kind(), Some(ScheduledActionKind::Unlock(_)));
+
+                let content = match (lift_now, unlock) {
+                    (true, true) => format!("Unlocked {}.", row.target()),
+                    (true, false) => format!("Lifted the punishment for {}.", row.target()),
+                    (false, true) => format!(
+                        "Cancelled action #{}. The lock on {} will no longer expire.",
+                        row.id,
+                        row.target()
+                    ),
+                    (false, false) => format!(
+                        "Cancelled action #{}. The punishment on {} will no longer expire.",
+                        row.id,
+                        row.target()
+                    ),
+                };
+
+                EditInteractionResponse::new().content(content)
+            }
+            _ => unreachable!("Unknown subcommand"),
+        };
+
+        interaction.edit_response(ctx, response).await.unwrap();
+
+        Ok(())
+    }
+
+    fn register(_ctx: &Context, _ready: &Ready) -> Result<CreateCommand> {
+        let command = CreateCommand::new("scheduled")
+            .description("Manage temporary bans and mutes")
+            .default_member_permissions(Permissions::BAN_MEMBERS)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "list",
+                    "List pending unbans and unmutes",
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::User,
+                    "user",
+                    "Only show actions for this user",
+                )),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "cancel",
+                    "Cancel a pending unban or unmute",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "id", "The action number")
+                        .required(true),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "lift_now",
+                    "Lift the punishment now instead of keeping it",
+                )),
+            );
+
+        Ok(command)
+    }
+}